@@ -0,0 +1,402 @@
+// src/activitywatch.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::gaps::{seconds, time};
+use crate::json;
+use crate::parser::TimeEntry;
+use jiff::civil::{Date, Time};
+use jiff::tz::TimeZone;
+use jiff::{SignedDuration, Timestamp, ToSpan};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// An event from an ActivityWatch bucket: when it started, how long it
+/// lasted, and its string data, like `status` for AFK events or `app` and
+/// `title` for window events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub start: Timestamp,
+    pub seconds: f64,
+    pub data: BTreeMap<String, String>,
+}
+
+/// A stretch of active time on a day that no clocked entry covers, with the
+/// app in the foreground for most of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub start: Time,
+    pub end: Time,
+    pub app: Option<String>,
+}
+
+impl std::fmt::Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.strftime("%H:%M"),
+            self.end.strftime("%H:%M")
+        )?;
+        match &self.app {
+            Some(app) => write!(f, " {}", app),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A day's time tracked in the notes against the time ActivityWatch saw
+/// the computer in use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    pub date: Date,
+    pub tracked: TimeDuration,
+    pub observed: TimeDuration,
+    pub unlogged: Vec<Block>,
+}
+
+impl Day {
+    /// The day as CSV fields: date, tracked and observed time in `unit`,
+    /// the observed time beyond what was tracked, and the unlogged blocks
+    /// joined by `; `.
+    pub fn record(&self, unit: DurationUnit) -> [String; 5] {
+        let format = |duration: &TimeDuration| match duration.total_seconds() {
+            0 => "0".to_string(),
+            _ => unit.format(duration),
+        };
+        let untracked = TimeDuration::from_seconds(
+            self.observed
+                .total_seconds()
+                .saturating_sub(self.tracked.total_seconds()),
+        );
+        [
+            self.date.to_string(),
+            format(&self.tracked),
+            format(&self.observed),
+            format(&untracked),
+            self.unlogged
+                .iter()
+                .map(|block| block.to_string())
+                .collect::<Vec<_>>()
+                .join("; "),
+        ]
+    }
+}
+
+/// The ids of the buckets of type `kind` (`afkstatus`, `currentwindow`) in
+/// a `/api/0/buckets/` response.
+pub fn bucket_ids(body: &str, kind: &str) -> Vec<String> {
+    let re_id = Regex::new(r#""id"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    let re_type = Regex::new(r#""type"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    objects(body)
+        .into_iter()
+        .filter(|bucket| {
+            re_type
+                .captures(bucket)
+                .is_some_and(|cap| json::unescape(&cap[1]) == kind)
+        })
+        .filter_map(|bucket| re_id.captures(bucket).map(|cap| json::unescape(&cap[1])))
+        .collect()
+}
+
+/// Reads the events of a `/api/0/buckets/<id>/events` response.
+pub fn parse_events(body: &str) -> Result<Vec<Event>, String> {
+    let re_timestamp = Regex::new(r#""timestamp"\s*:\s*"([^"]+)""#).unwrap();
+    let re_duration = Regex::new(r#""duration"\s*:\s*([0-9.eE+-]+)"#).unwrap();
+    let re_data = Regex::new(r#""data"\s*:\s*\{"#).unwrap();
+    let re_field = Regex::new(r#""((?:[^"\\]|\\.)*)"\s*:\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    let mut events = vec![];
+    for object in objects(body) {
+        // The data's fields could be named like the event's, so only look
+        // for the event's before the data starts.
+        let (event, data) = match re_data.find(object) {
+            Some(data) => (&object[..data.start()], &object[data.end()..]),
+            None => (object, ""),
+        };
+        let start = re_timestamp
+            .captures(event)
+            .ok_or_else(|| format!("event without a timestamp: {}", object))?[1]
+            .parse::<Timestamp>()
+            .map_err(|e| format!("invalid event timestamp: {}", e))?;
+        let seconds = re_duration
+            .captures(event)
+            .and_then(|cap| cap[1].parse::<f64>().ok())
+            .ok_or_else(|| format!("event without a duration: {}", object))?;
+        events.push(Event {
+            start,
+            seconds,
+            data: re_field
+                .captures_iter(data)
+                .map(|cap| (json::unescape(&cap[1]), json::unescape(&cap[2])))
+                .collect(),
+        });
+    }
+    Ok(events)
+}
+
+/// Compares the tracked time of `entries` with the time the `afk` events
+/// say the computer was in use, for each day from `since` to `until` with
+/// either. Active stretches longer than `min_block` that no clocked entry
+/// (one with a `timeStarted` time) covers are unlogged, named after the
+/// app the `windows` events had in front longest. Days with time tracked
+/// only without clock times have no unlogged blocks, as their entries
+/// can't be placed in the day.
+pub fn compare<'a>(
+    entries: impl IntoIterator<Item = &'a TimeEntry>,
+    afk: &[Event],
+    windows: &[Event],
+    tz: &TimeZone,
+    since: Date,
+    until: Date,
+    min_block: &TimeDuration,
+) -> Vec<Day> {
+    let mut tracked: BTreeMap<Date, TimeDuration> = BTreeMap::new();
+    let mut sessions: BTreeMap<Date, Vec<(i64, i64)>> = BTreeMap::new();
+    for entry in entries {
+        let Some(date) = entry.date else {
+            continue;
+        };
+        *tracked.entry(date).or_default() += &entry.duration;
+        if let Some(started) = entry.started {
+            let start = seconds(started);
+            let end = start.saturating_add(entry.duration.total_seconds().min(86400) as i64);
+            sessions
+                .entry(date)
+                .or_default()
+                .push((start, end.min(86400)));
+        }
+    }
+    let active = merge(local_spans(
+        afk.iter()
+            .filter(|event| event.data.get("status").is_some_and(|s| s == "not-afk")),
+        tz,
+    ));
+    let mut apps: BTreeMap<Date, Vec<(i64, i64, &str)>> = BTreeMap::new();
+    for event in windows {
+        let Some(app) = event.data.get("app") else {
+            continue;
+        };
+        for (date, spans) in local_spans([event], tz) {
+            apps.entry(date).or_default().extend(
+                spans
+                    .into_iter()
+                    .map(|(start, end)| (start, end, app.as_str())),
+            );
+        }
+    }
+
+    let mut days = vec![];
+    let mut date = since;
+    while date <= until {
+        let tracked = tracked.get(&date).cloned().unwrap_or_default();
+        let spans = active.get(&date).map(Vec::as_slice).unwrap_or_default();
+        let observed: i64 = spans.iter().map(|(start, end)| end - start).sum();
+        if tracked.total_seconds() > 0 || observed > 0 {
+            let sessions = sessions.get(&date);
+            let unlogged = match sessions {
+                None if tracked.total_seconds() > 0 => vec![],
+                _ => uncovered(spans, sessions.map(Vec::as_slice).unwrap_or_default())
+                    .into_iter()
+                    .filter(|(start, end)| end - start > min_block.total_seconds() as i64)
+                    .map(|(start, end)| Block {
+                        start: time(start),
+                        end: time(end.min(86399)),
+                        app: foreground(apps.get(&date), start, end),
+                    })
+                    .collect(),
+            };
+            days.push(Day {
+                date,
+                tracked,
+                observed: TimeDuration::from_seconds(observed as u64),
+                unlogged,
+            });
+        }
+        date = date.checked_add(1.day()).unwrap();
+    }
+    days
+}
+
+/// The events as spans of seconds into each local day, split at midnight.
+fn local_spans<'a>(
+    events: impl IntoIterator<Item = &'a Event>,
+    tz: &TimeZone,
+) -> BTreeMap<Date, Vec<(i64, i64)>> {
+    let mut days: BTreeMap<Date, Vec<(i64, i64)>> = BTreeMap::new();
+    for event in events {
+        let Ok(end) = event
+            .start
+            .checked_add(SignedDuration::from_secs_f64(event.seconds.max(0.0)))
+        else {
+            continue;
+        };
+        let mut start = tz.to_datetime(event.start);
+        let end = tz.to_datetime(end);
+        while start.date() < end.date() {
+            days.entry(start.date())
+                .or_default()
+                .push((seconds(start.time()), 86400));
+            start = start
+                .date()
+                .tomorrow()
+                .unwrap()
+                .to_datetime(Time::midnight());
+        }
+        if start < end {
+            days.entry(start.date())
+                .or_default()
+                .push((seconds(start.time()), seconds(end.time())));
+        }
+    }
+    days
+}
+
+/// Sorts each day's spans and joins the overlapping ones.
+fn merge(days: BTreeMap<Date, Vec<(i64, i64)>>) -> BTreeMap<Date, Vec<(i64, i64)>> {
+    days.into_iter()
+        .map(|(date, mut spans)| {
+            spans.sort_unstable();
+            let mut merged: Vec<(i64, i64)> = vec![];
+            for (start, end) in spans {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            (date, merged)
+        })
+        .collect()
+}
+
+/// The parts of the sorted, disjoint `spans` that no session covers.
+fn uncovered(spans: &[(i64, i64)], sessions: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut parts = vec![];
+    for &(start, end) in spans {
+        let mut from = start;
+        let mut covering: Vec<&(i64, i64)> = sessions
+            .iter()
+            .filter(|(s, e)| *s < end && *e > start)
+            .collect();
+        covering.sort_unstable();
+        for (s, e) in covering {
+            if *s > from {
+                parts.push((from, *s));
+            }
+            from = from.max(*e);
+        }
+        if from < end {
+            parts.push((from, end));
+        }
+    }
+    parts
+}
+
+/// The app in front longest between `start` and `end`.
+fn foreground(apps: Option<&Vec<(i64, i64, &str)>>, start: i64, end: i64) -> Option<String> {
+    let mut totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for &(s, e, app) in apps.into_iter().flatten() {
+        let overlap = e.min(end) - s.max(start);
+        if overlap > 0 {
+            *totals.entry(app).or_default() += overlap;
+        }
+    }
+    totals
+        .into_iter()
+        .max_by_key(|(app, seconds)| (*seconds, std::cmp::Reverse(*app)))
+        .map(|(app, _)| app.to_string())
+}
+
+/// The objects nested directly in the JSON array or object `body`.
+fn objects(body: &str) -> Vec<&str> {
+    let mut objects = vec![];
+    let (mut depth, mut start) = (0, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for (index, c) in body.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                depth += 1;
+                if depth == 2 && c == '{' {
+                    start = index;
+                }
+            }
+            '}' | ']' => {
+                if depth == 2 && c == '}' {
+                    objects.push(&body[start..=index]);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    objects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_compare() {
+        let buckets = r#"{
+            "aw-watcher-afk_laptop": {"id": "aw-watcher-afk_laptop", "type": "afkstatus"},
+            "aw-watcher-window_laptop": {"id": "aw-watcher-window_laptop", "type": "currentwindow"}
+        }"#;
+        assert_eq!(
+            vec!["aw-watcher-afk_laptop"],
+            bucket_ids(buckets, "afkstatus")
+        );
+
+        let afk = parse_events(
+            r#"[
+                {"id": 3, "timestamp": "2025-01-03T23:30:00+00:00", "duration": 3600.0, "data": {"status": "not-afk"}},
+                {"id": 2, "timestamp": "2025-01-02T12:00:00.000000+00:00", "duration": 1800.0, "data": {"status": "afk"}},
+                {"id": 1, "timestamp": "2025-01-02T09:00:00.000000+00:00", "duration": 10800, "data": {"status": "not-afk"}}
+            ]"#,
+        )
+        .unwrap();
+        let windows = parse_events(
+            r#"[{"timestamp": "2025-01-02T11:00:00Z", "duration": 3600, "data": {"app": "firefox", "title": "Docs {draft} \"v2\""}}]"#,
+        )
+        .unwrap();
+        assert_eq!("Docs {draft} \"v2\"", windows[0].data["title"]);
+
+        let entries = [TimeEntry {
+            duration: parse_duration("1h30m"),
+            started: Some("09:00".parse().unwrap()),
+            date: Some(date(2025, 1, 2)),
+            ..Default::default()
+        }];
+        let days = compare(
+            &entries,
+            &afk,
+            &windows,
+            &TimeZone::UTC,
+            date(2025, 1, 2),
+            date(2025, 1, 4),
+            &parse_duration("15m"),
+        );
+        let records: Vec<[String; 5]> = days
+            .iter()
+            .map(|day| day.record(DurationUnit::Compact))
+            .collect();
+        assert_eq!(
+            vec![
+                ["2025-01-02", "1h30m", "3h", "1h30m", "10:30-12:00 firefox"],
+                ["2025-01-03", "0", "30m", "30m", "23:30-23:59"],
+                ["2025-01-04", "0", "30m", "30m", "00:00-00:30"],
+            ],
+            records
+        );
+    }
+}
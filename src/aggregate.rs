@@ -0,0 +1,85 @@
+// src/aggregate.rs
+
+use crate::duration::TimeDuration;
+use crate::parser::TimeEntry;
+use ahash::{AHashMap, AHashSet};
+
+/// Totals for one set of tags in `--accumulate` mode.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Aggregate {
+    pub duration: TimeDuration,
+    pub entries: usize,
+    /// Files the entries came from, each listed once in the order first
+    /// seen.
+    pub files: Vec<String>,
+}
+
+/// Totals entries, given with their file, per comma-joined tag set. Rows
+/// are expected grouped by file, as scans produce them, so most files are
+/// the last one listed for their tags and need no lookup. The result is
+/// sorted by tags.
+pub fn accumulate<'a>(
+    rows: impl IntoIterator<Item = (&'a TimeEntry, &'a str)>,
+) -> Vec<(&'a str, Aggregate)> {
+    let rows = rows.into_iter();
+    // Most runs have far fewer tag sets than entries; this avoids the early
+    // rehashes without reserving a slot per entry.
+    // Each total keeps the files it has listed, so a tag set spread over
+    // many files is still checked in constant time.
+    let mut totals: AHashMap<&str, (Aggregate, AHashSet<&str>)> =
+        AHashMap::with_capacity(rows.size_hint().0 / 4);
+    for (entry, file) in rows {
+        let (total, seen) = totals.entry(entry.tags.as_str()).or_default();
+        total.duration += &entry.duration;
+        total.entries += 1;
+        if total.files.last().is_none_or(|last| last != file) && seen.insert(file) {
+            total.files.push(file.to_string());
+        }
+    }
+    let mut totals: Vec<(&str, Aggregate)> = totals
+        .into_iter()
+        .map(|(tags, (total, _))| (tags, total))
+        .collect();
+    totals.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+
+    #[test]
+    fn test_accumulate() {
+        let entries = [
+            TimeEntry::test("#dev", "1h"),
+            TimeEntry::test("#ops", "30m"),
+            TimeEntry::test("#dev", "2h"),
+            TimeEntry::test("#dev", "15m"),
+            TimeEntry::test("#dev", "45m"),
+        ];
+        let files = ["b.md", "b.md", "b.md", "a.md", "b.md"];
+        let totals = accumulate(entries.iter().zip(files));
+        assert_eq!(
+            vec![
+                (
+                    "#dev",
+                    Aggregate {
+                        duration: parse_duration("4h"),
+                        entries: 4,
+                        files: vec!["b.md".to_string(), "a.md".to_string()],
+                    }
+                ),
+                (
+                    "#ops",
+                    Aggregate {
+                        duration: parse_duration("30m"),
+                        entries: 1,
+                        files: vec!["b.md".to_string()],
+                    }
+                ),
+            ],
+            totals
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// src/atomic.rs
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Numbers the temporary files, so two written to the same path at once
+/// don't share one.
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// A file written under a temporary name beside its path, replacing the
+/// file at the path only once committed, so whatever reads it sees the
+/// old content or the new, never half of it. Dropped without being
+/// committed, the temporary file is removed and the path left alone.
+///
+/// A path that is a link replaces the file it links to. One naming a pipe
+/// or device, which can't be replaced, is written to directly.
+pub struct AtomicFile {
+    file: File,
+    temp: Option<PathBuf>,
+    path: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let failed =
+            |e: &dyn std::fmt::Display| format!("failed to create {}: {}", path.display(), e);
+        let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let metadata = fs::metadata(&target).ok();
+        if metadata.as_ref().is_some_and(|m| !m.is_file()) {
+            let file = File::create(&target).map_err(|e| failed(&e))?;
+            return Ok(AtomicFile {
+                file,
+                temp: None,
+                path: target,
+                committed: false,
+            });
+        }
+        let name = target
+            .file_name()
+            .ok_or_else(|| failed(&"not a file name"))?;
+        let temp = target.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            name.to_string_lossy(),
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::create(&temp).map_err(|e| failed(&e))?;
+        // A replaced file keeps its permissions.
+        if let Some(metadata) = metadata {
+            file.set_permissions(metadata.permissions())?;
+        }
+        Ok(AtomicFile {
+            file,
+            temp: Some(temp),
+            path: target,
+            committed: false,
+        })
+    }
+
+    /// Replaces the file at the path with what was written, once it's on
+    /// disk, so a crash can't leave the path empty.
+    pub fn commit(mut self) -> Result<(), Box<dyn Error>> {
+        let failed = |e: std::io::Error| format!("failed to write {}: {}", self.path.display(), e);
+        self.file.flush().map_err(failed)?;
+        if let Some(temp) = &self.temp {
+            self.file.sync_all().map_err(failed)?;
+            fs::rename(temp, &self.path).map_err(failed)?;
+        }
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if let (false, Some(temp)) = (self.committed, &self.temp) {
+            let _ = fs::remove_file(temp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atomic_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.csv");
+        fs::write(&path, "old\n").unwrap();
+        let files = || fs::read_dir(dir.path()).unwrap().count();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        let other = AtomicFile::create(&path).unwrap();
+        assert_eq!(3, files());
+        drop(other);
+        writeln!(file, "new").unwrap();
+        assert_eq!("old\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(2, files());
+        file.commit().unwrap();
+        assert_eq!("new\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(1, files());
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        writeln!(file, "partial").unwrap();
+        drop(file);
+        assert_eq!("new\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(1, files());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_file_special_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("shared.csv");
+        fs::write(&target, "old\n").unwrap();
+        let link = dir.path().join("link.csv");
+        std::os::unix::fs::symlink("shared.csv", &link).unwrap();
+        let mut file = AtomicFile::create(&link).unwrap();
+        writeln!(file, "new").unwrap();
+        file.commit().unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!("new\n", fs::read_to_string(&target).unwrap());
+
+        let fifo = dir.path().join("fifo");
+        let made = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(made.success());
+        let reader = {
+            let fifo = fifo.clone();
+            std::thread::spawn(move || fs::read_to_string(fifo).unwrap())
+        };
+        let mut file = AtomicFile::create(&fifo).unwrap();
+        writeln!(file, "piped").unwrap();
+        file.commit().unwrap();
+        assert_eq!("piped\n", reader.join().unwrap());
+        assert_eq!(3, fs::read_dir(dir.path()).unwrap().count());
+    }
+}
@@ -0,0 +1,418 @@
+// src/billing.rs
+
+use crate::config::Overtime;
+use crate::duration::{DurationUnit, TimeDuration, format_decimal};
+use crate::json;
+use crate::parser::TimeEntry;
+use csv::Writer;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Billable time and amount for one rated tag. `overtime` is the part of
+/// `duration` billed at the tag's overtime multiplier.
+#[derive(Debug, Default, PartialEq)]
+pub struct BillingLine {
+    pub tag: String,
+    pub duration: TimeDuration,
+    pub overtime: TimeDuration,
+    pub rate: f64,
+    pub amount: f64,
+    pub currency: Option<String>,
+}
+
+/// What an entry's own `[rate: 120]` or `[cost: 80]` inline field bills it
+/// at instead of its tag's rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Price {
+    /// An hourly rate for the entry.
+    Rate(f64),
+    /// A flat amount for the entry, whatever its duration.
+    Cost(f64),
+}
+
+/// The entry's price override, `cost` winning over `rate`. Fields that
+/// aren't numbers are warned about and ignored.
+fn price(entry: &TimeEntry) -> Option<Price> {
+    [
+        ("cost", Price::Cost as fn(f64) -> Price),
+        ("rate", Price::Rate),
+    ]
+    .into_iter()
+    .find_map(|(name, price)| {
+        let value = entry.fields.get(name)?;
+        match value.trim().parse::<f64>() {
+            Ok(amount) if amount.is_finite() && amount >= 0.0 => Some(price(amount)),
+            _ => {
+                log::warn!(
+                    "line {}: ignoring {} '{}', not an amount",
+                    entry.line,
+                    name,
+                    value
+                );
+                None
+            }
+        }
+    })
+}
+
+/// Totals entries per rated tag. An entry carrying several rated tags is
+/// billed once, to the first of them in tag order. Overtime is counted per
+/// tag and day, so undated entries are always billed at the base rate.
+/// Entries with their own `rate` or `cost` field are billed at it instead,
+/// and left out of overtime.
+pub fn bill(
+    entries: &[TimeEntry],
+    rates: &BTreeMap<String, f64>,
+    overtime: &BTreeMap<String, Overtime>,
+) -> Vec<BillingLine> {
+    let mut days: BTreeMap<(&str, Option<Date>), u64> = BTreeMap::new();
+    let mut priced: BTreeMap<&str, (u64, f64)> = BTreeMap::new();
+    for entry in entries {
+        let mut rated = entry.tags.split(',').filter(|t| rates.contains_key(*t));
+        let Some(tag) = rated.next() else {
+            continue;
+        };
+        if rated.next().is_some() {
+            log::warn!(
+                "line {}: entry has several rated tags ({}), billing {}",
+                entry.line,
+                entry.tags,
+                tag
+            );
+        }
+        let seconds = entry.duration.total_seconds();
+        match price(entry) {
+            Some(price) => {
+                let (total, amount) = priced.entry(tag).or_default();
+                *total += seconds;
+                *amount += match price {
+                    Price::Rate(rate) => seconds as f64 / 3600.0 * rate,
+                    Price::Cost(cost) => cost,
+                };
+            }
+            None => *days.entry((tag, entry.date)).or_default() += seconds,
+        }
+    }
+
+    let mut lines: BTreeMap<&str, BillingLine> = BTreeMap::new();
+    for ((tag, date), seconds) in days {
+        let rate = rates[tag];
+        let (over, multiplier) = match (overtime.get(tag), date) {
+            (Some(tier), Some(_)) => (
+                seconds.saturating_sub((tier.after * 3600.0) as u64),
+                tier.multiplier,
+            ),
+            _ => (0, 1.0),
+        };
+        let line = lines.entry(tag).or_insert_with(|| BillingLine {
+            tag: tag.to_string(),
+            rate,
+            ..Default::default()
+        });
+        line.duration += TimeDuration::from_seconds(seconds);
+        line.overtime += TimeDuration::from_seconds(over);
+        line.amount += ((seconds - over) as f64 + over as f64 * multiplier) / 3600.0 * rate;
+    }
+    for (tag, (seconds, amount)) in priced {
+        let line = lines.entry(tag).or_insert_with(|| BillingLine {
+            tag: tag.to_string(),
+            rate: rates[tag],
+            ..Default::default()
+        });
+        line.duration += TimeDuration::from_seconds(seconds);
+        line.amount += amount;
+    }
+    lines.into_values().collect()
+}
+
+/// Which way `--round` moves a billed entry's duration to a multiple of the
+/// increment.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Rounding {
+    #[default]
+    Up,
+    Nearest,
+    Down,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Rounding::Up),
+            "nearest" => Ok(Rounding::Nearest),
+            "down" => Ok(Rounding::Down),
+            _ => Err(format!("unknown rounding '{}'", s)),
+        }
+    }
+}
+
+impl Rounding {
+    pub fn round(self, duration: &TimeDuration, increment: &TimeDuration) -> TimeDuration {
+        let (seconds, step) = (duration.total_seconds(), increment.total_seconds());
+        if step == 0 {
+            return duration.clone();
+        }
+        let steps = match self {
+            Rounding::Up => seconds.div_ceil(step),
+            Rounding::Nearest => (seconds + step / 2) / step,
+            Rounding::Down => seconds / step,
+        };
+        TimeDuration::from_seconds(steps.saturating_mul(step))
+    }
+}
+
+/// A billed entry's duration before and after rounding, for the audit
+/// ledger. `line` is 1-based.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub file: String,
+    pub line: usize,
+    pub tags: String,
+    pub raw: TimeDuration,
+    pub rounded: TimeDuration,
+}
+
+/// Rounds the entries, given with their file, that carry a rated tag, and
+/// returns what was changed for each of them, in order.
+pub fn round_entries(
+    entries: &mut [(TimeEntry, String)],
+    rates: &BTreeMap<String, f64>,
+    increment: &TimeDuration,
+    rounding: Rounding,
+) -> Vec<Adjustment> {
+    let mut adjustments = vec![];
+    for (entry, file) in entries {
+        if !entry.tags.split(',').any(|t| rates.contains_key(t)) {
+            continue;
+        }
+        let rounded = rounding.round(&entry.duration, increment);
+        adjustments.push(Adjustment {
+            file: file.clone(),
+            line: entry.line,
+            tags: entry.tags.clone(),
+            raw: entry.duration.clone(),
+            rounded: rounded.clone(),
+        });
+        entry.duration = rounded;
+    }
+    adjustments
+}
+
+/// Writes the rounding audit trail: each entry's raw and rounded duration,
+/// the difference, and the running total of differences.
+pub fn write_ledger<W: Write>(
+    adjustments: &[Adjustment],
+    writer: W,
+    unit: DurationUnit,
+) -> Result<(), Box<dyn Error>> {
+    let signed = |seconds: i128| {
+        let magnitude = unit.format(&TimeDuration::from_seconds(seconds.unsigned_abs() as u64));
+        match seconds {
+            0 => magnitude,
+            s if s < 0 => format!("-{}", magnitude),
+            _ => format!("+{}", magnitude),
+        }
+    };
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record([
+        "file",
+        "line",
+        "tags",
+        "raw",
+        "rounded",
+        "delta",
+        "cumulative_delta",
+    ])?;
+    let mut cumulative: i128 = 0;
+    for adjustment in adjustments {
+        let delta =
+            adjustment.rounded.total_seconds() as i128 - adjustment.raw.total_seconds() as i128;
+        cumulative += delta;
+        csv_writer.write_record(&[
+            adjustment.file.clone(),
+            adjustment.line.to_string(),
+            adjustment.tags.clone(),
+            unit.format(&adjustment.raw),
+            unit.format(&adjustment.rounded),
+            signed(delta),
+            signed(cumulative),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Converts rates and amounts to `currency`, given in units per unit of the
+/// configured rates' currency.
+pub fn convert(lines: &mut [BillingLine], currency: &str, exchange_rate: f64) {
+    for line in lines {
+        line.rate *= exchange_rate;
+        line.amount *= exchange_rate;
+        line.currency = Some(currency.to_string());
+    }
+}
+
+pub fn write_csv<W: Write>(
+    lines: &[BillingLine],
+    writer: W,
+    unit: DurationUnit,
+) -> Result<(), Box<dyn Error>> {
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record([
+        "tag", "duration", "hours", "overtime", "rate", "amount", "currency",
+    ])?;
+    for line in lines {
+        let overtime = if line.overtime.total_seconds() == 0 {
+            "0".to_string()
+        } else {
+            unit.format(&line.overtime)
+        };
+        csv_writer.write_record(&[
+            line.tag.clone(),
+            unit.format(&line.duration),
+            DurationUnit::Hours.format(&line.duration),
+            overtime,
+            format_decimal(line.rate),
+            format!("{:.2}", line.amount),
+            line.currency.clone().unwrap_or_default(),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+pub fn to_json(lines: &[BillingLine]) -> String {
+    let items: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            format!(
+                "{{\"tag\":{},\"seconds\":{},\"hours\":{},\"overtime_seconds\":{},\"rate\":{},\"amount\":{:.2},\"currency\":{}}}",
+                json::quote(&line.tag),
+                line.duration.total_seconds(),
+                DurationUnit::Hours.format(&line.duration),
+                line.overtime.total_seconds(),
+                format_decimal(line.rate),
+                line.amount,
+                line.currency
+                    .as_deref()
+                    .map_or("null".to_string(), json::quote)
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_bill_rated_tags() {
+        let rates = BTreeMap::from([("#acme".to_string(), 100.0), ("#globex".to_string(), 80.0)]);
+        let entries = vec![
+            TimeEntry::test("#acme", "1h30m"),
+            TimeEntry::test("#acme,#globex", "1h"),
+            TimeEntry::test("#globex", "30m"),
+            TimeEntry::test("#internal", "2h"),
+        ];
+        let lines = bill(&entries, &rates, &BTreeMap::new());
+        assert_eq!(2, lines.len());
+        assert_eq!("#acme", lines[0].tag);
+        assert_eq!(250.0, lines[0].amount);
+        assert_eq!(40.0, lines[1].amount);
+    }
+
+    #[test]
+    fn test_bill_overtime_per_day() {
+        let rates = BTreeMap::from([("#acme".to_string(), 100.0)]);
+        let overtime = BTreeMap::from([(
+            "#acme".to_string(),
+            Overtime {
+                after: 8.0,
+                multiplier: 1.5,
+            },
+        )]);
+        let dated = |duration, day| TimeEntry {
+            date: Some(date(2025, 1, day)),
+            ..TimeEntry::test("#acme", duration)
+        };
+        let entries = vec![
+            dated("6h", 1),
+            dated("4h", 1),
+            dated("8h", 2),
+            TimeEntry::test("#acme", "10h"),
+        ];
+        let mut lines = bill(&entries, &rates, &overtime);
+        assert_eq!(7200, lines[0].overtime.total_seconds());
+        // 26h at the base rate plus 2h at 1.5x.
+        assert_eq!(2900.0, lines[0].amount);
+
+        convert(&mut lines, "EUR", 0.5);
+        assert_eq!(1450.0, lines[0].amount);
+        assert_eq!(50.0, lines[0].rate);
+        assert_eq!(Some("EUR".to_string()), lines[0].currency);
+    }
+
+    #[test]
+    fn test_bill_entry_prices() {
+        let rates = BTreeMap::from([("#acme".to_string(), 100.0)]);
+        let overtime = BTreeMap::from([(
+            "#acme".to_string(),
+            Overtime {
+                after: 1.0,
+                multiplier: 2.0,
+            },
+        )]);
+        let priced = |duration, field: &str, value: &str| TimeEntry {
+            date: Some(date(2025, 1, 1)),
+            fields: BTreeMap::from([(field.to_string(), value.to_string())]),
+            ..TimeEntry::test("#acme", duration)
+        };
+        let entries = vec![
+            priced("1h", "client", "Acme"),
+            priced("2h", "rate", "150"),
+            priced("3h", "cost", "80"),
+            priced("30m", "rate", "rush"),
+        ];
+        let lines = bill(&entries, &rates, &overtime);
+        assert_eq!(parse_duration("6h30m"), lines[0].duration);
+        // 1h30m at the tag's rate with 30m of it overtime, 2h at 150, and 80.
+        assert_eq!(200.0 + 300.0 + 80.0, lines[0].amount);
+        assert_eq!(100.0, lines[0].rate);
+    }
+
+    #[test]
+    fn test_round_entries_ledger() {
+        let rates = BTreeMap::from([("#acme".to_string(), 100.0)]);
+        let mut entries = vec![
+            (TimeEntry::test("#acme", "50m"), "a.md".to_string()),
+            (TimeEntry::test("#internal", "10m"), "a.md".to_string()),
+            (TimeEntry::test("#acme", "1h5m"), "b.md".to_string()),
+        ];
+        let increment = parse_duration("15m");
+        let adjustments = round_entries(&mut entries, &rates, &increment, Rounding::Nearest);
+        assert_eq!(parse_duration("45m"), entries[0].0.duration);
+        assert_eq!(parse_duration("10m"), entries[1].0.duration);
+        assert_eq!(parse_duration("1h"), entries[2].0.duration);
+        assert_eq!(
+            parse_duration("1h15m"),
+            Rounding::Up.round(&parse_duration("1h1s"), &increment)
+        );
+
+        let mut output = vec![];
+        write_ledger(&adjustments, &mut output, DurationUnit::Compact).unwrap();
+        assert_eq!(
+            "file,line,tags,raw,rounded,delta,cumulative_delta\n\
+             a.md,0,#acme,50m,45m,-5m,-5m\n\
+             b.md,0,#acme,1h5m,1h,-5m,-10m\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
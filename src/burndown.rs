@@ -0,0 +1,155 @@
+// src/burndown.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::locale::Locale;
+use csv::Writer;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Write;
+
+const CHART_WIDTH: i64 = 40;
+
+/// One day of a burndown: estimates and actuals are cumulative up to and
+/// including `date`, and `remaining` goes negative once the work overruns.
+#[derive(Debug, PartialEq)]
+pub struct BurndownRow {
+    pub date: Date,
+    pub estimate: i64,
+    pub actual: i64,
+    pub remaining: i64,
+}
+
+/// Builds one row per calendar day between the first and last dated
+/// estimate or actual. Estimates are treated as scope: each one adds to the
+/// total on the day it was written.
+pub fn burndown(
+    estimates: &[(Date, TimeDuration)],
+    actuals: &[(Date, TimeDuration)],
+) -> Vec<BurndownRow> {
+    let mut days: BTreeMap<Date, (i64, i64)> = BTreeMap::new();
+    for (date, estimate) in estimates {
+        days.entry(*date).or_default().0 += estimate.total_seconds() as i64;
+    }
+    for (date, actual) in actuals {
+        days.entry(*date).or_default().1 += actual.total_seconds() as i64;
+    }
+
+    let (Some(first), Some(last)) = (days.keys().next(), days.keys().next_back()) else {
+        return vec![];
+    };
+    let mut rows = vec![];
+    let (mut estimate, mut actual) = (0, 0);
+    for date in first
+        .series(jiff::Span::new().days(1))
+        .take_while(|d| d <= last)
+    {
+        if let Some((day_estimate, day_actual)) = days.get(&date) {
+            estimate += day_estimate;
+            actual += day_actual;
+        }
+        rows.push(BurndownRow {
+            date,
+            estimate,
+            actual,
+            remaining: estimate - actual,
+        });
+    }
+    rows
+}
+
+pub fn write_csv<W: Write>(
+    rows: &[BurndownRow],
+    writer: W,
+    unit: DurationUnit,
+) -> Result<(), Box<dyn Error>> {
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["date", "estimate", "actual", "remaining"])?;
+    for row in rows {
+        csv_writer.write_record(&[
+            row.date.to_string(),
+            format_signed(row.estimate, unit),
+            format_signed(row.actual, unit),
+            format_signed(row.remaining, unit),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Renders the remaining estimate as a horizontal bar per day, scaled to the
+/// largest estimate in the series, with dates and numbers as `locale` writes
+/// them.
+pub fn write_chart<W: Write>(
+    rows: &[BurndownRow],
+    mut writer: W,
+    unit: DurationUnit,
+    locale: &Locale,
+) -> Result<(), Box<dyn Error>> {
+    let scale = rows.iter().map(|r| r.estimate).max().unwrap_or(0).max(1);
+    for row in rows {
+        let width = (row.remaining.max(0) * CHART_WIDTH / scale) as usize;
+        writeln!(
+            writer,
+            "{} {:<width$} {} remaining, {} actual",
+            locale.date(row.date),
+            "█".repeat(width),
+            locale.number(&format_signed(row.remaining, unit)),
+            locale.number(&format_signed(row.actual, unit)),
+            width = CHART_WIDTH as usize
+        )?;
+    }
+    Ok(())
+}
+
+fn format_signed(seconds: i64, unit: DurationUnit) -> String {
+    if seconds == 0 {
+        return "0".to_string();
+    }
+    let sign = if seconds < 0 { "-" } else { "" };
+    format!(
+        "{}{}",
+        sign,
+        unit.format(&TimeDuration::from_seconds(seconds.unsigned_abs()))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_burndown_fills_every_day() {
+        let estimates = vec![(date(2025, 1, 1), parse_duration("4h"))];
+        let actuals = vec![
+            (date(2025, 1, 1), parse_duration("1h")),
+            (date(2025, 1, 3), parse_duration("2h")),
+        ];
+        let rows = burndown(&estimates, &actuals);
+        let remaining: Vec<i64> = rows.iter().map(|r| r.remaining / 3600).collect();
+        assert_eq!(vec![3, 3, 1], remaining);
+        assert_eq!(date(2025, 1, 2), rows[1].date);
+    }
+
+    #[test]
+    fn test_burndown_overrun_goes_negative() {
+        let estimates = vec![(date(2025, 1, 1), parse_duration("1h"))];
+        let actuals = vec![(date(2025, 1, 1), parse_duration("1h30m"))];
+        let rows = burndown(&estimates, &actuals);
+        assert_eq!(
+            "-30m",
+            format_signed(rows[0].remaining, DurationUnit::Compact)
+        );
+        assert_eq!(
+            "-0.5",
+            format_signed(rows[0].remaining, DurationUnit::Hours)
+        );
+    }
+
+    #[test]
+    fn test_burndown_empty() {
+        assert!(burndown(&[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+// src/changed.rs
+
+use crate::git;
+use jiff::civil::{Date, DateTime, Time};
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, Zoned};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::Infallible;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The point `--changed-since` compares notes against: a time, or a git
+/// revision when the text isn't one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Since {
+    Time(Timestamp),
+    Revision(String),
+}
+
+impl FromStr for Since {
+    type Err = Infallible;
+
+    /// Reads an RFC 3339 timestamp, or a local `YYYY-MM-DD[THH:MM[:SS]]`;
+    /// anything else is a git revision like `HEAD~3` or `export-2025-01`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let local = |datetime: DateTime| {
+            datetime
+                .to_zoned(TimeZone::system())
+                .ok()
+                .map(|zoned: Zoned| zoned.timestamp())
+        };
+        let time = s
+            .parse::<Timestamp>()
+            .ok()
+            .or_else(|| s.parse::<DateTime>().ok().and_then(local))
+            .or_else(|| {
+                s.parse::<Date>()
+                    .ok()
+                    .and_then(|date| local(date.to_datetime(Time::midnight())))
+            });
+        Ok(match time {
+            Some(time) => Since::Time(time),
+            None => Since::Revision(s.to_string()),
+        })
+    }
+}
+
+/// Keeps the notes modified since `since`: by modification time, or by
+/// git when it is a revision, counting notes that differ from it in the
+/// working tree and untracked ones. Notes inside an archive count as
+/// modified when the archive is.
+pub fn retain_changed(files: &mut Vec<PathBuf>, since: &Since) -> Result<(), Box<dyn Error>> {
+    match since {
+        Since::Time(time) => {
+            let mut kept = vec![];
+            for file in files.drain(..) {
+                let modified = file
+                    .ancestors()
+                    .find_map(|path| fs::metadata(path).ok().filter(|m| m.is_file()))
+                    .ok_or_else(|| format!("cannot read {}", file.display()))?
+                    .modified()?;
+                if Timestamp::try_from(modified)? >= *time {
+                    kept.push(file);
+                }
+            }
+            *files = kept;
+        }
+        Since::Revision(revision) => {
+            // Changed paths per repository, keyed by its top level.
+            let mut repositories: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+            let mut kept = vec![];
+            for file in files.drain(..) {
+                let on_disk = file
+                    .ancestors()
+                    .find(|path| path.is_file())
+                    .unwrap_or(&file)
+                    .canonicalize()?;
+                let dir = on_disk.parent().unwrap_or(Path::new("."));
+                let top = PathBuf::from(git::run(dir, &["rev-parse", "--show-toplevel"])?.trim());
+                if !repositories.contains_key(&top) {
+                    let changed = changed_paths(&top, revision)?;
+                    repositories.insert(top.clone(), changed);
+                }
+                if repositories[&top].contains(&on_disk) {
+                    kept.push(file);
+                }
+            }
+            *files = kept;
+        }
+    }
+    Ok(())
+}
+
+fn changed_paths(top: &Path, revision: &str) -> Result<BTreeSet<PathBuf>, Box<dyn Error>> {
+    // Resolved first so a revision starting with `-` can't be read as an
+    // option to `git diff`.
+    let commit = git::run(
+        top,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            "--end-of-options",
+            &format!("{}^{{commit}}", revision),
+        ],
+    )
+    .map_err(|_| format!("'{}' is not a revision in {}", revision, top.display()))?;
+    // Listed NUL-terminated, since git quotes names that aren't ASCII.
+    let diff = git::run(top, &["diff", "-z", "--name-only", commit.trim(), "--"])?;
+    let untracked = git::run(top, &["ls-files", "-z", "--others", "--exclude-standard"])?;
+    Ok(diff
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| top.join(name).canonicalize().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(
+            Since::Time("2025-01-03T10:00:00Z".parse().unwrap()),
+            "2025-01-03T10:00:00Z".parse().unwrap()
+        );
+        assert!(matches!("2025-01-03".parse(), Ok(Since::Time(_))));
+        assert_eq!(
+            Since::Revision("HEAD~1".to_string()),
+            "HEAD~1".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_retain_changed_since_revision() {
+        let vault = tempfile::tempdir().unwrap();
+        let run = |args: &[&str]| {
+            git::run(
+                vault.path(),
+                &[
+                    &["-c", "user.name=t", "-c", "user.email=t@example.com"],
+                    args,
+                ]
+                .concat(),
+            )
+            .unwrap()
+        };
+        run(&["init", "-q"]);
+        let [old, edited, added] =
+            ["2025-01-01.md", "möte.md", "ny fil ö.md"].map(|name| vault.path().join(name));
+        fs::write(&old, "- [ ] A [timeTracked: 1h]").unwrap();
+        fs::write(&edited, "- [ ] B [timeTracked: 1h]").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-q", "-m", "notes"]);
+        fs::write(&edited, "- [ ] B [timeTracked: 2h]").unwrap();
+        fs::write(&added, "- [ ] C [timeTracked: 1h]").unwrap();
+
+        let mut files = vec![old, edited.clone(), added.clone()];
+        retain_changed(&mut files, &Since::Revision("HEAD".to_string())).unwrap();
+        assert_eq!(vec![edited.clone(), added.clone()], files);
+
+        let option = Since::Revision(format!("--output={}", vault.path().join("out").display()));
+        assert!(retain_changed(&mut files, &option).is_err());
+        assert!(!vault.path().join("out").exists());
+    }
+}
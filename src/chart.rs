@@ -0,0 +1,85 @@
+// src/chart.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::locale::Locale;
+use std::error::Error;
+use std::io::Write;
+
+/// Width of the longest bar, in terminal columns.
+const BAR_WIDTH: u64 = 40;
+/// Partial blocks by eighths of a column, for bars between whole columns.
+const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// A bar `eighths` eighths of a column long.
+fn bar(eighths: u64) -> String {
+    let mut bar = "█".repeat((eighths / 8) as usize);
+    bar.push_str(EIGHTHS[(eighths % 8) as usize]);
+    bar
+}
+
+/// Renders each total as a labelled horizontal bar scaled to the largest
+/// one, followed by the total in `unit` as `locale` writes it. Bars are drawn to an eighth of a
+/// column, and any time at all shows at least a sliver.
+pub fn write_bars<W: Write>(
+    totals: &[(String, TimeDuration)],
+    mut writer: W,
+    unit: DurationUnit,
+    locale: &Locale,
+) -> Result<(), Box<dyn Error>> {
+    let label_width = totals
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let scale = totals
+        .iter()
+        .map(|(_, total)| total.total_seconds())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for (label, total) in totals {
+        let seconds = total.total_seconds();
+        let eighths = (seconds * BAR_WIDTH * 8 + scale / 2) / scale;
+        let bar = bar(if seconds > 0 { eighths.max(1) } else { 0 });
+        writeln!(
+            writer,
+            "{:<label_width$} {:<bar_width$} {}",
+            label,
+            bar,
+            locale.duration(total, unit),
+            bar_width = BAR_WIDTH as usize
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+
+    #[test]
+    fn test_write_bars() {
+        let totals = vec![
+            ("#dev".to_string(), parse_duration("4h")),
+            ("#ops".to_string(), parse_duration("1h")),
+            ("#meetings".to_string(), parse_duration("1m")),
+        ];
+        let mut output = vec![];
+        write_bars(
+            &totals,
+            &mut output,
+            DurationUnit::Compact,
+            &Locale::default(),
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(format!("#dev      {} 4h", "█".repeat(40)), lines[0]);
+        assert_eq!(
+            format!("#ops      {}{} 1h", "█".repeat(10), " ".repeat(30)),
+            lines[1]
+        );
+        assert_eq!(format!("#meetings ▏{} 1m", " ".repeat(39)), lines[2]);
+    }
+}
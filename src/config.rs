@@ -0,0 +1,325 @@
+// src/config.rs
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Top-level keys are defaults for every run; `[profile.NAME]` tables
+/// override them when selected with `--profile NAME`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(flatten)]
+    pub defaults: Profile,
+    pub profile: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub directories: Vec<PathBuf>,
+    pub recursive: bool,
+    pub tags: Vec<String>,
+    pub rates: BTreeMap<String, f64>,
+    pub overtime: BTreeMap<String, Overtime>,
+    /// Currency that billing amounts are converted to.
+    pub currency: Option<String>,
+    /// Units of each currency per unit of the currency `rates` are in.
+    pub exchange_rates: BTreeMap<String, f64>,
+    pub format: Option<String>,
+    /// Identifier tag prefixes, like `#jira-`, mapped to a regex the rest
+    /// of the tag must match (empty for any).
+    pub identifiers: BTreeMap<String, String>,
+    /// Extra duration unit names, like `t` or `Std`, mapped to the built-in
+    /// unit (`h`, `m`, `s`, or `d`) they stand for.
+    pub units: BTreeMap<String, String>,
+    /// `second` or `minute`, the precision durations are kept at.
+    pub granularity: Option<String>,
+    /// How durations are rounded to the granularity: `up`, `nearest`, or
+    /// `down`.
+    pub granularity_rounding: Option<String>,
+    /// The frontmatter property marking a note archived, `archived` unless
+    /// set.
+    pub archived_key: Option<String>,
+    /// Read trailing `(2h)` durations as in `--legacy-durations`.
+    pub legacy_durations: bool,
+    /// Notes larger than this, like `10M`, are skipped.
+    pub max_file_size: Option<String>,
+    /// The locale of rendered reports, like `de-DE`, as in `--locale`.
+    pub locale: Option<String>,
+    /// Requests a second sent to web services, as in `--rate-limit`.
+    pub rate_limit: Option<f64>,
+    /// Names entry reports use for columns instead of their own, like
+    /// `duration = "Hours"`.
+    pub column_names: BTreeMap<String, String>,
+    /// Inline fields, like `client` for `[client: Acme]`, added to entry
+    /// reports as `field:NAME` columns unless `--columns` is given.
+    pub fields: Vec<String>,
+    /// The reports `bundle` writes, as `[[report]]` tables.
+    #[serde(rename = "report")]
+    pub reports: Vec<Report>,
+    /// Tag groups an entry may carry only one of, as `[[exclusive]]`
+    /// tables.
+    pub exclusive: Vec<Exclusive>,
+    /// Time budgets per tag, like `"#meetings" = "<6h/week"`, checked by
+    /// `targets`.
+    pub targets: BTreeMap<String, String>,
+    /// The first day of a sprint, `YYYY-MM-DD`, that sprints of
+    /// `sprint_length` follow each other from.
+    pub sprint_start: Option<String>,
+    /// How long sprints last, like `2w` or `10d`; two weeks unless set.
+    pub sprint_length: Option<String>,
+    /// Named sprints, as `[[sprint]]` tables, for days they cover.
+    #[serde(rename = "sprint")]
+    pub sprints: Vec<SprintRange>,
+}
+
+/// A sprint named explicitly, like `2025.03` or `Hardening`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SprintRange {
+    pub name: String,
+    /// Inclusive `YYYY-MM-DD` bounds.
+    pub start: String,
+    pub end: String,
+}
+
+/// Tags, like `#client-*`, that exclude each other on an entry.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Exclusive {
+    /// Tags where `*` stands for any text.
+    pub tags: Vec<String>,
+    /// Whether every entry needs exactly one of them, rather than at most
+    /// one.
+    pub required: bool,
+}
+
+/// One report of a `bundle`, written from the same scan as the others.
+/// Filters narrow the entries the command line selects.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Report {
+    /// The file written, stdout when unset or `-`.
+    pub output: Option<PathBuf>,
+    /// A report format, else the output's extension if it names one, else
+    /// `csv`.
+    pub format: Option<String>,
+    pub columns: Vec<String>,
+    pub group_by: Option<String>,
+    pub accumulate: bool,
+    pub tags: Vec<String>,
+    /// Inclusive `YYYY-MM-DD` bounds on the entries' dates.
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// A regex the entry's text must match.
+    #[serde(rename = "match")]
+    pub text_match: Option<String>,
+}
+
+/// Time on a tag beyond `after` hours in a day is billed at `multiplier`
+/// times the tag's rate.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Overtime {
+    pub after: f64,
+    pub multiplier: f64,
+}
+
+impl Config {
+    /// Loads `path`, or the default config file if it exists. A missing
+    /// default file yields an empty config.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("cannot read config {}: {}", path.display(), e))?;
+        Self::parse(&content)
+            .map_err(|e| format!("invalid config {}: {}", path.display(), e).into())
+    }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Returns the defaults, overridden by the named profile if given.
+    pub fn resolve(&self, name: Option<&str>) -> Result<Profile, Box<dyn Error>> {
+        let mut resolved = self.defaults.clone();
+        let Some(name) = name else {
+            return Ok(resolved);
+        };
+        let profile = self
+            .profile
+            .get(name)
+            .ok_or_else(|| format!("unknown profile '{}'", name))?;
+        if !profile.directories.is_empty() {
+            resolved.directories = profile.directories.clone();
+        }
+        resolved.recursive |= profile.recursive;
+        if !profile.tags.is_empty() {
+            resolved.tags = profile.tags.clone();
+        }
+        if !profile.fields.is_empty() {
+            resolved.fields = profile.fields.clone();
+        }
+        if !profile.reports.is_empty() {
+            resolved.reports = profile.reports.clone();
+        }
+        if !profile.exclusive.is_empty() {
+            resolved.exclusive = profile.exclusive.clone();
+        }
+        if !profile.sprints.is_empty() {
+            resolved.sprints = profile.sprints.clone();
+        }
+        if profile.sprint_start.is_some() {
+            resolved.sprint_start = profile.sprint_start.clone();
+        }
+        if profile.sprint_length.is_some() {
+            resolved.sprint_length = profile.sprint_length.clone();
+        }
+        resolved.rates.extend(profile.rates.clone());
+        resolved.overtime.extend(profile.overtime.clone());
+        if profile.currency.is_some() {
+            resolved.currency = profile.currency.clone();
+        }
+        resolved
+            .exchange_rates
+            .extend(profile.exchange_rates.clone());
+        if profile.format.is_some() {
+            resolved.format = profile.format.clone();
+        }
+        resolved.identifiers.extend(profile.identifiers.clone());
+        resolved.units.extend(profile.units.clone());
+        resolved.column_names.extend(profile.column_names.clone());
+        resolved.targets.extend(profile.targets.clone());
+        if profile.granularity.is_some() {
+            resolved.granularity = profile.granularity.clone();
+        }
+        if profile.granularity_rounding.is_some() {
+            resolved.granularity_rounding = profile.granularity_rounding.clone();
+        }
+        resolved.legacy_durations |= profile.legacy_durations;
+        if profile.archived_key.is_some() {
+            resolved.archived_key = profile.archived_key.clone();
+        }
+        if profile.max_file_size.is_some() {
+            resolved.max_file_size = profile.max_file_size.clone();
+        }
+        if profile.locale.is_some() {
+            resolved.locale = profile.locale.clone();
+        }
+        if profile.rate_limit.is_some() {
+            resolved.rate_limit = profile.rate_limit;
+        }
+        resolved.directories = resolved
+            .directories
+            .iter()
+            .map(|d| expand_home(d))
+            .collect();
+        Ok(resolved)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/timetracker/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("timetracker").join("config.toml"))
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    const CONFIG: &str = dedent!(
+        r##"
+    rates = { "#internal" = 0.0 }
+    exchange_rates = { EUR = 0.9 }
+
+    [profile.acme]
+    directories = ["/vault/Journals"]
+    recursive = true
+    tags = ["#acme"]
+    rates = { "#acme" = 120.0 }
+    format = "json"
+    currency = "EUR"
+    overtime = { "#acme" = { after = 8.0, multiplier = 1.5 } }
+    "##
+    );
+
+    #[test]
+    fn test_resolve_profile_overrides_defaults() {
+        let config = Config::parse(CONFIG).unwrap();
+        let profile = config.resolve(Some("acme")).unwrap();
+        assert_eq!(vec![PathBuf::from("/vault/Journals")], profile.directories);
+        assert!(profile.recursive);
+        assert_eq!(vec!["#acme"], profile.tags);
+        assert_eq!(Some(&120.0), profile.rates.get("#acme"));
+        assert_eq!(Some(&0.0), profile.rates.get("#internal"));
+        assert_eq!(Some("json".to_string()), profile.format);
+        assert_eq!(Some("EUR".to_string()), profile.currency);
+        assert_eq!(Some(&0.9), profile.exchange_rates.get("EUR"));
+        assert_eq!(
+            Some(&Overtime {
+                after: 8.0,
+                multiplier: 1.5
+            }),
+            profile.overtime.get("#acme")
+        );
+    }
+
+    #[test]
+    fn test_parse_reports() {
+        let config = Config::parse(dedent!(
+            r##"
+        [[report]]
+        output = "tags.csv"
+        group_by = "tag"
+
+        [[report]]
+        tags = ["#acme"]
+        since = "2025-01-01"
+        match = "(?i)review"
+        "##
+        ))
+        .unwrap();
+        let profile = config.resolve(None).unwrap();
+        assert_eq!(2, profile.reports.len());
+        assert_eq!(Some(PathBuf::from("tags.csv")), profile.reports[0].output);
+        assert_eq!(Some("tag".to_string()), profile.reports[0].group_by);
+        assert_eq!(
+            Some("(?i)review".to_string()),
+            profile.reports[1].text_match
+        );
+        assert!(Config::parse("[[report]]\ngroup = \"tag\"").is_err());
+    }
+
+    #[test]
+    fn test_resolve_without_profile() {
+        let config = Config::parse(CONFIG).unwrap();
+        let profile = config.resolve(None).unwrap();
+        assert!(profile.directories.is_empty());
+        assert!(profile.tags.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_unknown_profile() {
+        let config = Config::parse(CONFIG).unwrap();
+        assert!(config.resolve(Some("globex")).is_err());
+    }
+}
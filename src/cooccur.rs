@@ -0,0 +1,141 @@
+// src/cooccur.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::json;
+use crate::parser::TimeEntry;
+use csv::Writer;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::io::Write;
+
+/// Which cell value a CSV co-occurrence matrix is rendered with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Count,
+    Duration,
+}
+
+/// Square, symmetric matrix of how often (and how long) tags appear on the
+/// same entry. The diagonal holds the totals for each tag on its own.
+#[derive(Default, Debug, PartialEq)]
+pub struct CooccurrenceMatrix {
+    pub tags: Vec<String>,
+    pub counts: Vec<Vec<u32>>,
+    pub durations: Vec<Vec<TimeDuration>>,
+}
+
+impl CooccurrenceMatrix {
+    pub fn from_entries(entries: &[TimeEntry]) -> Self {
+        let tags: Vec<String> = entries
+            .iter()
+            .flat_map(|entry| split_tags(&entry.tags))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let n = tags.len();
+        let mut matrix = CooccurrenceMatrix {
+            counts: vec![vec![0; n]; n],
+            durations: vec![vec![TimeDuration::default(); n]; n],
+            tags,
+        };
+
+        for entry in entries {
+            let indices: BTreeSet<usize> = split_tags(&entry.tags)
+                .filter_map(|tag| matrix.tags.binary_search(&tag).ok())
+                .collect();
+            for &i in &indices {
+                for &j in &indices {
+                    matrix.counts[i][j] += 1;
+                    matrix.durations[i][j] += &entry.duration;
+                }
+            }
+        }
+        matrix
+    }
+
+    pub fn write_csv<W: Write>(
+        &self,
+        writer: W,
+        metric: Metric,
+        unit: DurationUnit,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut csv_writer = Writer::from_writer(writer);
+        let mut header = vec![String::new()];
+        header.extend(self.tags.iter().cloned());
+        csv_writer.write_record(&header)?;
+        for (i, tag) in self.tags.iter().enumerate() {
+            let mut row = vec![tag.clone()];
+            for j in 0..self.tags.len() {
+                row.push(match metric {
+                    Metric::Count => self.counts[i][j].to_string(),
+                    Metric::Duration => unit.format(&self.durations[i][j]),
+                });
+            }
+            csv_writer.write_record(&row)?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        let tags: Vec<String> = self.tags.iter().map(|t| json::quote(t)).collect();
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|row| {
+                let cells: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+                format!("[{}]", cells.join(","))
+            })
+            .collect();
+        let seconds: Vec<String> = self
+            .durations
+            .iter()
+            .map(|row| {
+                let cells: Vec<String> =
+                    row.iter().map(|d| d.total_seconds().to_string()).collect();
+                format!("[{}]", cells.join(","))
+            })
+            .collect();
+        format!(
+            "{{\"tags\":[{}],\"counts\":[{}],\"seconds\":[{}]}}",
+            tags.join(","),
+            counts.join(","),
+            seconds.join(",")
+        )
+    }
+}
+
+fn split_tags(tags: &str) -> impl Iterator<Item = String> + '_ {
+    tags.split(',')
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooccurrence_counts_pairs() {
+        let entries = vec![
+            TimeEntry::test("#a,#b", "1h"),
+            TimeEntry::test("#a,#b,#c", "30m"),
+            TimeEntry::test("#c", "2h"),
+        ];
+        let matrix = CooccurrenceMatrix::from_entries(&entries);
+        assert_eq!(vec!["#a", "#b", "#c"], matrix.tags);
+        assert_eq!(
+            vec![vec![2, 2, 1], vec![2, 2, 1], vec![1, 1, 2]],
+            matrix.counts
+        );
+        assert_eq!(5400, matrix.durations[0][1].total_seconds());
+        assert_eq!(9000, matrix.durations[2][2].total_seconds());
+    }
+
+    #[test]
+    fn test_cooccurrence_ignores_untagged() {
+        let entries = vec![TimeEntry::test("", "1h")];
+        let matrix = CooccurrenceMatrix::from_entries(&entries);
+        assert!(matrix.tags.is_empty());
+    }
+}
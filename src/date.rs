@@ -0,0 +1,170 @@
+// src/date.rs
+
+use crate::parser::lines;
+use jiff::civil::{Date, Weekday};
+use regex::Regex;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Where an entry's date comes from. Every source falls back to the file
+/// name's date.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DateSource {
+    /// The Tasks plugin completion date (`✅ 2025-01-03`).
+    Completion,
+    #[default]
+    File,
+    /// The `date` key of the note's frontmatter.
+    Frontmatter,
+}
+
+impl FromStr for DateSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "completion" => Ok(DateSource::Completion),
+            "file" => Ok(DateSource::File),
+            "frontmatter" => Ok(DateSource::Frontmatter),
+            _ => Err(format!("unknown date source '{}'", s)),
+        }
+    }
+}
+
+/// Extracts the `YYYY-MM-DD` date from a daily note's file name.
+pub fn date_from_path(path: &Path) -> Option<Date> {
+    let name = path.file_stem()?.to_string_lossy();
+    static RE_DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap());
+    RE_DATE.find(&name)?.as_str().parse().ok()
+}
+
+/// Parses a rolling window like `7d` or `2w` into the first day of the
+/// window ending (inclusively) on `today`.
+pub fn window_start(window: &str, today: Date) -> Result<Date, String> {
+    let re = Regex::new(r"^(\d+)([dw])$").unwrap();
+    let cap = re
+        .captures(window.trim())
+        .ok_or_else(|| format!("expected a window like 7d or 2w, got '{}'", window))?;
+    let count: i64 = cap[1].parse().map_err(|e| format!("{}: {}", window, e))?;
+    let too_long = || format!("window '{}' is too long", window);
+    let days = match &cap[2] {
+        "w" => count.checked_mul(7).ok_or_else(too_long)?,
+        _ => count,
+    };
+    if days == 0 {
+        return Err("window must be at least one day".to_string());
+    }
+    let span = jiff::Span::new()
+        .try_days(days - 1)
+        .map_err(|_| too_long())?;
+    today.checked_sub(span).map_err(|_| too_long())
+}
+
+/// The Monday of the week given as `YYYY-Www` or any `YYYY-MM-DD` in it.
+pub fn week_start(week: &str) -> Result<Date, String> {
+    let re = Regex::new(r"^(\d{4})-W(\d{2})$").unwrap();
+    if let Some(cap) = re.captures(week.trim()) {
+        let year: i16 = cap[1].parse().map_err(|e| format!("{}: {}", week, e))?;
+        let number: i8 = cap[2].parse().map_err(|e| format!("{}: {}", week, e))?;
+        return jiff::civil::ISOWeekDate::new(year, number, jiff::civil::Weekday::Monday)
+            .map(|d| d.date())
+            .map_err(|e| format!("invalid week '{}': {}", week, e));
+    }
+    let date: Date = week
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected YYYY-Www or YYYY-MM-DD, got '{}'", week))?;
+    Ok(monday(date))
+}
+
+/// The Monday on or before `date`.
+pub fn monday(date: Date) -> Date {
+    let offset = date.weekday().to_monday_zero_offset() as i64;
+    date.checked_sub(jiff::Span::new().days(offset)).unwrap()
+}
+
+/// Whether `date` falls on a Saturday or Sunday.
+pub fn is_weekend(date: Date) -> bool {
+    matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday)
+}
+
+/// Reads the `date` key of a note's YAML frontmatter. Datetimes are
+/// truncated to their date.
+pub fn frontmatter_date(content: &str) -> Option<Date> {
+    let mut lines = lines(content);
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+    static RE_DATE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"^date:\s*["']?(\d{4}-\d{2}-\d{2})"#).unwrap());
+    lines
+        .take_while(|line| line.trim_end() != "---")
+        .find_map(|line| RE_DATE.captures(line))
+        .and_then(|cap| cap[1].parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_date_from_path() {
+        let path = Path::new("Journals/2025/2025-01-03.md");
+        assert_eq!(Some(date(2025, 1, 3)), date_from_path(path));
+    }
+
+    #[test]
+    fn test_date_from_path_with_prefix() {
+        let path = Path::new("Journals/Daily 2025-01-03 notes.md");
+        assert_eq!(Some(date(2025, 1, 3)), date_from_path(path));
+    }
+
+    #[test]
+    fn test_date_from_path_without_date() {
+        assert_eq!(None, date_from_path(Path::new("Projects/Acme.md")));
+    }
+
+    #[test]
+    fn test_window_start() {
+        let today = date(2025, 1, 10);
+        assert_eq!(Ok(date(2025, 1, 4)), window_start("7d", today));
+        assert_eq!(Ok(date(2024, 12, 28)), window_start("2w", today));
+        assert_eq!(Ok(today), window_start("1d", today));
+        assert!(window_start("0d", today).is_err());
+        assert!(window_start("7", today).is_err());
+        assert!(window_start("99999999d", today).is_err());
+        assert!(window_start("9999999999999999999w", today).is_err());
+    }
+
+    #[test]
+    fn test_week_start() {
+        assert_eq!(Ok(date(2025, 1, 6)), week_start("2025-W02"));
+        assert_eq!(Ok(date(2025, 1, 6)), week_start("2025-01-12"));
+        assert_eq!(Ok(date(2024, 12, 30)), week_start("2025-01-01"));
+        assert!(week_start("2025-W54").is_err());
+        assert!(week_start("last week").is_err());
+    }
+
+    #[test]
+    fn test_is_weekend() {
+        assert!(!is_weekend(date(2025, 1, 10)));
+        assert!(is_weekend(date(2025, 1, 11)));
+        assert!(is_weekend(date(2025, 1, 12)));
+    }
+
+    #[test]
+    fn test_frontmatter_date() {
+        let content = "---\ntitle: Acme\ndate: \"2025-01-03T09:00\"\n---\n- [ ] Task\n";
+        assert_eq!(Some(date(2025, 1, 3)), frontmatter_date(content));
+    }
+
+    #[test]
+    fn test_frontmatter_date_outside_frontmatter() {
+        let content = "# Notes\ndate: 2025-01-03\n";
+        assert_eq!(None, frontmatter_date(content));
+        let content = "---\ntitle: Acme\n---\ndate: 2025-01-03\n";
+        assert_eq!(None, frontmatter_date(content));
+    }
+}
@@ -0,0 +1,305 @@
+// src/digest.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::locale::Locale;
+use crate::parser::TimeEntry;
+use jiff::ToSpan;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Write;
+
+/// Tags and days listed in a digest.
+const TOP: usize = 5;
+
+/// A tag's time in the digest's month and the month before.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagTotal {
+    pub tag: String,
+    pub current: TimeDuration,
+    pub previous: TimeDuration,
+}
+
+/// One of the busiest days of the month, with the tag it went to most.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    pub date: Date,
+    pub total: TimeDuration,
+    pub top_tag: String,
+}
+
+/// A month of tracked time compared with the month before, for reading
+/// at a glance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Digest {
+    /// The first day of the month.
+    pub month: Date,
+    pub total: TimeDuration,
+    pub previous_total: TimeDuration,
+    pub entries: usize,
+    pub active_days: usize,
+    /// The tags with the most time this month, most first.
+    pub tags: Vec<TagTotal>,
+    /// The days with the most time this month, most first.
+    pub days: Vec<Day>,
+}
+
+/// Builds the digest of `month` (any day in it) from dated entries.
+pub fn digest(entries: &[TimeEntry], month: Date) -> Digest {
+    let month = month.first_of_month();
+    let previous = month.checked_sub(1.month()).unwrap();
+    let next = month.checked_add(1.month()).unwrap();
+
+    let mut total = TimeDuration::default();
+    let mut previous_total = TimeDuration::default();
+    let mut count = 0;
+    let mut tags: BTreeMap<&str, (TimeDuration, TimeDuration)> = BTreeMap::new();
+    let mut days: BTreeMap<Date, (TimeDuration, BTreeMap<&str, TimeDuration>)> = BTreeMap::new();
+    for entry in entries {
+        let Some(date) = entry.date else {
+            continue;
+        };
+        let entry_tags = entry.tags.split(',').filter(|t| !t.is_empty());
+        if date >= month && date < next {
+            total += &entry.duration;
+            count += 1;
+            let (day_total, day_tags) = days.entry(date).or_default();
+            *day_total += &entry.duration;
+            for tag in entry_tags {
+                tags.entry(tag).or_default().0 += &entry.duration;
+                *day_tags.entry(tag).or_default() += &entry.duration;
+            }
+        } else if date >= previous && date < month {
+            previous_total += &entry.duration;
+            for tag in entry_tags {
+                tags.entry(tag).or_default().1 += &entry.duration;
+            }
+        }
+    }
+
+    let mut tags: Vec<TagTotal> = tags
+        .into_iter()
+        .filter(|(_, (current, _))| current.total_seconds() > 0)
+        .map(|(tag, (current, previous))| TagTotal {
+            tag: tag.to_string(),
+            current,
+            previous,
+        })
+        .collect();
+    tags.sort_by_key(|tag| std::cmp::Reverse(tag.current.total_seconds()));
+    tags.truncate(TOP);
+
+    let active_days = days.len();
+    let mut days: Vec<Day> = days
+        .into_iter()
+        .map(|(date, (total, day_tags))| Day {
+            date,
+            total,
+            top_tag: day_tags
+                .into_iter()
+                .rev()
+                .max_by_key(|(_, duration)| duration.total_seconds())
+                .map(|(tag, _)| tag.to_string())
+                .unwrap_or_default(),
+        })
+        .collect();
+    days.sort_by_key(|day| std::cmp::Reverse(day.total.total_seconds()));
+    days.truncate(TOP);
+
+    Digest {
+        month,
+        total,
+        previous_total,
+        entries: count,
+        active_days,
+        tags,
+        days,
+    }
+}
+
+/// Escapes text for HTML element content and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn amount(duration: &TimeDuration, unit: DurationUnit, locale: &Locale) -> String {
+    match duration.total_seconds() {
+        0 => "0".to_string(),
+        _ => locale.duration(duration, unit),
+    }
+}
+
+/// The difference from `previous` to `current`, signed, like `+1h30m`.
+fn change(
+    current: &TimeDuration,
+    previous: &TimeDuration,
+    unit: DurationUnit,
+    locale: &Locale,
+) -> String {
+    let (current, previous) = (current.total_seconds(), previous.total_seconds());
+    let sign = if current >= previous { '+' } else { '-' };
+    match current.abs_diff(previous) {
+        0 => "0".to_string(),
+        diff => format!(
+            "{}{}",
+            sign,
+            locale.duration(&TimeDuration::from_seconds(diff), unit)
+        ),
+    }
+}
+
+impl Digest {
+    /// Writes the digest as a standalone HTML page with inline styles, so
+    /// it renders the same in email clients. Months, days, and numbers are
+    /// written the way `locale` has them.
+    pub fn write_html<W: Write>(
+        &self,
+        mut writer: W,
+        unit: DurationUnit,
+        locale: &Locale,
+    ) -> Result<(), Box<dyn Error>> {
+        let month = locale.month(self.month);
+        let previous = locale.month(self.month.checked_sub(1.month())?);
+        let cell = "padding:4px 12px;border-bottom:1px solid #ddd";
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(
+            writer,
+            "<html><head><meta charset=\"utf-8\"><title>Time digest: {}</title></head>",
+            month
+        )?;
+        writeln!(
+            writer,
+            "<body style=\"font-family:sans-serif;color:#222;max-width:640px;margin:auto\">"
+        )?;
+        writeln!(writer, "<h1>Time digest: {}</h1>", month)?;
+        writeln!(
+            writer,
+            "<p>Tracked <strong>{}</strong> in {} entries over {} days, {} from {} ({}).</p>",
+            amount(&self.total, unit, locale),
+            self.entries,
+            self.active_days,
+            change(&self.total, &self.previous_total, unit, locale),
+            previous,
+            amount(&self.previous_total, unit, locale)
+        )?;
+
+        writeln!(writer, "<h2>Top tags</h2>")?;
+        writeln!(writer, "<table style=\"border-collapse:collapse\">")?;
+        writeln!(
+            writer,
+            "<tr><th style=\"{0};text-align:left\">Tag</th><th style=\"{0}\">{1}</th><th style=\"{0}\">{2}</th><th style=\"{0}\">Change</th></tr>",
+            cell,
+            escape(&month),
+            escape(&previous)
+        )?;
+        for tag in &self.tags {
+            writeln!(
+                writer,
+                "<tr><td style=\"{0}\">{1}</td><td style=\"{0};text-align:right\">{2}</td><td style=\"{0};text-align:right\">{3}</td><td style=\"{0};text-align:right\">{4}</td></tr>",
+                cell,
+                escape(&tag.tag),
+                amount(&tag.current, unit, locale),
+                amount(&tag.previous, unit, locale),
+                change(&tag.current, &tag.previous, unit, locale)
+            )?;
+        }
+        writeln!(writer, "</table>")?;
+
+        writeln!(writer, "<h2>Busiest days</h2>")?;
+        writeln!(writer, "<ul>")?;
+        for day in &self.days {
+            write!(
+                writer,
+                "<li>{} {}: {}",
+                locale.weekday(day.date),
+                locale.date(day.date),
+                locale.duration(&day.total, unit)
+            )?;
+            if !day.top_tag.is_empty() {
+                write!(writer, ", mostly {}", escape(&day.top_tag))?;
+            }
+            writeln!(writer, "</li>")?;
+        }
+        writeln!(writer, "</ul>")?;
+        writeln!(writer, "</body></html>")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_digest() {
+        let entries = [
+            TimeEntry::test("#dev", "2h").dated(date(2024, 12, 30)),
+            TimeEntry::test("#dev,#pbi-1", "3h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#ops", "1h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#ops", "2h").dated(date(2025, 1, 7)),
+            TimeEntry::test("#dev", "8h").dated(date(2025, 2, 3)),
+        ];
+        let digest = digest(&entries, date(2025, 1, 15));
+        assert_eq!(date(2025, 1, 1), digest.month);
+        assert_eq!(parse_duration("6h"), digest.total);
+        assert_eq!(parse_duration("2h"), digest.previous_total);
+        assert_eq!((3, 2), (digest.entries, digest.active_days));
+        let tags: Vec<(&str, String)> = digest
+            .tags
+            .iter()
+            .map(|t| {
+                (
+                    t.tag.as_str(),
+                    change(
+                        &t.current,
+                        &t.previous,
+                        DurationUnit::Compact,
+                        &Locale::default(),
+                    ),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("#dev", "+1h".to_string()),
+                ("#ops", "+3h".to_string()),
+                ("#pbi-1", "+3h".to_string()),
+            ],
+            tags
+        );
+        assert_eq!(
+            Day {
+                date: date(2025, 1, 6),
+                total: parse_duration("4h"),
+                top_tag: "#dev".to_string(),
+            },
+            digest.days[0]
+        );
+
+        let mut html = vec![];
+        digest
+            .write_html(&mut html, DurationUnit::Compact, &Locale::default())
+            .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("<h1>Time digest: January 2025</h1>"));
+        assert!(html.contains(
+            "Tracked <strong>6h</strong> in 3 entries over 2 days, +4h from December 2024 (2h)."
+        ));
+        assert!(html.contains("<li>Mon 2025-01-06: 4h, mostly #dev</li>"));
+        assert_eq!("a &lt;b&gt; &amp; &quot;c&quot;", escape("a <b> & \"c\""));
+
+        let mut html = vec![];
+        let german = "de-DE".parse().unwrap();
+        digest
+            .write_html(&mut html, DurationUnit::Hours, &german)
+            .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("+4 from Dezember 2024 (2)."));
+        assert!(html.contains("<li>Mo 06.01.2025: 4, mostly #dev</li>"));
+    }
+}
@@ -0,0 +1,227 @@
+// src/doctor.rs
+
+use crate::date::date_from_path;
+use crate::duration::Units;
+use crate::exclusive::Exclusives;
+use crate::identifier::Identifiers;
+use crate::parser::{coverage, lines, scan_note};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A note that doesn't follow the vault's conventions. `line` is 1-based.
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Checks `files`, given as (path, content), for unparsed or misplaced time
+/// fields, near-miss `Work on [[N]]` headings, untagged entries, daily notes
+/// without a date in their name, identifier tags that don't match their
+/// format, entries breaking an exclusive tag group, and tags that look like
+/// typos of more common ones.
+pub fn check(
+    files: &[(PathBuf, String)],
+    identifiers: &Identifiers,
+    exclusives: &Exclusives,
+) -> Vec<Finding> {
+    let re_list_item = Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s").unwrap();
+    let re_work_on = Regex::new(r"(?i)^#+\s+work\s*on\b").unwrap();
+    let re_pbi_heading = Regex::new(r"(?i)^#+\s+Work on \[\[(\d+)\]\]").unwrap();
+
+    let mut findings = vec![];
+    let mut tags: BTreeMap<String, Vec<(&Path, usize)>> = BTreeMap::new();
+    for (path, content) in files {
+        let scan = scan_note(content.as_bytes(), true, &Units::default());
+        let coverage = coverage(content.as_bytes(), &scan.lines);
+        let entries = scan.entries;
+        let finding = |line: Option<usize>, message: &str| Finding {
+            path: path.clone(),
+            line,
+            message: message.to_string(),
+        };
+        if !entries.is_empty() && date_from_path(path).is_none() {
+            findings.push(finding(
+                None,
+                "tracked time in a note without a YYYY-MM-DD date in its name",
+            ));
+        }
+
+        for (index, line) in lines(content).enumerate() {
+            let number = index + 1;
+            if re_work_on.is_match(line) && !re_pbi_heading.is_match(line) {
+                findings.push(finding(
+                    Some(number),
+                    "heading looks like 'Work on [[N]]' but won't tag entries with #pbi-N",
+                ));
+            }
+            if coverage.unparsed.contains(&number) {
+                findings.push(finding(
+                    Some(number),
+                    "time field is not parsed; expected a field like [timeTracked: 1h30m]",
+                ));
+            }
+            // Indented lines that parse are continuations of the task above.
+            if entries.iter().any(|e| e.line == number)
+                && !re_list_item.is_match(line)
+                && !line.starts_with(char::is_whitespace)
+            {
+                findings.push(finding(
+                    Some(number),
+                    "time field on a line that is not a task",
+                ));
+            }
+        }
+
+        for entry in &entries {
+            if entry.tags.is_empty() {
+                findings.push(finding(Some(entry.line), "entry has no tags"));
+            }
+            for tag in identifiers.invalid(&entry.tags) {
+                findings.push(finding(
+                    Some(entry.line),
+                    &format!("{} doesn't match its identifier format", tag),
+                ));
+            }
+            for violation in exclusives.violations(&entry.tags) {
+                findings.push(finding(Some(entry.line), &violation));
+            }
+            for tag in entry.tags.split(',').filter(|t| !t.is_empty()) {
+                tags.entry(tag.to_string())
+                    .or_default()
+                    .push((path, entry.line));
+            }
+        }
+    }
+
+    for (tag, uses) in &tags {
+        let Some((common, common_uses)) = tags
+            .iter()
+            .filter(|(other, other_uses)| {
+                other_uses.len() > uses.len() && looks_like_typo(tag, other)
+            })
+            .max_by_key(|(_, other_uses)| other_uses.len())
+        else {
+            continue;
+        };
+        let (path, line) = uses[0];
+        findings.push(Finding {
+            path: path.to_path_buf(),
+            line: Some(line),
+            message: format!(
+                "{} looks like a typo of {} (used {} times)",
+                tag,
+                common,
+                common_uses.len()
+            ),
+        });
+    }
+    findings
+}
+
+/// Whether `tag` is within two edits of `other`, ignoring case. Tags that
+/// only differ in their digits, like `#pbi-12` and `#pbi-13`, are distinct.
+fn looks_like_typo(tag: &str, other: &str) -> bool {
+    let strip = |t: &str| -> String { t.chars().filter(|c| !c.is_numeric()).collect() };
+    if tag.chars().count() < 5 || strip(tag) == strip(other) {
+        return tag != other && tag.eq_ignore_ascii_case(other);
+    }
+    edit_distance(&tag.to_lowercase(), &other.to_lowercase()) <= 2
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    fn messages(findings: &[Finding]) -> Vec<String> {
+        findings.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_check_lines() {
+        let content = dedent!(
+            r#"
+        # Work on 123
+        - [ ] Task #dev [timeTracked 1h]
+        Spent [timeTracked: 30m] #dev
+        - [ ] Untagged [timeTracked: 15m]
+        - [ ] Typo #pbi-12a [timeTracked: 15m]
+        "#
+        );
+        let files = vec![(PathBuf::from("2025-01-01.md"), content.to_string())];
+        assert_eq!(
+            vec![
+                "2025-01-01.md:1: heading looks like 'Work on [[N]]' but won't tag entries with #pbi-N",
+                "2025-01-01.md:2: time field is not parsed; expected a field like [timeTracked: 1h30m]",
+                "2025-01-01.md:3: time field on a line that is not a task",
+                "2025-01-01.md:4: entry has no tags",
+                "2025-01-01.md:5: #pbi-12a doesn't match its identifier format",
+            ],
+            messages(&check(
+                &files,
+                &Identifiers::default(),
+                &Exclusives::default()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_check_tag_typos_and_names() {
+        let files = vec![
+            (
+                PathBuf::from("2025-01-01.md"),
+                "- [ ] A #meetings #pbi-12 [timeTracked: 1h]\n- [ ] B #meetings [timeTracked: 1h]"
+                    .to_string(),
+            ),
+            (
+                PathBuf::from("Inbox.md"),
+                "- [ ] C #meetins #pbi-13 [timeTracked: 1h]".to_string(),
+            ),
+        ];
+        assert_eq!(
+            vec![
+                "Inbox.md: tracked time in a note without a YYYY-MM-DD date in its name",
+                "Inbox.md:1: #meetins looks like a typo of #meetings (used 2 times)",
+            ],
+            messages(&check(
+                &files,
+                &Identifiers::default(),
+                &Exclusives::default()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(0, edit_distance("abc", "abc"));
+        assert_eq!(1, edit_distance("#meetins", "#meetings"));
+        assert_eq!(2, edit_distance("#ops", "#dps-"));
+    }
+}
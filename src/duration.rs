@@ -0,0 +1,478 @@
+// src/duration.rs
+
+use regex::Regex;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A span of time in whole seconds, over `std::time::Duration`, so
+/// accumulating years of entries cannot overflow. Addition saturates with a
+/// warning instead, and subtraction stops at zero.
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeDuration(Duration);
+
+impl TimeDuration {
+    pub fn new(hours: u64, minutes: u64, seconds: u64) -> Self {
+        [(hours, 3600), (minutes, 60), (seconds, 1)]
+            .into_iter()
+            .map(|(value, unit)| TimeDuration::from_seconds(saturating_mul(value, unit)))
+            .sum()
+    }
+
+    pub fn from_seconds(seconds: u64) -> Self {
+        TimeDuration(Duration::from_secs(seconds))
+    }
+
+    pub fn total_seconds(&self) -> u64 {
+        self.0.as_secs()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn hours(&self) -> u64 {
+        self.total_seconds() / 3600
+    }
+
+    pub fn minutes(&self) -> u64 {
+        self.total_seconds() % 3600 / 60
+    }
+
+    pub fn seconds(&self) -> u64 {
+        self.total_seconds() % 60
+    }
+}
+
+impl From<Duration> for TimeDuration {
+    /// Drops any fraction of a second.
+    fn from(duration: Duration) -> Self {
+        TimeDuration::from_seconds(duration.as_secs())
+    }
+}
+
+impl From<TimeDuration> for Duration {
+    fn from(duration: TimeDuration) -> Self {
+        duration.0
+    }
+}
+
+impl Add<&TimeDuration> for &TimeDuration {
+    type Output = TimeDuration;
+
+    fn add(self, other: &TimeDuration) -> TimeDuration {
+        TimeDuration(self.0.checked_add(other.0).unwrap_or_else(|| {
+            log::warn!("duration overflowed; saturating at {} seconds", u64::MAX);
+            Duration::from_secs(u64::MAX)
+        }))
+    }
+}
+
+impl Add for TimeDuration {
+    type Output = TimeDuration;
+
+    fn add(self, other: TimeDuration) -> TimeDuration {
+        &self + &other
+    }
+}
+
+impl AddAssign<&TimeDuration> for TimeDuration {
+    fn add_assign(&mut self, other: &TimeDuration) {
+        *self = &*self + other;
+    }
+}
+
+impl AddAssign for TimeDuration {
+    fn add_assign(&mut self, other: TimeDuration) {
+        *self += &other;
+    }
+}
+
+impl Sub<&TimeDuration> for &TimeDuration {
+    type Output = TimeDuration;
+
+    fn sub(self, other: &TimeDuration) -> TimeDuration {
+        TimeDuration(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Sub for TimeDuration {
+    type Output = TimeDuration;
+
+    fn sub(self, other: TimeDuration) -> TimeDuration {
+        &self - &other
+    }
+}
+
+impl SubAssign<&TimeDuration> for TimeDuration {
+    fn sub_assign(&mut self, other: &TimeDuration) {
+        *self = &*self - other;
+    }
+}
+
+impl Sum for TimeDuration {
+    fn sum<I: Iterator<Item = TimeDuration>>(iter: I) -> Self {
+        iter.fold(TimeDuration::default(), |total, duration| total + duration)
+    }
+}
+
+impl<'a> Sum<&'a TimeDuration> for TimeDuration {
+    fn sum<I: Iterator<Item = &'a TimeDuration>>(iter: I) -> Self {
+        iter.fold(TimeDuration::default(), |total, duration| &total + duration)
+    }
+}
+
+impl fmt::Display for TimeDuration {
+    /// The compact form, like `1h30m`, or `0s` for no time.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.is_zero() {
+            true => write!(f, "0s"),
+            false => write!(f, "{}", format_duration(self)),
+        }
+    }
+}
+
+impl FromStr for TimeDuration {
+    type Err = String;
+
+    /// Reads a duration written only in the built-in units, like `1h30m`,
+    /// `2 h 15 m`, or `0`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let units = Units::default();
+        match s.trim() {
+            "0" => Ok(TimeDuration::default()),
+            text if units.is_duration(text) => Ok(units.parse(text)),
+            _ => Err(format!("invalid duration '{}'; expected like 1h30m", s)),
+        }
+    }
+}
+
+impl Serialize for TimeDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeDuration {
+    /// Reads a duration like `"1h30m"`, or a number of seconds.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DurationVisitor;
+
+        impl Visitor<'_> for DurationVisitor {
+            type Value = TimeDuration;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a duration like \"1h30m\" or a number of seconds")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<TimeDuration, E> {
+                text.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, seconds: u64) -> Result<TimeDuration, E> {
+                Ok(TimeDuration::from_seconds(seconds))
+            }
+
+            fn visit_i64<E: de::Error>(self, seconds: i64) -> Result<TimeDuration, E> {
+                u64::try_from(seconds)
+                    .map(TimeDuration::from_seconds)
+                    .map_err(|_| E::custom("a duration can't be negative"))
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+/// How durations are rendered in output: the compact `1h30m` form, or a
+/// single number in one unit for tools that can't parse the compact form.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum DurationUnit {
+    #[default]
+    Compact,
+    Hours,
+    Minutes,
+    Seconds,
+}
+
+impl DurationUnit {
+    pub fn format(&self, duration: &TimeDuration) -> String {
+        let seconds = duration.total_seconds();
+        match self {
+            DurationUnit::Compact => format_duration(duration),
+            DurationUnit::Hours => format_decimal(seconds as f64 / 3600.0),
+            DurationUnit::Minutes => format_decimal(seconds as f64 / 60.0),
+            DurationUnit::Seconds => seconds.to_string(),
+        }
+    }
+}
+
+impl FromStr for DurationUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(DurationUnit::Compact),
+            "hours" => Ok(DurationUnit::Hours),
+            "minutes" => Ok(DurationUnit::Minutes),
+            "seconds" => Ok(DurationUnit::Seconds),
+            _ => Err(format!("unknown duration unit '{}'", s)),
+        }
+    }
+}
+
+/// Formats with at most two decimals, dropping trailing zeros.
+pub(crate) fn format_decimal(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+pub fn format_duration(duration: &TimeDuration) -> String {
+    let mut parts = vec![];
+    if duration.hours() > 0 {
+        parts.push(format!("{}h", duration.hours()));
+    }
+    if duration.minutes() > 0 {
+        parts.push(format!("{}m", duration.minutes()));
+    }
+    if duration.seconds() > 0 {
+        parts.push(format!("{}s", duration.seconds()));
+    }
+    parts.join("")
+}
+
+fn saturating_mul(value: u64, unit: u64) -> u64 {
+    value.checked_mul(unit).unwrap_or_else(|| {
+        log::warn!("duration overflowed; saturating at {} seconds", u64::MAX);
+        u64::MAX
+    })
+}
+
+fn unit_seconds(unit: &str) -> Option<u64> {
+    match unit {
+        "h" => Some(3600),
+        "m" => Some(60),
+        "s" => Some(1),
+        "d" => Some(8 * 3600),
+        _ => None,
+    }
+}
+
+/// The unit names durations are parsed with: `h`, `m`, `s`, and `d`, plus
+/// aliases from the config for notes in other languages, like `t` or
+/// `Std` for hours. Names are matched case-insensitively.
+#[derive(Debug, Clone)]
+pub struct Units {
+    re: Regex,
+    aliases: BTreeMap<String, u64>,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::new(&BTreeMap::new()).unwrap()
+    }
+}
+
+impl Units {
+    /// Builds the units from the config's `units` table, mapping each alias
+    /// to the built-in unit it stands for.
+    pub fn new(aliases: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut names: Vec<String> = ["h", "m", "s", "d"].map(String::from).to_vec();
+        let mut seconds = BTreeMap::new();
+        for (alias, unit) in aliases {
+            let unit_seconds = unit_seconds(&unit.to_lowercase()).ok_or_else(|| {
+                format!(
+                    "unit alias '{}' must stand for h, m, s, or d, not '{}'",
+                    alias, unit
+                )
+            })?;
+            if alias.is_empty() || alias.chars().any(|c| !c.is_alphabetic()) {
+                return Err(format!("unit alias '{}' must be only letters", alias));
+            }
+            seconds.insert(alias.to_lowercase(), unit_seconds);
+            names.push(regex::escape(alias));
+        }
+        // Longest first, so `min` wins over `m`.
+        names.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        let re = Regex::new(&format!(
+            r"(?i)(?P<value>\d+)\s*(?P<unit>{})",
+            names.join("|")
+        ))
+        .unwrap();
+        Ok(Units {
+            re,
+            aliases: seconds,
+        })
+    }
+
+    pub fn parse(&self, text: &str) -> TimeDuration {
+        let mut duration = TimeDuration::default();
+        for cap in self.re.captures_iter(text) {
+            let value: u64 = cap["value"].parse().unwrap_or_else(|_| {
+                log::warn!("duration '{}' is too large; saturating", &cap[0]);
+                u64::MAX
+            });
+            let unit = cap["unit"].to_lowercase();
+            let unit = self
+                .aliases
+                .get(&unit)
+                .copied()
+                .or_else(|| unit_seconds(&unit))
+                .unwrap_or(1);
+            duration += TimeDuration::from_seconds(saturating_mul(value, unit));
+        }
+        duration
+    }
+
+    /// Whether `text` is nothing but durations, like `2h15m` or `1 h 30 m`.
+    pub fn is_duration(&self, text: &str) -> bool {
+        self.re.is_match(text) && self.re.replace_all(text, "").trim().is_empty()
+    }
+}
+
+/// Parses a duration like `1h30m` with the built-in units only.
+pub fn parse_duration(text: &str) -> TimeDuration {
+    Units::default().parse(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_hours() {
+        let duration = parse_duration("3h");
+        assert_eq!(TimeDuration::new(3, 0, 0), duration);
+    }
+
+    #[test]
+    fn test_parse_duration_minutes() {
+        let duration = parse_duration("45m");
+        assert_eq!(TimeDuration::new(0, 45, 0), duration);
+    }
+
+    #[test]
+    fn test_parse_duration_seconds() {
+        let duration = parse_duration("30s");
+        assert_eq!(TimeDuration::new(0, 0, 30), duration);
+    }
+
+    #[test]
+    fn test_parse_duration_combined_with_spaces() {
+        let duration = parse_duration("2h 10m 15s");
+        assert_eq!(TimeDuration::new(2, 10, 15), duration);
+    }
+
+    #[test]
+    fn test_parse_duration_combined() {
+        let duration = parse_duration("2h15m10s");
+        assert_eq!(TimeDuration::new(2, 15, 10), duration);
+    }
+
+    #[test]
+    fn test_parse_duration_days() {
+        let duration = parse_duration("1d");
+        assert_eq!(TimeDuration::new(8, 0, 0), duration);
+    }
+
+    #[test]
+    fn test_units_aliases() {
+        let aliases = BTreeMap::from([
+            ("t".to_string(), "h".to_string()),
+            ("Std".to_string(), "h".to_string()),
+            ("min".to_string(), "m".to_string()),
+        ]);
+        let units = Units::new(&aliases).unwrap();
+        assert_eq!(TimeDuration::new(2, 30, 0), units.parse("2t30min"));
+        assert_eq!(TimeDuration::new(2, 0, 0), units.parse("2 Std"));
+        assert_eq!(TimeDuration::new(1, 15, 0), units.parse("1h 15m"));
+        assert!(Units::new(&BTreeMap::from([("t".to_string(), "x".to_string())])).is_err());
+        assert!(Units::new(&BTreeMap::from([("2".to_string(), "h".to_string())])).is_err());
+    }
+
+    #[test]
+    fn test_duration_unit_hours() {
+        let duration = parse_duration("1h30m");
+        assert_eq!("1.5", DurationUnit::Hours.format(&duration));
+        assert_eq!("0.33", DurationUnit::Hours.format(&parse_duration("20m")));
+        assert_eq!("2", DurationUnit::Hours.format(&parse_duration("2h")));
+    }
+
+    #[test]
+    fn test_duration_unit_minutes_and_seconds() {
+        let duration = parse_duration("1h1m30s");
+        assert_eq!("61.5", DurationUnit::Minutes.format(&duration));
+        assert_eq!("3690", DurationUnit::Seconds.format(&duration));
+        assert_eq!("0", DurationUnit::Seconds.format(&TimeDuration::default()));
+    }
+
+    #[test]
+    fn test_from_seconds_normalizes() {
+        assert_eq!(parse_duration("1h1m1s"), TimeDuration::from_seconds(3661));
+    }
+
+    #[test]
+    fn test_parse_duration_normalizes() {
+        assert_eq!("1h30m", format_duration(&parse_duration("90m")));
+    }
+
+    #[test]
+    fn test_add_saturates_on_overflow() {
+        let mut duration = TimeDuration::from_seconds(u64::MAX - 1);
+        duration += parse_duration("1h");
+        assert_eq!(u64::MAX, duration.total_seconds());
+        let huge = parse_duration("99999999999999999999h");
+        assert_eq!(u64::MAX, huge.total_seconds());
+    }
+
+    #[test]
+    fn test_duration_operators_and_serde() {
+        let durations = [parse_duration("1h"), parse_duration("45m")];
+        let total: TimeDuration = durations.iter().sum();
+        assert_eq!(parse_duration("1h45m"), total);
+        assert_eq!(parse_duration("15m"), &durations[0] - &durations[1]);
+        assert_eq!(TimeDuration::default(), &durations[1] - &durations[0]);
+        assert_eq!(Duration::from_secs(6300), Duration::from(total.clone()));
+        assert_eq!(total, TimeDuration::from(Duration::from_millis(6_300_900)));
+        assert_eq!("1h45m", total.to_string());
+        assert_eq!("0s", TimeDuration::default().to_string());
+        assert_eq!(Ok(total.clone()), "1h 45m".parse());
+        assert!("soon".parse::<TimeDuration>().is_err());
+
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Budget {
+            limit: TimeDuration,
+            warn_at: TimeDuration,
+        }
+        let budget: Budget = toml::from_str("limit = \"1h45m\"\nwarn_at = 3600").unwrap();
+        assert_eq!(
+            (total, parse_duration("1h")),
+            (budget.limit.clone(), budget.warn_at.clone())
+        );
+        assert_eq!(
+            "limit = \"1h45m\"\nwarn_at = \"1h\"\n",
+            toml::to_string(&budget).unwrap()
+        );
+        assert!(toml::from_str::<Budget>("limit = \"later\"\nwarn_at = 0").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        let duration = parse_duration("invalid");
+        assert_eq!(TimeDuration::new(0, 0, 0), duration);
+    }
+
+    #[test]
+    fn test_parse_duration_mixed_valid_invalid() {
+        let duration = parse_duration("2h invalid 30m");
+        assert_eq!(TimeDuration::new(2, 30, 0), duration);
+    }
+}
@@ -0,0 +1,180 @@
+// src/embed.rs
+
+use crate::parser::lines;
+use crate::scan::collect_entries;
+use crate::vault;
+use regex::Regex;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Embedded notes deeper than this are left out, which also ends cycles.
+const MAX_DEPTH: usize = 8;
+
+thread_local! {
+    /// The notes of the vault resolved against last, so each embed doesn't
+    /// walk the vault again.
+    static NOTES: RefCell<Option<(PathBuf, Vec<PathBuf>)>> = const { RefCell::new(None) };
+}
+
+/// A note with the notes it embeds spliced in.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expanded {
+    pub content: String,
+    /// For each line of `content`, the line of the original note it came
+    /// from: itself, or the embed that brought it in.
+    pub lines: Vec<usize>,
+}
+
+/// Splices each note embedded on a line of its own (`![[Task note]]`, also
+/// as a list item) into `content` right after the embed, so its entries
+/// take on the embedding note's headings, `Work on [[N]]` PBI, and tags.
+/// Lines are indented to nest under an embedding list item. The embedded
+/// note's frontmatter is left out, and its headings are blanked so they
+/// don't end the embedding note's sections. Embeds are resolved like
+/// Obsidian does, by note name or path suffix anywhere in the vault
+/// containing `note` (or its folder outside a vault).
+pub fn expand(content: &str, note: &Path) -> Expanded {
+    let mut expanded = Expanded::default();
+    let mut stack = vec![note.canonicalize().unwrap_or_else(|_| note.to_path_buf())];
+    splice(content, note, None, "", &mut stack, &mut expanded);
+    expanded
+}
+
+fn splice(
+    content: &str,
+    note: &Path,
+    embedded_at: Option<usize>,
+    indent: &str,
+    stack: &mut Vec<PathBuf>,
+    expanded: &mut Expanded,
+) {
+    let re_embed = Regex::new(r"^(\s*)([-*+]\s+)?!\[\[([^\]|#]+)[^\]]*\]\]\s*$").unwrap();
+    let re_heading = Regex::new(r"^#+\s").unwrap();
+    let body = match embedded_at {
+        Some(_) => strip_frontmatter(content),
+        None => content,
+    };
+    for (index, line) in lines(body).enumerate() {
+        let origin = embedded_at.unwrap_or(index + 1);
+        if embedded_at.is_some() && re_heading.is_match(line) {
+            expanded.content.push('\n');
+        } else {
+            expanded.content.push_str(indent);
+            expanded.content.push_str(line);
+            expanded.content.push('\n');
+        }
+        expanded.lines.push(origin);
+
+        let Some(cap) = re_embed.captures(line) else {
+            continue;
+        };
+        let name = cap[3].trim();
+        if Path::new(name)
+            .extension()
+            .is_some_and(|extension| extension != "md")
+        {
+            continue;
+        }
+        let Some(target) = resolve(name, note) else {
+            log::warn!(
+                "{}:{}: cannot find embedded note '{}'",
+                note.display(),
+                index + 1,
+                name
+            );
+            continue;
+        };
+        if stack.len() > MAX_DEPTH || stack.contains(&target) {
+            log::warn!(
+                "{}:{}: not embedding '{}' again",
+                note.display(),
+                index + 1,
+                name
+            );
+            continue;
+        }
+        let Ok(embedded) = fs::read_to_string(&target) else {
+            log::warn!("cannot read embedded note {}", target.display());
+            continue;
+        };
+        let nested = match cap.get(2) {
+            Some(_) => format!("{}{}    ", indent, &cap[1]),
+            None => format!("{}{}", indent, &cap[1]),
+        };
+        stack.push(target.clone());
+        splice(&embedded, &target, Some(origin), &nested, stack, expanded);
+        stack.pop();
+    }
+}
+
+fn strip_frontmatter(content: &str) -> &str {
+    let mut offset = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        offset += line.len();
+        let fence = line.trim_start_matches('\u{feff}').trim_end() == "---";
+        if index == 0 && !fence {
+            return content;
+        }
+        if index > 0 && fence {
+            return content[offset..].trim_start_matches(['\r', '\n']);
+        }
+    }
+    content
+}
+
+/// Finds the note `name` (like `Task note` or `Projects/Task note`) in the
+/// vault containing `note`, preferring the shortest path.
+fn resolve(name: &str, note: &Path) -> Option<PathBuf> {
+    let dir = note
+        .parent()
+        .unwrap_or(Path::new("."))
+        .canonicalize()
+        .ok()?;
+    let root = vault::find_root(&dir).unwrap_or(dir);
+    let target = Path::new(name.strip_suffix(".md").unwrap_or(name));
+    NOTES.with_borrow_mut(|cached| {
+        if cached.as_ref().is_none_or(|(r, _)| *r != root) {
+            let mut notes = vec![];
+            collect_entries(&root, true, &mut notes).ok()?;
+            notes.sort_by_key(|path| path.components().count());
+            *cached = Some((root.clone(), notes));
+        }
+        let (_, notes) = cached.as_ref().unwrap();
+        notes
+            .iter()
+            .find(|path| path.with_extension("").ends_with(target))
+            .cloned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let vault = tempfile::tempdir().unwrap();
+        let tasks = vault.path().join("Tasks");
+        fs::create_dir(&tasks).unwrap();
+        fs::write(
+            tasks.join("Login bug.md"),
+            "---\nstatus: open\n---\n# Log\n- [ ] Repro [timeTracked: 1h]\n![[Daily]]\n",
+        )
+        .unwrap();
+        let daily = vault.path().join("Daily.md");
+        let content = "## Work on [[42]]\n- Backend #dev\n  - ![[Login bug]]\n- ![[diagram.png]]\n";
+        fs::write(&daily, content).unwrap();
+
+        let expanded = expand(content, &daily);
+        assert_eq!(
+            "## Work on [[42]]\n- Backend #dev\n  - ![[Login bug]]\n\n      - [ ] Repro [timeTracked: 1h]\n      ![[Daily]]\n- ![[diagram.png]]\n",
+            expanded.content
+        );
+        assert_eq!(vec![1, 2, 3, 3, 3, 3, 4], expanded.lines);
+        assert_eq!(
+            "# Log\r\n",
+            strip_frontmatter("\u{feff}---\r\nstatus: open\r\n--- \r\n# Log\r\n")
+        );
+    }
+}
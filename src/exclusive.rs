@@ -0,0 +1,113 @@
+// src/exclusive.rs
+
+use crate::config::Exclusive;
+use regex::Regex;
+
+/// A set of tags an entry may carry only one of, like the `#client-*` tags.
+#[derive(Debug, Clone)]
+struct Group {
+    /// The patterns as configured, comma-joined, for messages.
+    name: String,
+    patterns: Vec<Regex>,
+    /// Whether every entry must carry one of the tags.
+    required: bool,
+}
+
+/// The config's mutually exclusive tag groups.
+#[derive(Debug, Clone, Default)]
+pub struct Exclusives {
+    groups: Vec<Group>,
+}
+
+impl Exclusives {
+    /// Builds the groups from the config's `[[exclusive]]` tables. Patterns
+    /// are tags where `*` stands for any text, like `#client-*`.
+    pub fn new(rules: &[Exclusive]) -> Result<Self, String> {
+        let mut groups = vec![];
+        for rule in rules {
+            if rule.tags.is_empty() {
+                return Err("exclusive tag group without any tags".to_string());
+            }
+            let mut patterns = vec![];
+            for tag in &rule.tags {
+                if !tag.starts_with('#') || tag.len() < 2 {
+                    return Err(format!("exclusive tag '{}' must start with '#'", tag));
+                }
+                let pattern = tag
+                    .split('*')
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(".*");
+                patterns.push(Regex::new(&format!("^{}$", pattern)).unwrap());
+            }
+            groups.push(Group {
+                name: rule.tags.join(","),
+                patterns,
+                required: rule.required,
+            });
+        }
+        Ok(Exclusives { groups })
+    }
+
+    /// How a comma-joined tag list breaks the groups: one message per group
+    /// with more than one of its tags, or none of a required group's.
+    pub fn violations(&self, tags: &str) -> Vec<String> {
+        let mut violations = vec![];
+        for group in &self.groups {
+            let found: Vec<&str> = tags
+                .split(',')
+                .filter(|tag| group.patterns.iter().any(|re| re.is_match(tag)))
+                .collect();
+            match found.len() {
+                0 if group.required => {
+                    violations.push(format!("entry has no tag matching {}", group.name))
+                }
+                0 | 1 => {}
+                _ => violations.push(format!(
+                    "{} are exclusive ({})",
+                    found.join(" and "),
+                    group.name
+                )),
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations() {
+        let exclusives = Exclusives::new(&[
+            Exclusive {
+                tags: vec!["#client-*".to_string()],
+                required: true,
+            },
+            Exclusive {
+                tags: vec!["#billable".to_string(), "#internal".to_string()],
+                required: false,
+            },
+        ])
+        .unwrap();
+        assert!(exclusives.violations("#dev,#client-acme").is_empty());
+        assert_eq!(
+            vec![
+                "#client-acme and #client-globex are exclusive (#client-*)",
+                "#billable and #internal are exclusive (#billable,#internal)",
+            ],
+            exclusives.violations("#client-acme,#client-globex,#billable,#internal")
+        );
+        assert_eq!(
+            vec!["entry has no tag matching #client-*"],
+            exclusives.violations("#dev,#billable")
+        );
+        assert!(Exclusives::default().violations("#a,#b").is_empty());
+        let missing_hash = Exclusive {
+            tags: vec!["client-*".to_string()],
+            required: false,
+        };
+        assert!(Exclusives::new(&[missing_hash]).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+// src/forecast.rs
+
+use crate::date::is_weekend;
+use crate::duration::{DurationUnit, TimeDuration};
+use jiff::ToSpan;
+use jiff::civil::Date;
+use std::collections::BTreeSet;
+
+/// When the remaining estimate on a tag would be done at the pace it has
+/// been worked on so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub estimate: TimeDuration,
+    pub tracked: TimeDuration,
+    /// What is left of the estimate, zero once it is used up.
+    pub remaining: TimeDuration,
+    /// Days with time tracked on the tag.
+    pub active_days: usize,
+    /// The completion date keeping the pace of the active days.
+    pub earliest: Option<Date>,
+    /// The completion date keeping the pace spread over every weekday
+    /// since work began, idle ones included.
+    pub latest: Option<Date>,
+}
+
+impl Forecast {
+    /// The forecast as CSV fields: estimate, tracked, remaining, active
+    /// days, and the earliest and latest completion dates.
+    pub fn record(&self, unit: DurationUnit) -> [String; 6] {
+        let date = |date: Option<Date>| date.map(|d| d.to_string()).unwrap_or_default();
+        [
+            unit.format(&self.estimate),
+            unit.format(&self.tracked),
+            match self.remaining.total_seconds() {
+                0 => "0".to_string(),
+                _ => unit.format(&self.remaining),
+            },
+            self.active_days.to_string(),
+            date(self.earliest),
+            date(self.latest),
+        ]
+    }
+}
+
+/// Projects completion of `estimate` from the time tracked on a tag, as
+/// (date, duration) pairs. Undated time counts toward what is done but not
+/// toward the pace. Dates are working days after `today`, and are left out
+/// when nothing remains or there is no dated time to take a pace from.
+pub fn forecast(
+    estimate: TimeDuration,
+    actuals: &[(Option<Date>, TimeDuration)],
+    today: Date,
+) -> Forecast {
+    let mut tracked = TimeDuration::default();
+    let mut dated = 0;
+    let mut days = BTreeSet::new();
+    for (date, duration) in actuals {
+        tracked += duration;
+        if let Some(date) = date {
+            dated += duration.total_seconds();
+            days.insert(*date);
+        }
+    }
+    let remaining = estimate
+        .total_seconds()
+        .saturating_sub(tracked.total_seconds());
+    let (mut earliest, mut latest) = (None, None);
+    if let Some(first) = days.first()
+        && remaining > 0
+        && dated > 0
+    {
+        let weekdays = first
+            .series(1.day())
+            .take_while(|date| *date <= today)
+            .filter(|date| !is_weekend(*date))
+            .count()
+            .max(days.len()) as u64;
+        let finish =
+            |days: u64| add_working_days(today, remaining.saturating_mul(days).div_ceil(dated));
+        earliest = Some(finish(days.len() as u64));
+        latest = Some(finish(weekdays));
+    }
+    Forecast {
+        estimate,
+        tracked,
+        remaining: TimeDuration::from_seconds(remaining),
+        active_days: days.len(),
+        earliest,
+        latest,
+    }
+}
+
+fn add_working_days(mut date: Date, days: u64) -> Date {
+    let mut left = days;
+    while left > 0 {
+        date = date.checked_add(1.day()).unwrap();
+        if !is_weekend(date) {
+            left -= 1;
+        }
+    }
+    date
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_forecast() {
+        // 6h over 3 of the 5 weekdays from Monday to Friday.
+        let actuals = [
+            (Some(date(2025, 1, 6)), parse_duration("2h")),
+            (Some(date(2025, 1, 8)), parse_duration("3h")),
+            (Some(date(2025, 1, 10)), parse_duration("1h")),
+            (None, parse_duration("2h")),
+        ];
+        let forecast = forecast(parse_duration("20h"), &actuals, date(2025, 1, 10));
+        assert_eq!(parse_duration("8h"), forecast.tracked);
+        assert_eq!(3, forecast.active_days);
+        // 12h left at 2h per active day takes 6 working days, and at 1.2h
+        // per weekday 10.
+        assert_eq!(Some(date(2025, 1, 20)), forecast.earliest);
+        assert_eq!(Some(date(2025, 1, 24)), forecast.latest);
+        assert_eq!(
+            ["20h", "8h", "12h", "3", "2025-01-20", "2025-01-24"],
+            forecast.record(DurationUnit::Compact)
+        );
+
+        let done = super::forecast(parse_duration("5h"), &actuals, date(2025, 1, 10));
+        assert_eq!("0", done.record(DurationUnit::Compact)[2]);
+        assert_eq!(None, done.earliest);
+    }
+}
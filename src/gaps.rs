@@ -0,0 +1,259 @@
+// src/gaps.rs
+
+use crate::date::is_weekend;
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::parser::TimeEntry;
+use jiff::ToSpan;
+use jiff::civil::{Date, Time};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The part of each day `--gaps` looks for unlogged time in, like
+/// `09:00-17:00`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkHours {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl FromStr for WorkHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid working hours '{}'; expected HH:MM-HH:MM", s);
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let (start, end): (Time, Time) = (
+            start.trim().parse().map_err(|_| invalid())?,
+            end.trim().parse().map_err(|_| invalid())?,
+        );
+        if start >= end {
+            return Err(format!("working hours '{}' end before they start", s));
+        }
+        Ok(WorkHours { start, end })
+    }
+}
+
+/// A stretch of working hours on `date` that no clocked session covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub date: Date,
+    pub start: Time,
+    pub end: Time,
+}
+
+impl Gap {
+    pub fn duration(&self) -> TimeDuration {
+        TimeDuration::from_seconds((seconds(self.end) - seconds(self.start)) as u64)
+    }
+
+    /// The gap as CSV fields: date, start, end, and duration in `unit`.
+    pub fn record(&self, unit: DurationUnit) -> [String; 4] {
+        [
+            self.date.to_string(),
+            self.start.strftime("%H:%M").to_string(),
+            self.end.strftime("%H:%M").to_string(),
+            unit.format(&self.duration()),
+        ]
+    }
+}
+
+pub(crate) fn seconds(time: Time) -> i64 {
+    time.hour() as i64 * 3600 + time.minute() as i64 * 60 + time.second() as i64
+}
+
+pub(crate) fn time(seconds: i64) -> Time {
+    Time::new(
+        (seconds / 3600) as i8,
+        (seconds % 3600 / 60) as i8,
+        (seconds % 60) as i8,
+        0,
+    )
+    .unwrap()
+}
+
+/// Finds the stretches of working hours longer than `threshold` that no
+/// session covers, on each day with at least one clocked session (an entry
+/// with a `timeStarted` time). Sessions run from their start for their
+/// duration and are cut off at midnight. Ordered by date and time.
+pub fn find_gaps<'a>(
+    entries: impl IntoIterator<Item = &'a TimeEntry>,
+    hours: WorkHours,
+    threshold: &TimeDuration,
+) -> Vec<Gap> {
+    let mut days: BTreeMap<Date, Vec<(i64, i64)>> = BTreeMap::new();
+    for entry in entries {
+        let (Some(date), Some(started)) = (entry.date, entry.started) else {
+            continue;
+        };
+        let start = seconds(started);
+        let end = start.saturating_add(entry.duration.total_seconds().min(86400) as i64);
+        days.entry(date).or_default().push((start, end.min(86400)));
+    }
+
+    let (work_start, work_end) = (seconds(hours.start), seconds(hours.end));
+    let mut gaps = vec![];
+    for (date, mut sessions) in days {
+        sessions.sort_unstable();
+        let mut covered = work_start;
+        let mut add = |from: i64, to: i64| {
+            if to - from > threshold.total_seconds() as i64 {
+                gaps.push(Gap {
+                    date,
+                    start: time(from),
+                    end: time(to),
+                });
+            }
+        };
+        for (start, end) in sessions {
+            if start > covered {
+                add(covered, start.min(work_end));
+            }
+            covered = covered.max(end);
+            if covered >= work_end {
+                break;
+            }
+        }
+        if covered < work_end {
+            add(covered, work_end);
+        }
+    }
+    gaps
+}
+
+/// A working day with less tracked time than wanted, with its daily note
+/// if it has one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseDay {
+    pub date: Date,
+    pub tracked: TimeDuration,
+    pub note: Option<String>,
+}
+
+impl SparseDay {
+    /// The day as CSV fields: date, weekday, tracked time in `unit` (`0`
+    /// for none), and note (empty when there is none).
+    pub fn record(&self, unit: DurationUnit) -> [String; 4] {
+        let tracked = match self.tracked.total_seconds() {
+            0 => "0".to_string(),
+            _ => unit.format(&self.tracked),
+        };
+        [
+            self.date.to_string(),
+            self.date.strftime("%a").to_string(),
+            tracked,
+            self.note.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Lists the weekdays from `since` to `until` with less than `threshold`
+/// tracked, whether or not they have a note. `notes` are the daily notes
+/// by date; a day with several notes shows the first.
+pub fn sparse_days<'a>(
+    notes: &[(Date, String)],
+    entries: impl IntoIterator<Item = &'a TimeEntry>,
+    since: Date,
+    until: Date,
+    threshold: &TimeDuration,
+) -> Vec<SparseDay> {
+    let mut tracked: BTreeMap<Date, TimeDuration> = BTreeMap::new();
+    for entry in entries {
+        if let Some(date) = entry.date {
+            *tracked.entry(date).or_default() += &entry.duration;
+        }
+    }
+    let mut days = vec![];
+    let mut date = since;
+    while date <= until {
+        let total = tracked.get(&date).cloned().unwrap_or_default();
+        if !is_weekend(date) && total.total_seconds() < threshold.total_seconds() {
+            days.push(SparseDay {
+                date,
+                tracked: total,
+                note: notes
+                    .iter()
+                    .find(|(d, _)| *d == date)
+                    .map(|(_, note)| note.clone()),
+            });
+        }
+        date = date.checked_add(1.day()).unwrap();
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::{date, time as at};
+
+    fn session(day: i8, started: &str, duration: &str) -> TimeEntry {
+        TimeEntry {
+            duration: parse_duration(duration),
+            started: Some(started.parse().unwrap()),
+            date: Some(date(2025, 1, day)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        let hours: WorkHours = "09:00-17:00".parse().unwrap();
+        let entries = vec![
+            session(6, "10:00", "2h"),
+            session(6, "08:00", "1h10m"),
+            session(6, "12:20", "4h30m"),
+            session(7, "09:00", "8h"),
+            session(8, "09:00", "1h"),
+        ];
+        let gaps = find_gaps(&entries, hours, &parse_duration("15m"));
+        let records: Vec<[String; 4]> = gaps
+            .iter()
+            .map(|gap| gap.record(DurationUnit::Compact))
+            .collect();
+        assert_eq!(
+            vec![
+                ["2025-01-06", "09:10", "10:00", "50m"],
+                ["2025-01-06", "12:00", "12:20", "20m"],
+                ["2025-01-08", "10:00", "17:00", "7h"],
+            ],
+            records
+        );
+        assert_eq!(at(9, 10, 0, 0), gaps[0].start);
+        assert!("17:00-09:00".parse::<WorkHours>().is_err());
+    }
+
+    #[test]
+    fn test_sparse_days() {
+        // Friday through the next Tuesday.
+        let entries = vec![
+            session(3, "09:00", "7h"),
+            session(6, "09:00", "2h"),
+            session(6, "13:00", "3h"),
+        ];
+        let notes = vec![
+            (date(2025, 1, 3), "2025-01-03.md".to_string()),
+            (date(2025, 1, 6), "2025-01-06.md".to_string()),
+            (date(2025, 1, 7), "2025-01-07.md".to_string()),
+        ];
+        let days = sparse_days(
+            &notes,
+            &entries,
+            date(2025, 1, 3),
+            date(2025, 1, 8),
+            &parse_duration("6h"),
+        );
+        let records: Vec<[String; 4]> = days
+            .iter()
+            .map(|day| day.record(DurationUnit::Compact))
+            .collect();
+        assert_eq!(
+            vec![
+                ["2025-01-06", "Mon", "5h", "2025-01-06.md"],
+                ["2025-01-07", "Tue", "0", "2025-01-07.md"],
+                ["2025-01-08", "Wed", "0", ""],
+            ],
+            records
+        );
+    }
+}
@@ -0,0 +1,76 @@
+// src/git.rs
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs git in `dir` and returns its output, failing with git's message
+/// when it exits unsuccessfully.
+pub(crate) fn run(dir: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The author of each line of `path` by `git blame`, indexed from line 1
+/// at position 0. Uncommitted lines are attributed to `Not Committed Yet`,
+/// as git blame does.
+pub fn line_authors(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("cannot blame {}", path.display()))?;
+    let blame = run(dir, &["blame", "--line-porcelain", "--", name])?;
+    Ok(blame
+        .lines()
+        .filter_map(|line| line.strip_prefix("author "))
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_line_authors() {
+        let vault = tempfile::tempdir().unwrap();
+        let commit = |author: &str, content: &str| {
+            fs::write(vault.path().join("2025-01-01.md"), content).unwrap();
+            let name = format!("user.name={}", author);
+            let setup = ["-c", name.as_str(), "-c", "user.email=team@example.com"];
+            run(vault.path(), &[&setup[..], &["add", "."]].concat()).unwrap();
+            run(
+                vault.path(),
+                &[&setup[..], &["commit", "-q", "-m", "notes"]].concat(),
+            )
+            .unwrap();
+        };
+        run(vault.path(), &["init", "-q"]).unwrap();
+        commit("Ada", "- [ ] A #dev [timeTracked: 1h]\n");
+        commit(
+            "Grace",
+            "- [ ] A #dev [timeTracked: 1h]\n- [ ] B #ops [timeTracked: 2h]\n",
+        );
+
+        assert_eq!(
+            vec!["Ada".to_string(), "Grace".to_string()],
+            line_authors(&vault.path().join("2025-01-01.md")).unwrap()
+        );
+    }
+}
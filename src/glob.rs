@@ -0,0 +1,100 @@
+// src/glob.rs
+
+use regex::Regex;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A path pattern like `Journals/2025/**` or `**/Clients/*.md`: `*` and `?`
+/// match within a folder or file name, `**` across folders, and `{a,b}` and
+/// `[abc]` alternatives. It matches a path when it matches the path's
+/// last components, so it doesn't depend on where the scan started.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    re: Regex,
+}
+
+impl FromStr for Glob {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s.trim_start_matches("./").trim_end_matches('/');
+        if pattern.is_empty() {
+            return Err("empty path filter".to_string());
+        }
+        let mut re = String::from("(?:^|/)");
+        let mut chars = pattern.chars().peekable();
+        let mut braces = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        re.push_str("(?:.*/)?");
+                    } else {
+                        re.push_str(".*");
+                    }
+                }
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                '[' => {
+                    let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                    let class = class
+                        .strip_prefix('!')
+                        .map_or(class.clone(), |rest| format!("^{}", rest));
+                    re.push_str(&format!("[{}]", class.replace('\\', "\\\\")));
+                }
+                '{' => {
+                    braces += 1;
+                    re.push_str("(?:");
+                }
+                '}' if braces > 0 => {
+                    braces -= 1;
+                    re.push(')');
+                }
+                ',' if braces > 0 => re.push('|'),
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        if braces > 0 {
+            return Err(format!("unclosed '{{' in path filter '{}'", s));
+        }
+        re.push('$');
+        let re = Regex::new(&re).map_err(|e| format!("invalid path filter '{}': {}", s, e))?;
+        Ok(Glob { re })
+    }
+}
+
+impl Glob {
+    pub fn is_match(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.re.is_match(&path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob() {
+        let glob: Glob = "Journals/2025/**".parse().unwrap();
+        assert!(glob.is_match(Path::new("/vault/Journals/2025/01/2025-01-03.md")));
+        assert!(glob.is_match(Path::new("Journals/2025/2025-01-03.md")));
+        assert!(!glob.is_match(Path::new("/vault/Journals/2024/2024-12-31.md")));
+        assert!(!glob.is_match(Path::new("/vault/OldJournals/2025/a.md")));
+
+        let glob: Glob = "**/Clients/{Acme,Globex}/*.md".parse().unwrap();
+        assert!(glob.is_match(Path::new("/vault/Work/Clients/Acme/kickoff.md")));
+        assert!(glob.is_match(Path::new("Clients/Globex/call.md")));
+        assert!(!glob.is_match(Path::new("/vault/Clients/Acme/2025/kickoff.md")));
+        assert!(!glob.is_match(Path::new("/vault/Clients/Initech/call.md")));
+
+        let glob: Glob = "2025-0[1-3]-??.md".parse().unwrap();
+        assert!(glob.is_match(Path::new("backup.zip/Journals/2025-02-14.md")));
+        assert!(!glob.is_match(Path::new("Journals/2025-04-01.md")));
+
+        assert!("{a,b".parse::<Glob>().is_err());
+        assert!("".parse::<Glob>().is_err());
+    }
+}
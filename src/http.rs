@@ -0,0 +1,338 @@
+// src/http.rs
+
+use log::warn;
+use std::error::Error;
+use std::io;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use ureq::Agent;
+use ureq::Timeout;
+
+/// Talks HTTP and HTTPS to the services entries are pushed to or checked
+/// against, retrying transient failures so a batch isn't half-applied.
+#[derive(Debug, Clone)]
+pub struct Client {
+    /// Attempts after the first before giving up.
+    pub retries: u32,
+    /// The wait before the first retry, doubled for each one after, unless
+    /// the server asks for longer with `Retry-After`.
+    pub backoff: Duration,
+    /// The least time between the starts of two requests.
+    pub interval: Duration,
+    last: Option<Instant>,
+    agent: Agent,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new(3)
+    }
+}
+
+struct Response {
+    status: u16,
+    status_line: String,
+    retry_after: Option<Duration>,
+    body: String,
+}
+
+impl Client {
+    /// A client retrying `retries` times, starting a second apart, without
+    /// a rate limit.
+    pub fn new(retries: u32) -> Self {
+        let agent = Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(Duration::from_secs(30)))
+            .build()
+            .into();
+        Client {
+            retries,
+            backoff: Duration::from_secs(1),
+            interval: Duration::ZERO,
+            last: None,
+            agent,
+        }
+    }
+
+    /// POSTs `body` as JSON to `url` and returns the response status,
+    /// failing unless it is a 2xx. A POST is only retried when it couldn't
+    /// reach the server or was answered 429, so one the server may have
+    /// applied isn't sent twice.
+    pub fn post(&mut self, url: &str, body: &str) -> Result<u16, Box<dyn Error>> {
+        let response = self.request("POST", url, Some(body), &[])?;
+        if !(200..300).contains(&response.status) {
+            return Err(format!("{} answered {}", url, response.status_line).into());
+        }
+        Ok(response.status)
+    }
+
+    /// GETs `url` with extra `headers`, returning the response status and
+    /// body whatever the status, so callers can tell a missing resource
+    /// from a failure. Connection failures, 429, and 5xx responses are
+    /// retried.
+    pub fn get(
+        &mut self,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<(u16, String), Box<dyn Error>> {
+        let response = self.request("GET", url, None, headers)?;
+        Ok((response.status, response.body))
+    }
+
+    fn request(
+        &mut self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> Result<Response, Box<dyn Error>> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("URL '{}' must start with http:// or https://", url).into());
+        }
+        // Only a GET can be sent again without a second effect.
+        let idempotent = method == "GET";
+        let mut backoff = self.backoff;
+        for attempt in 1.. {
+            self.throttle();
+            let (error, retry_after, retry) = match self.send(url, body, headers) {
+                Ok(response) if response.status == 429 => (
+                    format!("{} answered {}", url, response.status_line),
+                    response.retry_after,
+                    true,
+                ),
+                Ok(response) if response.status >= 500 => (
+                    format!("{} answered {}", url, response.status_line),
+                    response.retry_after,
+                    idempotent,
+                ),
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    let retry = idempotent || unsent(&e);
+                    (e.to_string(), None, retry)
+                }
+            };
+            if !retry || attempt > self.retries {
+                return Err(error.into());
+            }
+            let wait = retry_after.unwrap_or(backoff);
+            warn!(
+                "{}; retrying in {:?} ({} of {})",
+                error, wait, attempt, self.retries
+            );
+            sleep(wait);
+            backoff *= 2;
+        }
+        unreachable!()
+    }
+
+    fn throttle(&mut self) {
+        if let Some(last) = self.last {
+            sleep(self.interval.saturating_sub(last.elapsed()));
+        }
+        self.last = Some(Instant::now());
+    }
+
+    /// Sends a POST of `body`, or a GET without one.
+    fn send(
+        &self,
+        url: &str,
+        body: Option<&str>,
+        headers: &[(&str, &str)],
+    ) -> Result<Response, ureq::Error> {
+        let mut response = match body {
+            Some(body) => {
+                let mut request = self.agent.post(url);
+                for (name, value) in headers {
+                    request = request.header(*name, *value);
+                }
+                request.content_type("application/json").send(body)?
+            }
+            None => {
+                let mut request = self.agent.get(url);
+                for (name, value) in headers {
+                    request = request.header(*name, *value);
+                }
+                request.call()?
+            }
+        };
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok()?.trim().parse().ok())
+            .map(Duration::from_secs);
+        // Only GET callers read the body; the rest needn't wait for it.
+        let body = match body {
+            None => response.body_mut().read_to_string()?,
+            Some(_) => String::new(),
+        };
+        Ok(Response {
+            status: status.as_u16(),
+            status_line: format!(
+                "{} {}",
+                status.as_u16(),
+                status.canonical_reason().unwrap_or_default()
+            ),
+            retry_after,
+            body,
+        })
+    }
+}
+
+/// An `Authorization` header value for HTTP Basic authentication.
+pub fn basic_auth(user: &str, password: &str) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let credentials = format!("{}:{}", user, password);
+    let mut encoded = String::from("Basic ");
+    for chunk in credentials.as_bytes().chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Whether a request failed before any of it reached the server.
+fn unsent(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::ConnectProxyFailed(_)
+        | ureq::Error::Timeout(Timeout::Resolve | Timeout::Connect) => true,
+        ureq::Error::Io(e) => matches!(
+            e.kind(),
+            io::ErrorKind::ConnectionRefused
+                | io::ErrorKind::HostUnreachable
+                | io::ErrorKind::NetworkUnreachable
+                | io::ErrorKind::AddrNotAvailable
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Answers one request per response, returning the requests.
+    fn serve(responses: &'static [&'static str]) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            responses
+                .iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut request = String::new();
+                    let mut buffer = [0; 1024];
+                    let done = |request: &str| {
+                        request.ends_with("\r\n\r\n{}")
+                            || request.starts_with("GET") && request.ends_with("\r\n\r\n")
+                    };
+                    while !done(&request) {
+                        let read = stream.read(&mut buffer).unwrap();
+                        request.push_str(&String::from_utf8_lossy(&buffer[..read]));
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    request
+                })
+                .collect()
+        });
+        (format!("http://{}", address), server)
+    }
+
+    #[test]
+    fn test_post() {
+        let (url, server) = serve(&["HTTP/1.1 204 No Content\r\n\r\n"]);
+        let status = Client::default()
+            .post(&format!("{}/hooks/time", url), "{}")
+            .unwrap();
+        assert_eq!(204, status);
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /hooks/time HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\n{}"));
+        assert!(Client::default().post("ftp://example.com", "{}").is_err());
+    }
+
+    #[test]
+    fn test_get() {
+        let (url, server) = serve(&[
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n{\"a\":\r\n2\r\n1}\r\n0\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 2\r\n\r\n{}",
+        ]);
+        let mut client = Client::default();
+        let auth = [("Authorization", "Bearer token")];
+        assert_eq!(
+            (200, "{\"a\":1}".to_string()),
+            client.get(&format!("{}/issue/A-1", url), &auth).unwrap()
+        );
+        assert_eq!(
+            (404, "{}".to_string()),
+            client.get(&format!("{}/issue/A-2", url), &auth).unwrap()
+        );
+        let requests = server.join().unwrap();
+        assert!(requests[1].starts_with("GET /issue/A-1 HTTP/1.1\r\n"));
+        assert!(
+            requests[1]
+                .to_lowercase()
+                .contains("\r\nauthorization: bearer token\r\n")
+        );
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        assert_eq!(
+            "Basic dG9rZW46YXBpX3Rva2Vu",
+            basic_auth("token", "api_token")
+        );
+        assert_eq!("Basic YTpi", basic_auth("a", "b"));
+        assert_eq!("Basic YWI6", basic_auth("ab", ""));
+    }
+
+    #[test]
+    fn test_post_retries() {
+        let mut client = Client {
+            backoff: Duration::from_millis(10),
+            interval: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let (url, server) = serve(&[
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n\r\n",
+        ]);
+        let started = Instant::now();
+        assert_eq!(200, client.post(&url, "{}").unwrap());
+        assert_eq!(3, server.join().unwrap().len());
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        // The server may have applied a POST it answered 5xx.
+        let (url, server) = serve(&["HTTP/1.1 502 Bad Gateway\r\n\r\n"]);
+        let error = client.post(&url, "{}").unwrap_err();
+        assert!(error.to_string().ends_with("answered 502 Bad Gateway"));
+        assert_eq!(1, server.join().unwrap().len());
+
+        // Nothing reached a closed port, so it is tried again.
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        client.retries = 1;
+        let started = Instant::now();
+        let error = client
+            .post(&format!("http://{}", closed), "{}")
+            .unwrap_err();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(error.to_string().to_lowercase().contains("refused"));
+    }
+}
@@ -0,0 +1,122 @@
+// src/identifier.rs
+
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Tag prefixes that mark work-item identifiers, like `#pbi-` or `#jira-`,
+/// each with an optional format the rest of the tag must match.
+#[derive(Debug, Clone)]
+pub struct Identifiers {
+    prefixes: Vec<(String, Option<Regex>)>,
+}
+
+impl Default for Identifiers {
+    /// `#pbi-` followed by digits, as `Work on [[N]]` headings produce.
+    fn default() -> Self {
+        Identifiers {
+            prefixes: vec![("#pbi-".to_string(), Some(Regex::new(r"^(?:\d+)$").unwrap()))],
+        }
+    }
+}
+
+impl Identifiers {
+    /// Builds the prefixes from the config's `identifiers` table, mapping
+    /// each prefix to a regex for the rest of the tag (empty for any).
+    /// Without any, `#pbi-` followed by digits is the only identifier.
+    pub fn new(formats: &BTreeMap<String, String>) -> Result<Self, String> {
+        if formats.is_empty() {
+            return Ok(Identifiers::default());
+        }
+        let mut prefixes = vec![];
+        for (prefix, format) in formats {
+            if !prefix.starts_with('#') || prefix.len() < 2 {
+                return Err(format!(
+                    "identifier prefix '{}' must start with '#'",
+                    prefix
+                ));
+            }
+            let format = match format.as_str() {
+                "" => None,
+                format => Some(
+                    Regex::new(&format!("^(?:{})$", format))
+                        .map_err(|e| format!("invalid format for {}: {}", prefix, e))?,
+                ),
+            };
+            prefixes.push((prefix.clone(), format));
+        }
+        // Longest first, so `#gh-pr-` wins over `#gh-`.
+        prefixes.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        Ok(Identifiers { prefixes })
+    }
+
+    /// The identifier tags in a comma-joined tag list, each paired with
+    /// whether it matches its prefix's format.
+    fn matches<'a>(&'a self, tags: &'a str) -> impl Iterator<Item = (&'a str, bool)> {
+        tags.split(',').filter_map(|tag| {
+            let (prefix, format) = self
+                .prefixes
+                .iter()
+                .find(|(prefix, _)| tag.len() > prefix.len() && tag.starts_with(prefix.as_str()))?;
+            let valid = format
+                .as_ref()
+                .is_none_or(|re| re.is_match(&tag[prefix.len()..]));
+            Some((tag, valid))
+        })
+    }
+
+    /// The well-formed identifier tags in `tags`, comma-joined.
+    pub fn find(&self, tags: &str) -> String {
+        self.matches(tags)
+            .filter(|(_, valid)| *valid)
+            .map(|(tag, _)| tag)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// A well-formed identifier tag split into its prefix and the rest,
+    /// like `("#jira-", "OPS-7")`.
+    pub fn split<'a>(&self, tag: &'a str) -> Option<(&'a str, &'a str)> {
+        let (prefix, format) = self
+            .prefixes
+            .iter()
+            .find(|(prefix, _)| tag.len() > prefix.len() && tag.starts_with(prefix.as_str()))?;
+        let (prefix, rest) = tag.split_at(prefix.len());
+        format
+            .as_ref()
+            .is_none_or(|re| re.is_match(rest))
+            .then_some((prefix, rest))
+    }
+
+    /// Tags in `tags` that carry an identifier prefix but not its format.
+    pub fn invalid<'a>(&'a self, tags: &'a str) -> Vec<&'a str> {
+        self.matches(tags)
+            .filter(|(_, valid)| !valid)
+            .map(|(tag, _)| tag)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers() {
+        let formats = BTreeMap::from([
+            ("#jira-".to_string(), r"[A-Z]+-\d+".to_string()),
+            ("#gh-".to_string(), String::new()),
+        ]);
+        let identifiers = Identifiers::new(&formats).unwrap();
+        let tags = "#dev,#gh-42,#jira-OPS-7,#jira-ops";
+        assert_eq!("#gh-42,#jira-OPS-7", identifiers.find(tags));
+        assert_eq!(vec!["#jira-ops"], identifiers.invalid(tags));
+        assert_eq!("", identifiers.find("#pbi-12"));
+        assert_eq!(Some(("#jira-", "OPS-7")), identifiers.split("#jira-OPS-7"));
+        assert_eq!(None, identifiers.split("#jira-ops"));
+
+        let identifiers = Identifiers::default();
+        assert_eq!("#pbi-12", identifiers.find("#dev,#pbi-12"));
+        assert_eq!(vec!["#pbi-x"], identifiers.invalid("#pbi-x"));
+        assert!(Identifiers::new(&BTreeMap::from([("jira-".to_string(), String::new())])).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+// src/import.rs
+
+use crate::duration::{TimeDuration, parse_duration};
+use crate::json::{self, Json};
+use crate::parser::{Syntax, TimeEntry, extract_tags};
+use csv::ReaderBuilder;
+use jiff::Timestamp;
+use jiff::civil::Date;
+use jiff::tz::TimeZone;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Maps the columns of an external tracker's CSV export onto entries. The
+/// defaults match a Toggl Track detailed export.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ImportMap {
+    /// Name reported in the `source` column for imported entries.
+    pub source: String,
+    /// Column holding the duration, as `HH:MM:SS`, `HH:MM`, or `1h30m`.
+    pub duration: String,
+    /// Columns whose comma-separated values each become a tag.
+    pub tags: Vec<String>,
+    /// Column scanned for inline `#tags`, like a note's task text.
+    pub description: Option<String>,
+    /// Column holding the entry's date, starting with `YYYY-MM-DD`. A map
+    /// file without one leaves entries undated.
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+impl Default for ImportMap {
+    fn default() -> Self {
+        ImportMap {
+            source: "import".to_string(),
+            duration: "Duration".to_string(),
+            tags: vec!["Tags".to_string()],
+            description: Some("Description".to_string()),
+            date: Some("Start date".to_string()),
+        }
+    }
+}
+
+impl ImportMap {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read import map {}: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| format!("invalid import map {}: {}", path.display(), e).into())
+    }
+}
+
+/// Reads entries from a CSV export with a header row. `line` is the 1-based
+/// row number, counting the header.
+pub fn read_csv<R: Read>(reader: R, map: &ImportMap) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    let mut csv_reader = ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = csv_reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim() == name)
+            .ok_or_else(|| format!("missing column '{}'", name))
+    };
+    let duration_column = column(&map.duration)?;
+    let tag_columns = map
+        .tags
+        .iter()
+        .map(|name| column(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let description_column = map.description.as_deref().map(column).transpose()?;
+    let date_column = map.date.as_deref().map(column).transpose()?;
+
+    let mut entries = vec![];
+    for (index, record) in csv_reader.records().enumerate() {
+        let record = record?;
+        let line = index + 2;
+        let text = record.get(duration_column).unwrap_or_default();
+        let Some(duration) = parse_clock_duration(text) else {
+            log::warn!("row {}: cannot parse duration '{}'", line, text);
+            continue;
+        };
+
+        let description = description_column
+            .and_then(|c| record.get(c))
+            .unwrap_or_default();
+        let tags = tag_columns
+            .iter()
+            .filter_map(|&c| record.get(c))
+            .flat_map(|value| value.split(','));
+        let date = date_column
+            .and_then(|c| record.get(c))
+            .and_then(|d| d.get(..10))
+            .and_then(|d| d.parse().ok());
+        entries.push(imported(description, tags, duration, date, line));
+    }
+    Ok(entries)
+}
+
+/// Reads entries from a Toggl Track `/api/v9/me/time_entries` response,
+/// dated by when they started in `tz`. Running entries, which have a
+/// negative duration, are skipped. `line` is the 1-based position in the
+/// response.
+pub fn read_toggl(body: &str, tz: &TimeZone) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    let Json::Array(items) = json::parse(body)? else {
+        return Err("expected an array of time entries".into());
+    };
+    let mut entries = vec![];
+    for (index, item) in items.iter().enumerate() {
+        let line = index + 1;
+        let Some(Json::Number(seconds)) = item.get("duration") else {
+            return Err(format!("time entry {} has no duration", line).into());
+        };
+        if *seconds < 0.0 {
+            continue;
+        }
+        let start: Timestamp = item
+            .get("start")
+            .and_then(Json::as_str)
+            .ok_or_else(|| format!("time entry {} has no start", line))?
+            .parse()
+            .map_err(|e| format!("time entry {} has an invalid start: {}", line, e))?;
+        let tags = match item.get("tags") {
+            Some(Json::Array(tags)) => tags.iter().filter_map(Json::as_str).collect(),
+            _ => vec![],
+        };
+        entries.push(imported(
+            item.get("description")
+                .and_then(Json::as_str)
+                .unwrap_or_default(),
+            tags,
+            TimeDuration::from_seconds(*seconds as u64),
+            Some(start.to_zoned(tz.clone()).date()),
+            line,
+        ));
+    }
+    Ok(entries)
+}
+
+/// An imported entry, tagged by the `#tags` in its description and by
+/// `tags`, each made a tag with any spaces replaced by dashes.
+fn imported<'a>(
+    description: &str,
+    tags: impl IntoIterator<Item = &'a str>,
+    duration: TimeDuration,
+    date: Option<Date>,
+    line: usize,
+) -> TimeEntry {
+    let description = description.trim().to_string();
+    let mut text = description.clone();
+    for tag in tags.into_iter().map(str::trim).filter(|t| !t.is_empty()) {
+        let tag: String = tag
+            .trim_start_matches('#')
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-");
+        text.push_str(&format!(" #{}", tag));
+    }
+    TimeEntry {
+        tags: extract_tags(&text, &None, true),
+        duration,
+        text: description,
+        line,
+        date,
+        syntax: Syntax::Import,
+        ..Default::default()
+    }
+}
+
+/// Parses `HH:MM:SS` or `HH:MM`, falling back to the note syntax (`1h30m`).
+fn parse_clock_duration(text: &str) -> Option<TimeDuration> {
+    let text = text.trim();
+    if !text.contains(':') {
+        let duration = parse_duration(text);
+        return (duration.total_seconds() > 0).then_some(duration);
+    }
+    let parts: Vec<u64> = text
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let seconds = match parts[..] {
+        [hours, minutes] => hours * 3600 + minutes * 60,
+        [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+        _ => return None,
+    };
+    Some(TimeDuration::from_seconds(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    #[test]
+    fn test_read_toggl_csv() {
+        let csv = dedent!(
+            r#"
+            User,Project,Description,Tags,Start date,Duration
+            Me,Acme Corp,Weekly sync #standup,"meeting, client call",2025-01-03,01:30:00
+            Me,,Lunch,,2025-01-03,bad
+            Me,,Review,,,0:45
+            "#
+        );
+        let map = ImportMap {
+            tags: vec!["Tags".to_string(), "Project".to_string()],
+            ..Default::default()
+        };
+        let entries = read_csv(csv.as_bytes(), &map).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("#Acme-Corp,#client-call,#meeting,#standup", entries[0].tags);
+        assert_eq!(5400, entries[0].duration.total_seconds());
+        assert_eq!(2, entries[0].line);
+        assert_eq!(Some(jiff::civil::date(2025, 1, 3)), entries[0].date);
+        assert_eq!("", entries[1].tags);
+        assert_eq!(2700, entries[1].duration.total_seconds());
+        assert_eq!(None, entries[1].date);
+
+        let map: ImportMap = toml::from_str("source = \"toggl\"").unwrap();
+        assert_eq!(None, map.date);
+    }
+
+    #[test]
+    fn test_read_toggl() {
+        let body = r#"[
+            {"id":1,"description":"Weekly sync #standup","tags":["client call"],"start":"2025-01-03T23:30:00+00:00","duration":5400},
+            {"id":2,"description":null,"tags":null,"start":"2025-01-04T09:00:00Z","duration":-1736000000}
+        ]"#;
+        let entries = read_toggl(body, &TimeZone::fixed(jiff::tz::offset(1))).unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!("#client-call,#standup", entries[0].tags);
+        assert_eq!("Weekly sync #standup", entries[0].text);
+        assert_eq!(5400, entries[0].duration.total_seconds());
+        assert_eq!(Some(jiff::civil::date(2025, 1, 4)), entries[0].date);
+        assert!(read_toggl(r#"{"error":"forbidden"}"#, &TimeZone::UTC).is_err());
+    }
+
+    #[test]
+    fn test_read_csv_missing_column() {
+        let result = read_csv("Task,Time\nA,1h\n".as_bytes(), &ImportMap::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_clock_duration() {
+        assert_eq!(
+            Some(3661),
+            parse_clock_duration("1:01:01").map(|d| d.total_seconds())
+        );
+        assert_eq!(
+            Some(5400),
+            parse_clock_duration("1h30m").map(|d| d.total_seconds())
+        );
+        assert_eq!(None, parse_clock_duration("1:xx"));
+    }
+}
@@ -0,0 +1,180 @@
+// src/issues.rs
+
+use crate::identifier::Identifiers;
+use crate::parser::TimeEntry;
+use jiff::civil::Date;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+/// An identifier tag as logged in the notes, with the issue it names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    pub tag: String,
+    /// The issue key in the tracker, like `PBI-123`.
+    pub key: String,
+    /// The dates of the first and last entries logged to it, if dated.
+    pub first: Option<Date>,
+    pub last: Option<Date>,
+}
+
+/// What the tracker knows about an issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Missing,
+    Open,
+    Resolved(Date),
+}
+
+/// An identifier tag the tracker doesn't back up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub usage: Usage,
+    /// `Missing`, or the resolution of an issue time was logged to after.
+    pub status: Status,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let usage = &self.usage;
+        let date = |date: Option<Date>| date.map(|d| d.to_string()).unwrap_or_default();
+        match self.status {
+            Status::Resolved(resolved) => write!(
+                f,
+                "{} ({}): resolved {} but logged until {}",
+                usage.tag,
+                usage.key,
+                resolved,
+                date(usage.last)
+            ),
+            _ => write!(
+                f,
+                "{} ({}): no such issue, logged {} to {}",
+                usage.tag,
+                usage.key,
+                date(usage.first),
+                date(usage.last)
+            ),
+        }
+    }
+}
+
+/// The tracker key an identifier tag names: the rest of the tag when it is
+/// a key already, like `#jira-OPS-7`, else the rest after `project` or the
+/// tag's prefix, so `#pbi-123` is `PBI-123`.
+pub fn issue_key(prefix: &str, rest: &str, project: Option<&str>) -> String {
+    let re_key = Regex::new(r"^[A-Za-z][A-Za-z0-9_]*-\d+$").unwrap();
+    if re_key.is_match(rest) {
+        return rest.to_uppercase();
+    }
+    let project = project.unwrap_or(prefix.trim_start_matches('#').trim_end_matches('-'));
+    format!("{}-{}", project.to_uppercase(), rest)
+}
+
+/// The identifier tags logged in `entries`, in tag order.
+pub fn usages(
+    entries: &[TimeEntry],
+    identifiers: &Identifiers,
+    project: Option<&str>,
+) -> Vec<Usage> {
+    let mut usages: BTreeMap<&str, Usage> = BTreeMap::new();
+    for entry in entries {
+        for tag in entry.tags.split(',') {
+            let Some((prefix, rest)) = identifiers.split(tag) else {
+                continue;
+            };
+            let usage = usages.entry(tag).or_insert_with(|| Usage {
+                tag: tag.to_string(),
+                key: issue_key(prefix, rest, project),
+                first: None,
+                last: None,
+            });
+            if let Some(date) = entry.date {
+                usage.first = Some(usage.first.map_or(date, |first| first.min(date)));
+                usage.last = Some(usage.last.map_or(date, |last| last.max(date)));
+            }
+        }
+    }
+    usages.into_values().collect()
+}
+
+/// Reads an issue's status from a Jira REST API issue response.
+pub fn parse_status(body: &str) -> Status {
+    let re_resolved = Regex::new(r#""resolutiondate"\s*:\s*"(\d{4}-\d{2}-\d{2})"#).unwrap();
+    re_resolved
+        .captures(body)
+        .and_then(|cap| cap[1].parse().ok())
+        .map_or(Status::Open, Status::Resolved)
+}
+
+/// Looks up each usage's issue with `lookup`, reporting the missing ones
+/// and, when `resolved`, those with time logged after their resolution.
+pub fn check(
+    usages: Vec<Usage>,
+    resolved: bool,
+    mut lookup: impl FnMut(&str) -> Result<Status, Box<dyn Error>>,
+) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let mut findings = vec![];
+    for usage in usages {
+        let status = lookup(&usage.key)?;
+        let dangling = match status {
+            Status::Missing => true,
+            Status::Resolved(date) => resolved && usage.last.is_some_and(|last| last > date),
+            Status::Open => false,
+        };
+        if dangling {
+            findings.push(Finding { usage, status });
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_check() {
+        let identifiers = Identifiers::new(&BTreeMap::from([
+            ("#pbi-".to_string(), r"\d+".to_string()),
+            ("#jira-".to_string(), r"[A-Z]+-\d+".to_string()),
+        ]))
+        .unwrap();
+        let entries = [
+            TimeEntry::test("#dev,#pbi-7", "0m").dated(date(2025, 1, 3)),
+            TimeEntry::test("#pbi-7", "0m").dated(date(2025, 1, 6)),
+            TimeEntry::test("#pbi-124", "0m").dated(date(2025, 1, 6)),
+            TimeEntry::test("#jira-OPS-2", "0m").dated(date(2025, 1, 2)),
+        ];
+        let usages = usages(&entries, &identifiers, None);
+        let keys: Vec<&str> = usages.iter().map(|u| u.key.as_str()).collect();
+        assert_eq!(vec!["OPS-2", "PBI-124", "PBI-7"], keys);
+        assert_eq!(Some(date(2025, 1, 3)), usages[2].first);
+        assert_eq!("WEB-7", issue_key("#pbi-", "7", Some("web")));
+
+        let lookup = |key: &str| -> Result<Status, Box<dyn Error>> {
+            Ok(match key {
+                "PBI-7" => {
+                    parse_status(r#"{"fields":{"resolutiondate":"2025-01-05T10:00:00.000+0000"}}"#)
+                }
+                "OPS-2" => parse_status(r#"{"fields":{"resolutiondate":null}}"#),
+                _ => Status::Missing,
+            })
+        };
+        let findings: Vec<String> = check(usages.clone(), true, lookup)
+            .unwrap()
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "#pbi-124 (PBI-124): no such issue, logged 2025-01-06 to 2025-01-06",
+                "#pbi-7 (PBI-7): resolved 2025-01-05 but logged until 2025-01-06",
+            ],
+            findings
+        );
+        assert_eq!(1, check(usages, false, lookup).unwrap().len());
+    }
+}
@@ -0,0 +1,263 @@
+// src/json.rs
+
+/// Quotes and escapes `value` as a JSON string literal.
+pub fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Undoes a JSON string's escapes, keeping unknown ones as written.
+pub fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    Some(c) => out.push(c),
+                    None => out.push_str(&format!("\\u{}", code)),
+                }
+            }
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A parsed JSON value. Objects keep their keys in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The value of `key` in an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{}", *value as i64)
+            }
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write!(f, "{}", quote(value)),
+            Json::Array(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", values.join(","))
+            }
+            Json::Object(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(k, v)| format!("{}:{}", quote(k), v))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(","))
+            }
+        }
+    }
+}
+
+/// Parses `text` as a single JSON value.
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut parser = Parser {
+        text: text.as_bytes(),
+        at: 0,
+    };
+    let value = parser.value()?;
+    parser.space();
+    match parser.at < parser.text.len() {
+        true => Err(parser.error("trailing characters")),
+        false => Ok(value),
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("invalid JSON at byte {}: {}", self.at, message)
+    }
+
+    fn space(&mut self) {
+        while self
+            .text
+            .get(self.at)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.at += 1;
+        }
+    }
+
+    fn eat(&mut self, literal: &str) -> bool {
+        let matched = self.text[self.at..].starts_with(literal.as_bytes());
+        if matched {
+            self.at += literal.len();
+        }
+        matched
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.space();
+        match self.text.get(self.at) {
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = vec![];
+                self.space();
+                if self.eat("}") {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.space();
+                    if self.text.get(self.at) != Some(&b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.space();
+                    if !self.eat(":") {
+                        return Err(self.error("expected ':'"));
+                    }
+                    fields.push((key, self.value()?));
+                    self.space();
+                    if self.eat("}") {
+                        return Ok(Json::Object(fields));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("expected ',' or '}'"));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.at += 1;
+                let mut values = vec![];
+                self.space();
+                if self.eat("]") {
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.space();
+                    if self.eat("]") {
+                        return Ok(Json::Array(values));
+                    }
+                    if !self.eat(",") {
+                        return Err(self.error("expected ',' or ']'"));
+                    }
+                }
+            }
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') if self.eat("true") => Ok(Json::Bool(true)),
+            Some(b'f') if self.eat("false") => Ok(Json::Bool(false)),
+            Some(b'n') if self.eat("null") => Ok(Json::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.at;
+                while self
+                    .text
+                    .get(self.at)
+                    .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                {
+                    self.at += 1;
+                }
+                std::str::from_utf8(&self.text[start..self.at])
+                    .ok()
+                    .and_then(|number| number.parse().ok())
+                    .map(Json::Number)
+                    .ok_or_else(|| self.error("invalid number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Reads the string starting at the current `"`.
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.at + 1;
+        let mut escaped = false;
+        for (offset, &byte) in self.text[start..].iter().enumerate() {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => {
+                    let raw = std::str::from_utf8(&self.text[start..start + offset])
+                        .map_err(|_| self.error("invalid UTF-8"))?;
+                    self.at = start + offset + 1;
+                    return Ok(unescape(raw));
+                }
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_escapes_specials() {
+        assert_eq!(r#""a\"b\\c\n""#, quote("a\"b\\c\n"));
+        assert_eq!("a\"b\\c\n/é", unescape(r#"a\"b\\c\n\/\u00e9"#));
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = parse(r##" {"id": 7, "params": {"path": "a \"b\".md", "tags": ["#x", null, true, -1.5e1]}} "##)
+            .unwrap();
+        assert_eq!(Some(&Json::Number(7.0)), value.get("id"));
+        let params = value.get("params").unwrap();
+        assert_eq!(
+            Some("a \"b\".md"),
+            params.get("path").and_then(Json::as_str)
+        );
+        assert_eq!(
+            r##"{"path":"a \"b\".md","tags":["#x",null,true,-15]}"##,
+            params.to_string()
+        );
+        assert!(parse("{\"id\": 1,}").is_err());
+        assert!(parse("[1] 2").is_err());
+    }
+}
@@ -0,0 +1,62 @@
+// src/lib.rs
+
+pub mod activitywatch;
+pub mod aggregate;
+pub mod atomic;
+pub mod billing;
+pub mod burndown;
+pub mod changed;
+pub mod chart;
+pub mod config;
+pub mod cooccur;
+pub mod date;
+pub mod digest;
+pub mod doctor;
+pub mod duration;
+pub mod embed;
+pub mod exclusive;
+pub mod forecast;
+pub mod gaps;
+pub mod git;
+pub mod glob;
+pub mod http;
+pub mod identifier;
+pub mod import;
+pub mod issues;
+pub mod json;
+pub mod locale;
+pub mod manifest;
+pub mod markdown;
+pub mod metrics;
+pub mod notify;
+pub mod outline;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod parser;
+pub mod paths;
+pub mod properties;
+pub mod query;
+pub mod reconcile;
+pub mod report;
+pub mod retag;
+pub mod rpc;
+pub mod runs;
+pub mod scaffold;
+pub mod scan;
+pub mod schema;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod session;
+pub mod sprint;
+#[cfg(feature = "sql")]
+pub mod sql;
+pub mod stats;
+pub mod streak;
+pub mod suggest;
+pub mod tagtree;
+pub mod targets;
+pub mod timesheet;
+pub mod timings;
+pub mod vault;
+pub mod verify;
+pub mod writeback;
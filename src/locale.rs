@@ -0,0 +1,277 @@
+// src/locale.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use jiff::civil::Date;
+use std::fmt;
+use std::str::FromStr;
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const ENGLISH_WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Conventions for numbers, dates, and names in rendered reports like the
+/// digest and charts. Data formats like CSV and JSON keep ISO dates and
+/// `.` decimals whatever the locale, so other tools can read them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    /// The tag it was chosen by, like `de-DE`, or `en` for the default.
+    pub tag: &'static str,
+    decimal: char,
+    /// A strftime pattern for dates.
+    date: &'static str,
+    months: [&'static str; 12],
+    /// Abbreviated weekday names from Monday.
+    weekdays: [&'static str; 7],
+}
+
+/// The locales `--locale` accepts, the first being the default: English
+/// names with ISO dates.
+const LOCALES: [Locale; 8] = [
+    Locale {
+        tag: "en",
+        decimal: '.',
+        date: "%Y-%m-%d",
+        months: ENGLISH_MONTHS,
+        weekdays: ENGLISH_WEEKDAYS,
+    },
+    Locale {
+        tag: "en-US",
+        decimal: '.',
+        date: "%m/%d/%Y",
+        months: ENGLISH_MONTHS,
+        weekdays: ENGLISH_WEEKDAYS,
+    },
+    Locale {
+        tag: "en-GB",
+        decimal: '.',
+        date: "%d/%m/%Y",
+        months: ENGLISH_MONTHS,
+        weekdays: ENGLISH_WEEKDAYS,
+    },
+    Locale {
+        tag: "de-DE",
+        decimal: ',',
+        date: "%d.%m.%Y",
+        months: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        weekdays: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    },
+    Locale {
+        tag: "fr-FR",
+        decimal: ',',
+        date: "%d/%m/%Y",
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        weekdays: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    },
+    Locale {
+        tag: "es-ES",
+        decimal: ',',
+        date: "%d/%m/%Y",
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        weekdays: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+    },
+    Locale {
+        tag: "it-IT",
+        decimal: ',',
+        date: "%d/%m/%Y",
+        months: [
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+        weekdays: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+    },
+    Locale {
+        tag: "nl-NL",
+        decimal: ',',
+        date: "%d-%m-%Y",
+        months: [
+            "januari",
+            "februari",
+            "maart",
+            "april",
+            "mei",
+            "juni",
+            "juli",
+            "augustus",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        weekdays: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+    },
+];
+
+impl Locale {
+    /// A number written with `.` decimals, like `1.5`, in the locale's
+    /// convention.
+    pub fn number(&self, number: &str) -> String {
+        number.replace('.', &self.decimal.to_string())
+    }
+
+    /// A duration in `unit`, with the locale's decimal separator.
+    pub fn duration(&self, duration: &TimeDuration, unit: DurationUnit) -> String {
+        self.number(&unit.format(duration))
+    }
+
+    pub fn date(&self, date: Date) -> String {
+        date.strftime(self.date).to_string()
+    }
+
+    /// The month and year of `date`, like `Januar 2025`.
+    pub fn month(&self, date: Date) -> String {
+        format!("{} {}", self.months[date.month() as usize - 1], date.year())
+    }
+
+    /// The abbreviated weekday of `date`, like `Mo`.
+    pub fn weekday(&self, date: Date) -> &'static str {
+        self.weekdays[date.weekday().to_monday_zero_offset() as usize]
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        LOCALES[0]
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Reads a tag like `de-DE`, `de_DE`, or just the language, `de`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s.replace('_', "-");
+        LOCALES
+            .iter()
+            .find(|locale| locale.tag.eq_ignore_ascii_case(&wanted))
+            .or_else(|| {
+                LOCALES.iter().find(|locale| {
+                    locale
+                        .tag
+                        .split('-')
+                        .next()
+                        .unwrap()
+                        .eq_ignore_ascii_case(&wanted)
+                })
+            })
+            .copied()
+            .ok_or_else(|| {
+                let tags: Vec<&str> = LOCALES.iter().map(|locale| locale.tag).collect();
+                format!(
+                    "unknown locale '{}'; expected one of {}",
+                    s,
+                    tags.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_locale() {
+        let day = date(2025, 1, 6);
+        let english = Locale::default();
+        assert_eq!(
+            ("2025-01-06", "January 2025", "Mon"),
+            (
+                english.date(day).as_str(),
+                english.month(day).as_str(),
+                english.weekday(day)
+            )
+        );
+        let german: Locale = "de".parse().unwrap();
+        assert_eq!("de-DE", german.to_string());
+        assert_eq!(
+            ("06.01.2025", "Januar 2025", "Mo"),
+            (
+                german.date(day).as_str(),
+                german.month(day).as_str(),
+                german.weekday(day)
+            )
+        );
+        assert_eq!(
+            "1,5",
+            german.duration(&parse_duration("1h30m"), DurationUnit::Hours)
+        );
+        assert_eq!("01/06/2025", "en_us".parse::<Locale>().unwrap().date(day));
+        assert_eq!(
+            "dim.",
+            "fr-FR"
+                .parse::<Locale>()
+                .unwrap()
+                .weekday(date(2025, 1, 12))
+        );
+        assert!("xx-XX".parse::<Locale>().is_err());
+    }
+}
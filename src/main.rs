@@ -1,63 +1,1158 @@
 // src/main.rs
 
-use clap::{Arg, ArgAction, Command};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use csv::Writer;
-use log::{LevelFilter, debug};
+use jiff::civil::{Date, Time};
+use jiff::tz::TimeZone;
+use log::{LevelFilter, debug, info, warn};
 use regex::Regex;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
-use std::ffi::OsStr;
 use std::fs;
 use std::io::Write;
+use std::net::TcpListener;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use timetracker::activitywatch;
+use timetracker::aggregate;
+use timetracker::atomic::AtomicFile;
+use timetracker::billing::{self, Rounding};
+use timetracker::burndown;
+use timetracker::changed::{Since, retain_changed};
+use timetracker::chart;
+use timetracker::config::{Config, Profile, Report};
+use timetracker::cooccur::{CooccurrenceMatrix, Metric};
+use timetracker::date::{
+    DateSource, date_from_path, frontmatter_date, is_weekend, monday, week_start, window_start,
+};
+use timetracker::digest;
+use timetracker::doctor;
+use timetracker::duration::{DurationUnit, TimeDuration, Units, parse_duration};
+use timetracker::embed;
+use timetracker::exclusive::Exclusives;
+use timetracker::forecast;
+use timetracker::gaps::{self, WorkHours};
+use timetracker::git;
+use timetracker::glob::Glob;
+use timetracker::http;
+use timetracker::identifier::Identifiers;
+use timetracker::import::{self, ImportMap};
+use timetracker::issues;
+use timetracker::json::{self, Json};
+use timetracker::locale::Locale;
+use timetracker::manifest::Manifest;
+use timetracker::markdown;
+use timetracker::metrics;
+use timetracker::notify;
+#[cfg(feature = "parquet")]
+use timetracker::parquet;
+use timetracker::parser::{
+    Priority, Syntax, TimeEntry, coverage, has_tag, lines, mentions_time_fields, parse_coverage,
+    parse_estimates, parse_time_entries, rewrite_legacy_durations, scan_note,
+};
+use timetracker::paths::PathStyle;
+use timetracker::properties::{Value, front_matter, frontmatter_tags, parse_properties};
+use timetracker::query::{self, Group, Query};
+use timetracker::reconcile::{self, Action};
+use timetracker::report::{Cell, CsvWriter, JsonWriter, MarkdownWriter, ReportWriter, Summary};
+use timetracker::retag::retag;
+use timetracker::rpc::{self, RpcError};
+use timetracker::runs;
+use timetracker::scaffold;
+use timetracker::scan::{
+    MAX_FILE_SIZE, collect_entries, dedup_entries, is_archive, open_note, overlapping_dirs,
+    parse_size, read_file_list, read_note, skip_reason,
+};
+use timetracker::schema::{self, Field};
+#[cfg(feature = "scripting")]
+use timetracker::script::RowHook;
+use timetracker::session::{CloseAt, close_session};
+use timetracker::sprint::Sprints;
+#[cfg(feature = "sql")]
+use timetracker::sql;
+use timetracker::stats::Stats;
+use timetracker::streak::{self, Goal};
+use timetracker::suggest::{Suggester, add_tags};
+use timetracker::tagtree;
+use timetracker::targets::{self, Target};
+use timetracker::timesheet::Timesheet;
+use timetracker::timings::{self, Phase, Timed};
+use timetracker::vault;
+use timetracker::verify;
+use timetracker::writeback::{Backup, Change, Show, Writeback};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let matches = Command::new("timetracker")
+/// The options recorded as filters in a `--manifest`.
+const MANIFEST_FILTERS: [&str; 17] = [
+    "profile",
+    "changed_since",
+    "path_filter",
+    "tag",
+    "priority",
+    "checkbox_states",
+    "match",
+    "exclude_match",
+    "since",
+    "until",
+    "last",
+    "require_tags",
+    "include_archived",
+    "include_ignored",
+    "exclude_weekends",
+    "only_weekends",
+    "granularity",
+];
+
+#[cfg(not(feature = "parquet"))]
+const REPORT_FORMATS: [&str; 6] = ["csv", "json", "dataview", "timesheet", "full", "full-json"];
+#[cfg(feature = "parquet")]
+const REPORT_FORMATS: [&str; 7] = [
+    "csv",
+    "json",
+    "dataview",
+    "timesheet",
+    "full",
+    "full-json",
+    "parquet",
+];
+/// Columns of `--format full`, one row per entry and tag.
+const FULL_COLUMNS: [&str; 13] = [
+    "id",
+    "date",
+    "tag",
+    "seconds",
+    "priority",
+    "status",
+    "text",
+    "heading",
+    "file",
+    "line",
+    "vault",
+    "source",
+    "source_syntax",
+];
+const ENTRY_COLUMNS: [&str; 16] = [
+    "tags",
+    "duration",
+    "file",
+    "priority",
+    "source",
+    "date",
+    "weekday",
+    "text",
+    "id",
+    "identifier",
+    "source_syntax",
+    "h1",
+    "h2",
+    "h3",
+    "parents",
+    "author",
+];
+/// `source` column value for entries parsed from notes.
+const NOTES_SOURCE: &str = "obsidian";
+const COOCCUR_FORMATS: [&str; 2] = ["csv", "json"];
+const BURNDOWN_FORMATS: [&str; 2] = ["csv", "chart"];
+const BILLING_FORMATS: [&str; 2] = ["csv", "json"];
+const STATS_FORMATS: [&str; 2] = ["text", "json"];
+const QUERY_FORMATS: [&str; 2] = ["csv", "json"];
+#[cfg(feature = "sql")]
+const SQL_FORMATS: [&str; 2] = ["csv", "json"];
+
+fn cli() -> Command {
+    let command = Command::new("timetracker")
         .version("1.0")
         .about("Parses Markdown journals for time tracking info")
         .arg(
             Arg::new("directory")
                 .short('d')
                 .long("dir")
-                .help("Directory to search")
+                .help("Directory or zip archive to search")
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("files_from")
+                .long("files-from")
+                .help("Read newline-separated Markdown file paths from FILE ('-' for stdin)")
+                .value_name("FILE")
+                .allow_hyphen_values(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("changed_since")
+                .long("changed-since")
+                .help("Only read notes modified since a time (YYYY-MM-DD[THH:MM] or RFC 3339) or git revision")
+                .value_name("WHEN")
+                .value_parser(|s: &str| s.parse::<Since>())
+                .global(true),
+        )
+        .arg(
+            Arg::new("path_filter")
+                .long("path-filter")
+                .help("Only read notes whose path matches GLOB, like 'Journals/2025/**' (repeatable)")
+                .value_name("GLOB")
+                .value_parser(|s: &str| s.parse::<Glob>())
                 .action(ArgAction::Append)
-                .required(true),
+                .global(true),
         )
         .arg(
             Arg::new("recursive")
                 .short('r')
                 .long("recursive")
                 .help("Recurse into subdirectories")
-                .action(ArgAction::SetTrue),
+                .action(ArgAction::SetTrue)
+                .global(true),
         )
         .arg(
             Arg::new("verbosity")
                 .short('v')
                 .long("verbosity")
                 .help("Set log verbosity level (error, warn, info, debug, trace)")
-                .value_parser(["error", "warn", "info", "debug", "trace"]),
+                .value_parser(["error", "warn", "info", "debug", "trace"])
+                .global(true),
         )
         .arg(
             Arg::new("output")
                 .short('o')
                 .long("output")
-                .help("Output CSV file")
-                .value_name("FILE"),
+                .help("Write to FILE (- for stdout); repeat with FILE:FORMAT to write several formats from one scan")
+                .value_name("FILE")
+                .value_parser(|s: &str| Destination::from_str(s))
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("path_style")
+                .long("path-style")
+                .help("Show file paths as basename, relative to the searched directory, absolute, or abbrev (folders shortened)")
+                .value_name("STYLE")
+                .value_parser(|s: &str| s.parse::<PathStyle>())
+                .global(true),
+        )
+        .arg(
+            Arg::new("plain_text")
+                .long("plain-text")
+                .help("Strip Markdown (checkboxes, emphasis, links, wikilinks, inline code) from the text column")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("locale")
+                .long("locale")
+                .help("Write numbers, dates, and month and weekday names in the digest, stats text, and charts like LOCALE, e.g. de-DE")
+                .value_name("LOCALE")
+                .value_parser(|s: &str| s.parse::<Locale>())
+                .global(true),
+        )
+        .arg(
+            Arg::new("rate_limit")
+                .long("rate-limit")
+                .help("Send at most N requests a second to web services like Jira, webhooks, or ActivityWatch")
+                .value_name("N")
+                .value_parser(parse_rate)
+                .global(true),
+        )
+        .arg(
+            Arg::new("timings")
+                .long("timings")
+                .help("Print where the run's time went (discovery, io, parsing, aggregation, writing) to stderr")
+                .action(ArgAction::SetTrue)
+                .global(true),
         )
         .arg(
             Arg::new("basename")
                 .long("basename")
-                .help("Print only the basename of the file path")
-                .action(ArgAction::SetTrue),
+                .help("Print only the basename of the file path (same as --path-style basename)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("path_style")
+                .global(true),
+        )
+        .arg(
+            Arg::new("close_open_sessions")
+                .long("close-open-sessions")
+                .help("Close timeStarted sessions without an end at WHEN ('now' or HH:MM on the note's date)")
+                .value_name("WHEN")
+                .value_parser(|s: &str| s.parse::<CloseAt>())
+                .global(true),
+        )
+        .arg(
+            Arg::new("duration_unit")
+                .long("duration-unit")
+                .help("Render durations as compact text (1h30m) or a single number in hours, minutes, or seconds")
+                .value_name("UNIT")
+                .value_parser(["compact", "hours", "minutes", "seconds"])
+                .default_value("compact")
+                .global(true),
+        )
+        .arg(
+            Arg::new("tag")
+                .short('t')
+                .long("tag")
+                .help("Only include entries with this tag (repeatable)")
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("priority")
+                .long("priority")
+                .help("Only include entries with this priority (repeatable; 'none' for no priority)")
+                .value_parser(["highest", "high", "medium", "low", "lowest", "none"])
+                .action(ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("granularity")
+                .long("granularity")
+                .help("Precision durations are kept at; minute rounds away the seconds of every entry")
+                .value_parser(["second", "minute"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("granularity_rounding")
+                .long("granularity-rounding")
+                .help("How --granularity rounds durations [default: nearest]")
+                .value_name("MODE")
+                .value_parser(["up", "nearest", "down"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("checkbox_states")
+                .long("checkbox-states")
+                .help("Only include tasks with these checkbox characters or todo, done, in-progress, cancelled (comma-separated)")
+                .value_name("STATES")
+                .value_parser(|s: &str| parse_checkbox_state(s))
+                .value_delimiter(',')
+                .global(true),
+        )
+        .arg(
+            Arg::new("date_source")
+                .long("date-source")
+                .help("Where an entry's date comes from: the Tasks completion date, the file name, or the note's frontmatter")
+                .value_name("SOURCE")
+                .value_parser(["completion", "file", "frontmatter"])
+                .default_value("file")
+                .global(true),
+        )
+        .arg(
+            Arg::new("match")
+                .long("match")
+                .help("Only include entries whose task text matches REGEX")
+                .value_name("REGEX")
+                .value_parser(|s: &str| Regex::new(s))
+                .global(true),
+        )
+        .arg(
+            Arg::new("exclude_match")
+                .long("exclude-match")
+                .help("Exclude entries whose task text matches REGEX")
+                .value_name("REGEX")
+                .value_parser(|s: &str| Regex::new(s))
+                .global(true),
+        )
+        .arg(
+            Arg::new("since")
+                .long("since")
+                .help("Only include entries dated on or after DATE (YYYY-MM-DD)")
+                .value_name("DATE")
+                .value_parser(|s: &str| s.parse::<Date>())
+                .global(true),
+        )
+        .arg(
+            Arg::new("until")
+                .long("until")
+                .help("Only include entries dated on or before DATE (YYYY-MM-DD)")
+                .value_name("DATE")
+                .value_parser(|s: &str| s.parse::<Date>())
+                .global(true),
+        )
+        .arg(
+            Arg::new("last")
+                .long("last")
+                .help("Only include entries from the last N days or weeks, including today (e.g. 7d, 2w)")
+                .value_name("WINDOW")
+                .conflicts_with_all(["since", "until"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("sprint")
+                .long("sprint")
+                .help("Only include entries from the configured sprint NAME (e.g. 2025.03)")
+                .value_name("NAME")
+                .conflicts_with_all(["since", "until", "last"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("require_tags")
+                .long("require-tags")
+                .help("Fail on entries without any tags")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("default_tag")
+                .global(true),
+        )
+        .arg(
+            Arg::new("default_tag")
+                .long("default-tag")
+                .help("Tag given to entries without any tags, like #untagged")
+                .value_name("TAG")
+                .value_parser(|s: &str| parse_tag(s))
+                .global(true),
+        )
+        .arg(
+            Arg::new("embeds")
+                .long("embeds")
+                .help("Read notes embedded with ![[...]] as part of the note embedding them")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("inherit_frontmatter_tags")
+                .long("inherit-frontmatter-tags")
+                .help("Give every entry in a note the tags in its frontmatter")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("legacy_durations")
+                .long("legacy-durations")
+                .help("Also read a list item's trailing parenthetical duration, like '- did the thing (2h15m) #tag'")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("include_archived")
+                .long("include-archived")
+                .help("Include notes whose frontmatter has archived: true")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("max_file_size")
+                .long("max-file-size")
+                .help("Skip notes larger than SIZE, like 10M [default: 32M]")
+                .value_name("SIZE")
+                .value_parser(|s: &str| parse_size(s))
+                .global(true),
+        )
+        .arg(
+            Arg::new("include_ignored")
+                .long("include-ignored")
+                .help("Include notes matching the vault's Excluded files setting")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("authors")
+                .long("authors")
+                .help("Attribute each entry to the git author of its line")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("exclude_weekends")
+                .long("exclude-weekends")
+                .help("Exclude entries dated on a Saturday or Sunday")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("only_weekends")
+                .global(true),
+        )
+        .arg(
+            Arg::new("only_weekends")
+                .long("only-weekends")
+                .help("Only include entries dated on a Saturday or Sunday")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("week")
+                .long("week")
+                .help("Week for --format timesheet, as YYYY-Www or any YYYY-MM-DD in it (defaults to this week)")
+                .value_name("WEEK")
+                .value_parser(|s: &str| week_start(s))
+                .global(true),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .help("Config file (defaults to ~/.config/timetracker/config.toml)")
+                .value_name("FILE")
+                .allow_hyphen_values(true)
+                .global(true),
         )
         .arg(
-            Arg::new("accumulate")
-                .short('a')
-                .long("accumulate")
-                .help("Accumulate timeTracked values associated with tags")
-                .action(ArgAction::SetTrue),
+            Arg::new("profile")
+                .short('p')
+                .long("profile")
+                .help("Use the named [profile.NAME] settings from the config file")
+                .value_name("NAME")
+                .global(true),
+        )
+        .args(report_command_args())
+        .subcommand(
+            Command::new("report")
+                .about("Write the report, as running without a subcommand does")
+                .args(report_command_args()),
+        )
+        .subcommand(
+            Command::new("cooccur")
+                .about("Report how often and how long tags appear together")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_parser(COOCCUR_FORMATS)
+                        .default_value("csv"),
+                )
+                .arg(
+                    Arg::new("metric")
+                        .long("metric")
+                        .help("Matrix cell value for CSV output")
+                        .value_parser(["count", "duration"])
+                        .default_value("count"),
+                ),
+        )
+        .subcommand(
+            Command::new("burndown")
+                .about("Report remaining estimate vs cumulative actual time per day for --tag")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_parser(BURNDOWN_FORMATS)
+                        .default_value("csv"),
+                ),
+        )
+        .subcommand(
+            Command::new("forecast")
+                .about("Project when the remaining estimate on --tag will be done, from the pace of its tracked time"),
+        )
+        .subcommand(
+            Command::new("digest")
+                .about("Render a month's top tags, busiest days, and comparison with the month before as an HTML page")
+                .arg(
+                    Arg::new("month")
+                        .long("month")
+                        .help("Month to digest, as YYYY-MM [default: last month]")
+                        .value_name("MONTH")
+                        .value_parser(|s: &str| {
+                            format!("{}-01", s)
+                                .parse::<Date>()
+                                .map_err(|_| format!("invalid month '{}'; expected YYYY-MM", s))
+                        }),
+                ),
+        )
+        .subcommand(
+            Command::new("billing")
+                .about("Report billable time and amounts for tags with a configured rate")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_parser(BILLING_FORMATS)
+                        .default_value("csv"),
+                )
+                .arg(
+                    Arg::new("currency")
+                        .long("currency")
+                        .help("Convert amounts to CODE using the config's exchange_rates")
+                        .value_name("CODE"),
+                )
+                .arg(
+                    Arg::new("round")
+                        .long("round")
+                        .help("Round each billed entry to a multiple of DURATION (e.g. 15m)")
+                        .value_name("DURATION")
+                        .value_parser(|s: &str| parse_increment(s)),
+                )
+                .arg(
+                    Arg::new("round-mode")
+                        .long("round-mode")
+                        .help("Which way --round rounds")
+                        .value_parser(["up", "nearest", "down"])
+                        .default_value("up")
+                        .requires("round"),
+                )
+                .arg(
+                    Arg::new("audit")
+                        .long("audit")
+                        .help("Write each rounded entry's raw and rounded duration to FILE as CSV")
+                        .value_name("FILE")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .requires("round"),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check the notes against the vault's conventions and list problems")
+                .arg(
+                    Arg::new("coverage")
+                        .long("coverage")
+                        .help("Also list how many time field lines parsed in each file")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Report entries from external CSV exports (e.g. Toggl) merged with the notes' entries")
+                .arg(
+                    Arg::new("csv")
+                        .long("csv")
+                        .help("CSV export to import (repeatable)")
+                        .value_name("FILE")
+                        .action(ArgAction::Append)
+                        .required_unless_present("activitywatch"),
+                )
+                .arg(
+                    Arg::new("activitywatch")
+                        .long("activitywatch")
+                        .help("Compare each day's tracked time with the active time a local ActivityWatch server observed, listing unlogged blocks")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["csv", "map", "columns", "accumulate", "group_by", "gaps", "chart"]),
+                )
+                .arg(
+                    Arg::new("aw_url")
+                        .long("aw-url")
+                        .help("ActivityWatch server to read events from")
+                        .value_name("URL")
+                        .default_value("http://localhost:5600"),
+                )
+                .arg(
+                    Arg::new("min_block")
+                        .long("min-block")
+                        .help("Only list unlogged blocks longer than DURATION")
+                        .value_name("DURATION")
+                        .value_parser(|s: &str| parse_increment(s))
+                        .default_value("15m"),
+                )
+                .arg(
+                    Arg::new("map")
+                        .long("map")
+                        .help("TOML file mapping CSV columns to entries (defaults to Toggl's columns)")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_parser(REPORT_FORMATS)
+                        .default_value("csv"),
+                )
+                .arg(
+                    Arg::new("columns")
+                        .long("columns")
+                        .help("Comma-separated columns to output for each entry")
+                        .value_parser(|s: &str| parse_column(s))
+                        .value_delimiter(',')
+                        .default_value("tags,duration,file,source")
+                        .conflicts_with("accumulate"),
+                )
+                .args(report_args()),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Filter and group entries with a query like 'tag = #pbi-1 AND date >= 2025-01-01 GROUP BY week'")
+                .arg(
+                    Arg::new("query")
+                        .help("Conditions on tag, date, duration, priority, text, or file, joined with AND/OR, then an optional GROUP BY")
+                        .value_name("QUERY")
+                        .value_parser(|s: &str| s.parse::<Query>())
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_parser(QUERY_FORMATS)
+                        .default_value("csv"),
+                ),
+        )
+        .subcommand(
+            Command::new("retag")
+                .about("Rename a tag across the notes, showing the changed lines unless --write is given")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Tag to replace, e.g. '#meetins'")
+                        .value_name("TAG")
+                        .value_parser(parse_tag)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .help("Replacement tag, e.g. '#meetings'")
+                        .value_name("TAG")
+                        .value_parser(parse_tag)
+                        .required(true),
+                )
+                .args(writeback_args()),
+        )
+        .subcommand(
+            Command::new("suggest-tags")
+                .about("Suggest tags for untagged entries from the tagged entries with the most similar text, showing the changed lines unless --write is given")
+                .arg(
+                    Arg::new("min_similarity")
+                        .long("min-similarity")
+                        .help("Share of words, from 0 to 1, an entry must have in common with a tagged one to take its tags")
+                        .value_name("SCORE")
+                        .value_parser(parse_similarity)
+                        .default_value("0.3"),
+                )
+                .args(writeback_args()),
+        )
+        .subcommand(
+            Command::new("notify")
+                .about("POST a summary of the entries, rendered through a JSON template, to a webhook")
+                .arg(
+                    Arg::new("webhook")
+                        .long("webhook")
+                        .help("http:// URL to POST the payload to")
+                        .value_name("URL")
+                        .required_unless_present("dry_run"),
+                )
+                .arg(
+                    Arg::new("payload_template")
+                        .long("payload-template")
+                        .help("JSON file with {{summary}}, {{total}}, {{entries}}, {{tags}}, {{since}}, and {{until}} placeholders")
+                        .value_name("FILE")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .help("Print the payload instead of sending it")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("retries")
+                        .long("retries")
+                        .help("Times to retry a POST that never reached the webhook or was answered 429, backing off exponentially")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32))
+                        .default_value("3"),
+                ),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Serve tracked time as Prometheus metrics on /metrics, rescanning the notes on every scrape")
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .help("Address to listen on")
+                        .value_name("ADDRESS")
+                        .default_value("127.0.0.1:9184"),
+                )
+                .arg(
+                    Arg::new("on_change")
+                        .long("on-change")
+                        .help("Run COMMAND through the shell after each scrape whose metrics differ from the last")
+                        .value_name("COMMAND"),
+                ),
+        )
+        .subcommand(
+            Command::new("rpc")
+                .about("Answer JSON-RPC requests on stdin, a line each, with parsed notes and summaries for an editor plugin"),
+        )
+        .subcommand(
+            Command::new("bundle")
+                .about("Write every [[report]] defined in the config from one scan of the notes"),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Summarize the scanned notes: files, entries, tracked time, gaps, and top tags")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .value_parser(STATS_FORMATS)
+                        .default_value("text"),
+                )
+                .arg(
+                    Arg::new("top")
+                        .long("top")
+                        .help("Number of top tags to list")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("streak")
+                .about("Report the current and longest streaks of weeks meeting a weekly goal")
+                .arg(
+                    Arg::new("goal")
+                        .long("goal")
+                        .help("Weekly target like '#sideproject >= 5h/week' (repeatable)")
+                        .value_name("GOAL")
+                        .value_parser(|s: &str| s.parse::<Goal>())
+                        .action(ArgAction::Append)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("targets")
+                .about("Report each period's time against the tag budgets in the config's targets, as met, missed, or pending")
+                .arg(
+                    Arg::new("target")
+                        .long("target")
+                        .help("Budget like '#meetings < 6h/week', replacing a configured one for the tag (repeatable)")
+                        .value_name("TARGET")
+                        .value_parser(|s: &str| s.parse::<Target>())
+                        .action(ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("sessions")
+                .about("List runs of consecutive working days with time on each identifier tag, with their totals and the gaps between them"),
         )
-        .get_matches();
+        .subcommand(
+            Command::new("export")
+                .about("Export data for external tools")
+                .arg(
+                    Arg::new("tags_json")
+                        .long("tags-json")
+                        .help("Print every tag as a JSON tree of nested tags with their totals")
+                        .action(ArgAction::SetTrue)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("gaps")
+                .about("List working days with less tracked time than a threshold, or no note at all")
+                .arg(
+                    Arg::new("below")
+                        .long("below")
+                        .help("List weekdays with less than DURATION tracked")
+                        .value_name("DURATION")
+                        .value_parser(|s: &str| parse_increment(s))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("verify-issues")
+                .about("Check that every identifier tag names an existing Jira issue")
+                .arg(
+                    Arg::new("jira_url")
+                        .long("jira-url")
+                        .help("Base https:// URL of the Jira server; the API token is read from $JIRA_TOKEN")
+                        .value_name("URL")
+                        .value_parser(parse_api_url)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("project")
+                        .long("project")
+                        .help("Project key for identifiers that are bare numbers, like #pbi-123 (defaults to the tag prefix)")
+                        .value_name("KEY"),
+                )
+                .arg(
+                    Arg::new("check_resolved")
+                        .long("check-resolved")
+                        .help("Also report issues resolved before the last time logged to them")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Compare totals per tag set with a saved report and fail on any difference")
+                .arg(
+                    Arg::new("baseline")
+                        .long("baseline")
+                        .help("CSV from --accumulate or --format timesheet to compare with")
+                        .value_name("FILE")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("reconcile")
+                .about("Plan a push by matching worklogs per day and tag set with the remote's: create missing, skip existing")
+                .arg(
+                    Arg::new("remote")
+                        .long("remote")
+                        .help("CSV export of the worklogs already on the remote")
+                        .value_name("FILE")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required_unless_present("toggl_url"),
+                )
+                .arg(
+                    Arg::new("toggl_url")
+                        .long("toggl-url")
+                        .help("Fetch the remote worklogs from this Toggl Track server instead, like https://api.track.toggl.com; the API token is read from $TOGGL_API_TOKEN")
+                        .value_name("URL")
+                        .value_parser(parse_api_url)
+                        .conflicts_with_all(["remote", "map"]),
+                )
+                .arg(
+                    Arg::new("map")
+                        .long("map")
+                        .help("TOML file mapping the export's columns, as for import (defaults to Toggl's, dated by 'Start date')")
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("update")
+                        .long("update")
+                        .help("Update remote worklogs whose duration differs, instead of skipping them")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("auto")
+                        .long("auto")
+                        .help("Accept every create and update without asking")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("new-day")
+                .about("Create a daily note with a Work on [[N]] section for each active PBI")
+                .arg(
+                    Arg::new("pbi")
+                        .long("pbi")
+                        .help("PBI number to add a section for; repeat for several")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64))
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .help("Note to start from, with {{date}} and {{title}} replaced by the date")
+                        .value_name("FILE")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("date")
+                        .long("date")
+                        .help("Date of the note, YYYY-MM-DD (defaults to today)")
+                        .value_name("DATE")
+                        .value_parser(|s: &str| s.parse::<Date>()),
+                ),
+        );
+
+    #[cfg(feature = "scripting")]
+    let command = command.arg(
+        Arg::new("script")
+            .long("script")
+            .help("Rhai script whose process(entry) function can modify or drop each entry")
+            .value_name("FILE")
+            .global(true),
+    );
+
+    #[cfg(feature = "sql")]
+    let command = command.subcommand(
+        Command::new("sql")
+            .about("Run SQL against the entries, loaded into an in-memory SQLite table named entries")
+            .arg(
+                Arg::new("sql")
+                    .help("Query over the --format full columns, like 'SELECT tag, sum(seconds) FROM entries GROUP BY 1'")
+                    .value_name("SQL")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("format")
+                    .long("format")
+                    .help("Output format")
+                    .value_parser(SQL_FORMATS)
+                    .default_value("csv"),
+            ),
+    );
+
+    command
+}
+
+/// The options of the subcommands that rewrite notes, read by `writeback`.
+fn writeback_args() -> [Arg; 5] {
+    [
+        Arg::new("write")
+            .long("write")
+            .help("Rewrite the files, keeping each original as FILE.bak")
+            .action(ArgAction::SetTrue),
+        Arg::new("no_backup")
+            .long("no-backup")
+            .help("Don't keep .bak copies when writing")
+            .action(ArgAction::SetTrue)
+            .requires("write"),
+        Arg::new("backup_dir")
+            .long("backup-dir")
+            .help("Keep the originals under DIR, at their paths below the searched directory, instead of as .bak files")
+            .value_name("DIR")
+            .value_parser(clap::value_parser!(PathBuf))
+            .requires("write")
+            .conflicts_with("no_backup"),
+        Arg::new("diff")
+            .long("diff")
+            .help("Show the changes as a unified diff per note")
+            .action(ArgAction::SetTrue),
+        Arg::new("json_diff")
+            .long("json-diff")
+            .help("Show the changes as a JSON array of {file, line, before, after} records")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("diff"),
+    ]
+}
+
+/// The arguments of the report written without a subcommand, or with
+/// `report` for scripts that name it.
+fn report_command_args() -> Vec<Arg> {
+    let mut args = vec![
+        Arg::new("files")
+            .help("Notes to read instead of scanning directories")
+            .value_name("FILE")
+            .value_parser(clap::value_parser!(PathBuf))
+            .action(ArgAction::Append),
+        Arg::new("format")
+            .long("format")
+            .help("Output format")
+            .value_parser(REPORT_FORMATS)
+            .default_value("csv"),
+        Arg::new("columns")
+            .long("columns")
+            .help("Comma-separated columns to output for each entry")
+            .value_parser(|s: &str| parse_column(s))
+            .value_delimiter(',')
+            .default_value("tags,duration,file")
+            .conflicts_with("accumulate"),
+    ];
+    args.extend(report_args());
+    args.extend([
+        Arg::new("schema")
+            .long("schema")
+            .help("Print the JSON Schema of the report's JSON output instead of the report")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("chart"),
+        Arg::new("preview")
+            .long("preview")
+            .help("Print the first N entries parsed with their raw line, duration, tags, and date instead of the report")
+            .value_name("N")
+            .value_parser(clap::value_parser!(usize))
+            .conflicts_with_all(["accumulate", "chart", "gaps", "group_by", "schema"]),
+        Arg::new("manifest")
+            .long("manifest")
+            .help("Write the run's inputs, filters, totals, and version as JSON to FILE")
+            .value_name("FILE")
+            .allow_hyphen_values(true)
+            .conflicts_with("schema"),
+    ]);
+    args
+}
+
+/// The report options `import` takes too, for its merged entries.
+fn report_args() -> [Arg; 6] {
+    [
+        Arg::new("accumulate")
+            .short('a')
+            .long("accumulate")
+            .help("Accumulate timeTracked values associated with tags")
+            .action(ArgAction::SetTrue),
+        Arg::new("group_by")
+            .long("group-by")
+            .help("Total entries per tag, day, week, month, sprint, file, priority, hour-of-day, author, note.NAME, or field:NAME")
+            .value_name("GROUP")
+            .value_parser(|s: &str| s.parse::<Group>())
+            .conflicts_with("accumulate"),
+        Arg::new("gaps")
+            .long("gaps")
+            .help("List stretches of working hours longer than DURATION that no clocked session covers")
+            .value_name("DURATION")
+            .value_parser(|s: &str| parse_increment(s))
+            .conflicts_with_all(["accumulate", "group_by"]),
+        Arg::new("work_hours")
+            .long("work-hours")
+            .help("Working hours searched by --gaps")
+            .value_name("HH:MM-HH:MM")
+            .value_parser(|s: &str| s.parse::<WorkHours>())
+            .default_value("09:00-17:00"),
+        Arg::new("chart")
+            .long("chart")
+            .help("Draw the totals per tag, or per --group-by group, as bars in the terminal")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["accumulate", "gaps", "format"]),
+        Arg::new("no_paths")
+            .long("no-paths")
+            .help("Leave the file list out of --accumulate rows")
+            .action(ArgAction::SetTrue)
+            .requires("accumulate"),
+    ]
+}
+
+/// Settings shared by every report, resolved from the command line and the
+/// config file.
+struct Options {
+    close_at: Option<CloseAt>,
+    unit: DurationUnit,
+    tags: Vec<String>,
+    /// Priorities to include; `None` selects entries without a priority.
+    priorities: Vec<Option<Priority>>,
+    /// Checkbox characters of the tasks to include, all when empty. Entries
+    /// that aren't tasks are always included.
+    checkbox_states: Vec<char>,
+    text_match: Option<Regex>,
+    text_exclude: Option<Regex>,
+    date_source: DateSource,
+    since: Option<Date>,
+    until: Option<Date>,
+    /// `Some(true)` keeps only weekend entries, `Some(false)` drops them.
+    weekends: Option<bool>,
+    require_tags: bool,
+    default_tag: Option<String>,
+    /// The increment every entry's duration is rounded to, and how, when
+    /// coarser than a second.
+    granularity: Option<(TimeDuration, Rounding)>,
+    /// Fill in each entry's author with `git blame`.
+    authors: bool,
+    /// Splice embedded notes into the notes embedding them.
+    embeds: bool,
+    /// Read trailing `(2h)` durations of pre-convention notes.
+    legacy_durations: bool,
+    /// Add the tags in each note's frontmatter to its entries.
+    frontmatter_tags: bool,
+    /// The frontmatter property that skips a note when true, unless
+    /// `--include-archived` is given.
+    archived_key: Option<String>,
+    /// The Monday of the week reported by `--format timesheet`.
+    week: Date,
+    /// How file paths are shown, as scanned when `None`.
+    path_style: Option<PathStyle>,
+    /// Strip Markdown from the text column.
+    plain_text: bool,
+    /// How rendered reports write numbers, dates, and names.
+    locale: Locale,
+    /// The least time between two requests to a web service.
+    request_interval: Duration,
+    /// Whether large notes are memory-mapped; not by `serve` and `rpc`,
+    /// which must outlive a note truncated as it's read.
+    map_notes: bool,
+    /// The directories searched, reported as each entry's vault.
+    vaults: Vec<PathBuf>,
+    identifiers: Identifiers,
+    exclusives: Exclusives,
+    units: Units,
+    sprints: Sprints,
+    profile: Profile,
+    #[cfg(feature = "scripting")]
+    hook: Option<RowHook>,
+}
+
+/// Untagged entries reported in this run, warned about once at its end.
+static UNTAGGED: AtomicUsize = AtomicUsize::new(0);
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut command = cli();
+    let root_matches = command.get_matches_mut();
+    let matches = root_matches
+        .subcommand()
+        .map_or(&root_matches, |(_, sub_matches)| sub_matches);
+    // `report` writes the same report as no subcommand.
+    let (subcommand, report_matches) = match root_matches.subcommand() {
+        Some(("report", sub_matches)) => (None, sub_matches),
+        subcommand => (subcommand.map(|(name, _)| name), &root_matches),
+    };
 
     let level = match matches.get_one::<String>("verbosity").map(|s| s.as_str()) {
         Some("trace") => LevelFilter::Trace,
@@ -65,399 +1160,2794 @@ fn main() -> Result<(), Box<dyn Error>> {
         Some("info") => LevelFilter::Info,
         Some("warn") => LevelFilter::Warn,
         Some("error") => LevelFilter::Error,
-        _ => LevelFilter::Error,
+        _ => LevelFilter::Warn,
     };
     env_logger::Builder::new().filter_level(level).init();
+    let started = Instant::now();
+    if matches.get_flag("timings") {
+        timings::enable();
+    }
 
-    let recursive = matches.get_flag("recursive");
-    let dirs = matches.get_many::<String>("directory").unwrap();
-    let output = matches.get_one::<String>("output");
-    let use_basename = matches.get_flag("basename");
-    let accumulate = matches.get_flag("accumulate");
-
-    let mut entries = vec![];
-    for dir in dirs {
-        let path = Path::new(dir);
-        if path.is_dir() {
-            collect_entries(path, recursive, &mut entries)?;
+    let config = Config::load(matches.get_one::<String>("config").map(Path::new))?;
+    let profile = config.resolve(matches.get_one::<String>("profile").map(|s| s.as_str()))?;
+    for column in profile.column_names.keys() {
+        if !FULL_COLUMNS.contains(&column.as_str()) {
+            parse_column(column).map_err(|e| format!("{} in column_names", e))?;
         }
     }
 
-    let mut writer: Box<dyn Write> = match output {
-        Some(file) => Box::new(fs::File::create(file)?),
-        None => Box::new(std::io::stdout()),
-    };
+    let mut recursive = matches.get_flag("recursive") || profile.recursive;
+    let mut dirs: Vec<PathBuf> = matches
+        .get_many::<String>("directory")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    let files: Vec<PathBuf> = report_matches
+        .get_many::<PathBuf>("files")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    if dirs.is_empty() && files.is_empty() {
+        dirs = profile.directories.clone();
+    }
+    let files_from = matches.get_one::<String>("files_from");
+    if dirs.is_empty()
+        && files.is_empty()
+        && files_from.is_none()
+        && let Some(root) = vault::find_root(&std::env::current_dir()?)
+    {
+        let daily_notes = vault::daily_notes(&root);
+        info!("using daily notes in {}", daily_notes.dir.display());
+        recursive |= daily_notes.nested;
+        dirs.push(daily_notes.dir);
+    }
+    let destinations: Vec<Destination> = matches
+        .get_many::<Destination>("output")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
 
-    let mut csv_writer = Writer::from_writer(&mut writer);
+    let schema = subcommand.is_none() && report_matches.get_flag("schema");
+    if dirs.is_empty()
+        && files.is_empty()
+        && files_from.is_none()
+        && subcommand != Some("import")
+        && !schema
+    {
+        command
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "one of FILE, --dir, or --files-from is required outside an Obsidian vault",
+            )
+            .exit();
+    }
 
-    if accumulate {
-        let mut accumulated: HashMap<String, (TimeDuration, Vec<String>)> = HashMap::new();
-        for entry in entries {
-            debug!("parsing {}", entry.display());
-            let content = fs::read_to_string(&entry)?;
-            for (tag, duration) in parse_time_entries(&content, true) {
-                let file_path = if use_basename {
-                    entry.file_name().unwrap().to_string_lossy().into_owned()
-                } else {
-                    entry.to_string_lossy().into_owned()
-                };
-                let entry = accumulated
-                    .entry(tag)
-                    .or_insert((TimeDuration::default(), vec![]));
-                entry.0.hours += duration.hours;
-                entry.0.minutes += duration.minutes;
-                entry.0.seconds += duration.seconds;
-                entry.1.push(file_path);
+    let mut listed = vec![];
+    for file in files {
+        if is_archive(&file) {
+            collect_entries(&file, recursive, &mut listed)?;
+        } else {
+            listed.push(file);
+        }
+    }
+    if let Some(list) = files_from {
+        read_file_list(list, &mut listed)?;
+    }
+    let changed_since = matches.get_one::<Since>("changed_since");
+    let path_filters: Vec<&Glob> = matches
+        .get_many::<Glob>("path_filter")
+        .unwrap_or_default()
+        .collect();
+    let mut ignored: Vec<vault::IgnoreFilters> = vec![];
+    if !matches.get_flag("include_ignored") {
+        let roots: BTreeSet<PathBuf> = dirs
+            .iter()
+            .filter_map(|dir| vault::find_root(&std::path::absolute(dir).ok()?))
+            .collect();
+        ignored.extend(roots.iter().map(|root| vault::ignore_filters(root)));
+    }
+    let max_file_size = match matches.get_one::<u64>("max_file_size") {
+        Some(size) => *size,
+        None => match &profile.max_file_size {
+            Some(size) => parse_size(size).map_err(|e| format!("{} in max_file_size", e))?,
+            None => MAX_FILE_SIZE,
+        },
+    };
+    for (inner, outer) in overlapping_dirs(&dirs, recursive) {
+        warn!(
+            "{} overlaps {}; notes found under both are read once",
+            inner.display(),
+            outer.display()
+        );
+    }
+    // Walks the directories again each time, for `serve` to see new notes.
+    let scan = || -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut entries = vec![];
+        for dir in &dirs {
+            if dir.is_dir() || is_archive(dir) {
+                collect_entries(dir, recursive, &mut entries)?;
             }
         }
-        for (tag, (duration, paths)) in accumulated {
-            let paths_joined = paths.join(",");
-            csv_writer.write_record(&[tag, format_duration(&duration), paths_joined])?;
+        entries.retain(|entry| !ignored.iter().any(|filters| filters.is_ignored(entry)));
+        entries.extend(listed.iter().cloned());
+        // A single directory lists each note once, so resolving every path
+        // is only worth it when notes come from several places.
+        if dirs.len() + listed.len() > 1 {
+            let duplicates = dedup_entries(&mut entries);
+            if duplicates > 0 {
+                debug!("dropped {} notes listed more than once", duplicates);
+            }
         }
-    } else {
-        for entry in entries {
-            debug!("parsing {}", entry.display());
-            let content = fs::read_to_string(&entry)?;
-            for (tag, duration) in parse_time_entries(&content, true) {
-                let file_path = if use_basename {
-                    entry.file_name().unwrap().to_string_lossy().into_owned()
-                } else {
-                    entry.to_string_lossy().into_owned()
-                };
-                csv_writer.write_record(&[tag, format_duration(&duration), file_path])?;
+        if let Some(since) = changed_since {
+            retain_changed(&mut entries, since)?;
+        }
+        if !path_filters.is_empty() {
+            entries.retain(|entry| path_filters.iter().any(|glob| glob.is_match(entry)));
+        }
+        let mut skipped = vec![];
+        entries.retain(|entry| match skip_reason(entry, max_file_size) {
+            Some(reason) => {
+                skipped.push(format!("{} ({})", entry.display(), reason));
+                false
             }
+            None => true,
+        });
+        if !skipped.is_empty() {
+            warn!(
+                "skipped {} large or binary notes: {}",
+                skipped.len(),
+                skipped.join("; ")
+            );
         }
+        Ok(entries)
+    };
+    let entries = match schema {
+        true => vec![],
+        false => timings::time(Phase::Discovery, scan)?,
+    };
+
+    let reports = matches!(subcommand, None | Some("import"));
+    if !reports && (destinations.len() > 1 || destinations.iter().any(|d| d.format.is_some())) {
+        return Err(
+            "only reports can be written to several --output destinations or FILE:FORMAT".into(),
+        );
     }
-    csv_writer.flush()?;
+    // Reports write their destinations themselves, so they aren't opened
+    // here too.
+    let mut writer = match reports {
+        true => Destination::default().create()?,
+        false => destinations.first().cloned().unwrap_or_default().create()?,
+    };
 
-    Ok(())
-}
+    let tags: Vec<String> = matches
+        .get_many::<String>("tag")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let sprints = Sprints::new(
+        profile.sprint_start.as_deref(),
+        profile.sprint_length.as_deref(),
+        &profile.sprints,
+    )
+    .map_err(|e| format!("{} in config", e))?;
+    let (since, until) = match (
+        matches.get_one::<String>("last"),
+        matches.get_one::<String>("sprint"),
+    ) {
+        (Some(window), _) => {
+            let today = jiff::Zoned::now().date();
+            (Some(window_start(window, today)?), Some(today))
+        }
+        (None, Some(name)) => {
+            if sprints.is_empty() {
+                return Err(
+                    "--sprint requires sprint_start or [[sprint]] tables in the config".into(),
+                );
+            }
+            let sprint = sprints.find(name)?;
+            (Some(sprint.start), Some(sprint.end))
+        }
+        (None, None) => (
+            matches.get_one::<Date>("since").copied(),
+            matches.get_one::<Date>("until").copied(),
+        ),
+    };
+    let options = Options {
+        close_at: matches.get_one::<CloseAt>("close_open_sessions").copied(),
+        unit: matches
+            .get_one::<String>("duration_unit")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default(),
+        tags: if tags.is_empty() {
+            profile.tags.clone()
+        } else {
+            tags
+        },
+        priorities: matches
+            .get_many::<String>("priority")
+            .unwrap_or_default()
+            .map(|p| p.parse().ok())
+            .collect(),
+        checkbox_states: matches
+            .get_many::<char>("checkbox_states")
+            .unwrap_or_default()
+            .copied()
+            .collect(),
+        text_match: matches.get_one::<Regex>("match").cloned(),
+        text_exclude: matches.get_one::<Regex>("exclude_match").cloned(),
+        date_source: matches
+            .get_one::<String>("date_source")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default(),
+        since,
+        until,
+        weekends: if matches.get_flag("only_weekends") {
+            Some(true)
+        } else if matches.get_flag("exclude_weekends") {
+            Some(false)
+        } else {
+            None
+        },
+        require_tags: matches.get_flag("require_tags"),
+        default_tag: matches.get_one::<String>("default_tag").cloned(),
+        authors: matches.get_flag("authors"),
+        granularity: granularity(matches, &profile)?,
+        embeds: matches.get_flag("embeds"),
+        legacy_durations: matches.get_flag("legacy_durations") || profile.legacy_durations,
+        frontmatter_tags: matches.get_flag("inherit_frontmatter_tags"),
+        archived_key: (!matches.get_flag("include_archived")).then(|| {
+            profile
+                .archived_key
+                .clone()
+                .unwrap_or_else(|| "archived".to_string())
+        }),
+        week: matches
+            .get_one::<Date>("week")
+            .copied()
+            .unwrap_or_else(|| monday(jiff::Zoned::now().date())),
+        path_style: match matches.get_flag("basename") {
+            true => Some(PathStyle::Basename),
+            false => matches.get_one::<PathStyle>("path_style").copied(),
+        },
+        plain_text: matches.get_flag("plain_text"),
+        locale: match matches.get_one::<Locale>("locale") {
+            Some(locale) => *locale,
+            None => match &profile.locale {
+                Some(locale) => locale.parse().map_err(|e| format!("{} in locale", e))?,
+                None => Locale::default(),
+            },
+        },
+        request_interval: match matches
+            .get_one::<f64>("rate_limit")
+            .or(profile.rate_limit.as_ref())
+        {
+            Some(rate) => Duration::try_from_secs_f64(1.0 / rate)
+                .map_err(|_| format!("invalid rate_limit {}; expected requests a second", rate))?,
+            None => Duration::ZERO,
+        },
+        map_notes: !matches!(subcommand, Some("serve" | "rpc")),
+        vaults: dirs.clone(),
+        identifiers: Identifiers::new(&profile.identifiers)?,
+        exclusives: Exclusives::new(&profile.exclusive)?,
+        units: Units::new(&profile.units)?,
+        sprints,
+        profile,
+        #[cfg(feature = "scripting")]
+        hook: matches
+            .get_one::<String>("script")
+            .map(|path| RowHook::load(Path::new(path)))
+            .transpose()?,
+    };
 
-fn collect_entries(
-    dir: &Path,
-    recursive: bool,
-    entries: &mut Vec<PathBuf>,
-) -> Result<(), Box<dyn Error>> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() && path.extension() == Some(OsStr::new("md")) {
-            entries.push(path);
-        } else if recursive && path.is_dir() {
-            collect_entries(&path, true, entries)?;
+    let result = match root_matches.subcommand() {
+        Some(("cooccur", sub_matches)) => run_cooccur(sub_matches, &options, &entries, &mut writer),
+        Some(("burndown", sub_matches)) => {
+            run_burndown(sub_matches, &options, &entries, &mut writer)
+        }
+        Some(("digest", sub_matches)) => run_digest(sub_matches, &options, &entries, &mut writer),
+        Some(("forecast", _)) => run_forecast(&options, &entries, &mut writer),
+        Some(("billing", sub_matches)) => run_billing(sub_matches, &options, &entries, &mut writer),
+        Some(("doctor", sub_matches)) => run_doctor(sub_matches, &options, &entries, &mut writer),
+        Some(("import", sub_matches)) => run_import(sub_matches, &options, &entries, &destinations),
+        Some(("query", sub_matches)) => run_query(sub_matches, &options, &entries, &mut writer),
+        #[cfg(feature = "sql")]
+        Some(("sql", sub_matches)) => run_sql(sub_matches, &options, &entries, &mut writer),
+        Some(("retag", sub_matches)) => run_retag(sub_matches, &options, &entries, &mut writer),
+        Some(("suggest-tags", sub_matches)) => {
+            run_suggest_tags(sub_matches, &options, &entries, &mut writer)
+        }
+        Some(("new-day", sub_matches)) => run_new_day(sub_matches, &options, &mut writer),
+        Some(("notify", sub_matches)) => run_notify(sub_matches, &options, &entries, &mut writer),
+        Some(("serve", sub_matches)) => run_serve(sub_matches, &options, &scan),
+        Some(("rpc", _)) => run_rpc(&options, &scan),
+        Some(("bundle", _)) => run_bundle(&options, &entries),
+        Some(("stats", sub_matches)) => run_stats(sub_matches, &options, &entries, &mut writer),
+        Some(("streak", sub_matches)) => run_streak(sub_matches, &options, &entries, &mut writer),
+        Some(("verify", sub_matches)) => run_verify(sub_matches, &options, &entries, &mut writer),
+        Some(("verify-issues", sub_matches)) => {
+            run_verify_issues(sub_matches, &options, &entries, &mut writer)
         }
+        Some(("sessions", _)) => run_sessions(&options, &entries, &mut writer),
+        Some(("targets", sub_matches)) => run_targets(sub_matches, &options, &entries, &mut writer),
+        Some(("export", _)) => run_export(&options, &entries, &mut writer),
+        Some(("gaps", sub_matches)) => run_gaps(sub_matches, &options, &entries, &mut writer),
+        Some(("reconcile", sub_matches)) => {
+            run_reconcile(sub_matches, &options, &entries, &mut writer)
+        }
+        _ => run_report(matches, &options, &entries, &destinations),
+    };
+    // Reports write their destinations themselves.
+    let result = match reports {
+        true => result,
+        false => result.and_then(|()| writer.finish()),
+    };
+    let untagged = UNTAGGED.load(Ordering::Relaxed);
+    if untagged > 0 {
+        warn!("{} entries without tags (see --default-tag)", untagged);
     }
-    entries.sort();
-    Ok(())
+    if matches.get_flag("timings") {
+        eprint!("{}", timings::report(started.elapsed()));
+    }
+    result
 }
 
-#[derive(Default, Debug, Clone, PartialEq)]
-struct TimeDuration {
-    hours: u32,
-    minutes: u32,
-    seconds: u32,
+/// Where `--output` writes a report: a file, or stdout for `-` or no
+/// `--output`, in the format after a colon in `FILE:FORMAT`, if any.
+#[derive(Debug, Clone, Default)]
+struct Destination {
+    path: Option<PathBuf>,
+    format: Option<String>,
 }
 
-fn format_duration(duration: &TimeDuration) -> String {
-    let mut parts = vec![];
-    if duration.hours > 0 {
-        parts.push(format!("{}h", duration.hours));
-    }
-    if duration.minutes > 0 {
-        parts.push(format!("{}m", duration.minutes));
-    }
-    if duration.seconds > 0 {
-        parts.push(format!("{}s", duration.seconds));
+impl FromStr for Destination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Only a trailing word is a format, so `C:\report.csv` stays a path.
+        let (path, format) = match s.rsplit_once(':') {
+            Some((path, format))
+                if !path.is_empty()
+                    && !format.is_empty()
+                    && format.chars().all(|c| c.is_ascii_lowercase() || c == '-') =>
+            {
+                (path, Some(format.to_string()))
+            }
+            _ => (s, None),
+        };
+        Ok(Destination {
+            path: (path != "-").then(|| PathBuf::from(path)),
+            format,
+        })
     }
-    parts.join("")
 }
 
-fn parse_duration(text: &str) -> TimeDuration {
-    let mut duration = TimeDuration::default();
-    let re = Regex::new(r"(?i)(?P<value>\d+)(?P<unit>h|m|s|d)").unwrap();
-    for cap in re.captures_iter(text) {
-        let value: u32 = cap["value"].parse().unwrap_or(0);
-        match &cap["unit"] {
-            "h" => duration.hours += value,
-            "m" => duration.minutes += value,
-            "s" => duration.seconds += value,
-            "d" => duration.hours += value * 8,
-            _ => (),
+/// An open destination. A file is written beside its path and only
+/// replaces it on `finish`, so a report is never seen half written.
+enum Output {
+    Stdout(Timed<std::io::Stdout>),
+    File(Timed<AtomicFile>),
+}
+
+impl Output {
+    fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Output::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Output::File(Timed(file)) => file.commit(),
         }
     }
-    duration
 }
 
-fn extract_tags(task_text: &str, current_pbi: &Option<String>, sort_tags: bool) -> String {
-    let re_tags = Regex::new(r"#[a-zA-Z0-9_-]+|#pbi-\d+").unwrap();
-    let mut tags: Vec<String> = re_tags
-        .find_iter(task_text)
-        .map(|m| m.as_str().to_string())
-        .collect();
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::File(file) => file.write(buf),
+        }
+    }
 
-    if let Some(pbi) = current_pbi {
-        if !tags.iter().any(|t| t == pbi) {
-            tags.insert(0, pbi.clone());
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File(file) => file.flush(),
         }
     }
+}
 
-    if sort_tags {
-        tags.sort();
+impl Destination {
+    fn create(&self) -> Result<Output, Box<dyn Error>> {
+        Ok(match &self.path {
+            Some(path) => Output::File(Timed(AtomicFile::create(path)?)),
+            None => Output::Stdout(Timed(std::io::stdout())),
+        })
     }
 
-    if tags.is_empty() {
-        "".to_string()
-    } else {
-        tags.join(",")
+    /// Writes the destination with `write`, replacing a file only once
+    /// `write` succeeds.
+    fn write(
+        &self,
+        write: impl FnOnce(&mut dyn Write) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut output = self.create()?;
+        write(&mut output)?;
+        output.finish()
+    }
+
+    /// The format given with the destination, else the file's extension
+    /// when `--format` isn't on the command line and it names a format,
+    /// else `--format` or the profile's.
+    fn format<'a>(
+        &'a self,
+        matches: &'a ArgMatches,
+        options: &'a Options,
+    ) -> Result<&'a str, Box<dyn Error>> {
+        let extension = self
+            .path
+            .as_ref()
+            .and_then(|path| path.extension()?.to_str())
+            .filter(|extension| REPORT_FORMATS.contains(extension))
+            .filter(|_| matches.value_source("format") != Some(ValueSource::CommandLine));
+        let format = match (&self.format, extension) {
+            (Some(format), _) => format.as_str(),
+            (None, Some(extension)) => extension,
+            (None, None) => output_format(matches, options, &REPORT_FORMATS),
+        };
+        if !REPORT_FORMATS.contains(&format) {
+            return Err(format!(
+                "unknown output format '{}'; expected one of {}",
+                format,
+                REPORT_FORMATS.join(", ")
+            )
+            .into());
+        }
+        Ok(format)
     }
 }
 
-fn parse_time_entries(content: &str, sort_tags: bool) -> Vec<(String, TimeDuration)> {
-    let mut results = vec![];
-    let mut current_pbi: Option<String> = None;
+/// Pairs each destination, or stdout without any, with its format, checking
+/// every format before anything is written.
+fn report_destinations<'a>(
+    destinations: &'a [Destination],
+    matches: &'a ArgMatches,
+    options: &'a Options,
+) -> Result<Vec<(&'a Destination, &'a str)>, Box<dyn Error>> {
+    static STDOUT: Destination = Destination {
+        path: None,
+        format: None,
+    };
+    let destinations = if destinations.is_empty() {
+        std::slice::from_ref(&STDOUT)
+    } else {
+        destinations
+    };
+    destinations
+        .iter()
+        .map(|destination| Ok((destination, destination.format(matches, options)?)))
+        .collect()
+}
 
-    let re_heading = Regex::new(r"(?i)^#+\s+Work on \[\[(\d+)\]\]").unwrap();
-    let re_generic_heading = Regex::new(r"(?i)^#+\s+").unwrap(); // Matches any Markdown header
-    let re_time_tracked =
-        Regex::new(r"(?P<text>.*?)(?:\[\s*timeTracked\s*:\s*(?P<duration>[^\]]+)\])(?P<tags>.*)")
-            .unwrap();
+fn run_report(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    destinations: &[Destination],
+) -> Result<(), Box<dyn Error>> {
+    let outputs = report_destinations(destinations, matches, options)?;
+    check_report_formats(matches, &outputs)?;
 
-    for line in content.lines() {
-        log::debug!("Processing line: {}", line);
-        if let Some(cap) = re_heading.captures(line) {
-            current_pbi = Some(format!("#pbi-{}", &cap[1]));
-            log::debug!("Found PBI: {:?}", current_pbi);
-            continue;
+    let fields: Vec<String> = match matches.value_source("columns") {
+        Some(ValueSource::CommandLine) => vec![],
+        _ => options
+            .profile
+            .fields
+            .iter()
+            .map(|field| format!("field:{}", field))
+            .collect(),
+    };
+    let columns: Vec<&str> = matches
+        .get_many::<String>("columns")
+        .unwrap_or_default()
+        .map(|s| s.as_str())
+        .chain(fields.iter().map(|s| s.as_str()))
+        .collect();
+    if matches.get_flag("schema") {
+        for (destination, format) in outputs {
+            let schema = report_schema(matches, &columns, format, options)?;
+            destination.write(|writer| Ok(writeln!(writer, "{}", schema)?))?;
         }
+        return Ok(());
+    }
+    if let Some(count) = matches.get_one::<usize>("preview") {
+        for (destination, _) in outputs {
+            destination.write(|writer| write_preview(*count, entries, options, writer))?;
+        }
+        return Ok(());
+    }
+    let mut rows = vec![];
+    for entry in entries {
+        for time_entry in load_entries(entry, options)? {
+            rows.push(Row {
+                entry: time_entry,
+                path: entry,
+                source: NOTES_SOURCE,
+            });
+        }
+    }
+    if let Some(path) = matches.get_one::<String>("manifest") {
+        let manifest = manifest(matches, options, entries, &rows, &outputs);
+        fs::write(path, manifest.to_json(options.unit) + "\n")
+            .map_err(|e| format!("failed to write {}: {}", path, e))?;
+    }
+    write_report(matches, &columns, &rows, &outputs, options)
+}
 
-        if re_generic_heading.is_match(line) {
-            current_pbi = None; // Reset PBI when encountering a generic Markdown header
-            log::debug!("Resetting current PBI due to generic header");
+/// Checks that every output's format supports the `report_args` given.
+fn check_report_formats(
+    matches: &ArgMatches,
+    outputs: &[(&Destination, &str)],
+) -> Result<(), Box<dyn Error>> {
+    for (_, format) in outputs {
+        if *format == "csv" || *format == "json" {
             continue;
         }
-        log::debug!("Current PBI: {:?}", current_pbi);
-
-        if let Some(cap) = re_time_tracked.captures(line) {
-            let task_text = cap.name("text").map_or("", |m| m.as_str());
-            let duration_text = cap.name("duration").map_or("", |m| m.as_str());
-            let tags_text = cap.name("tags").map_or("", |m| m.as_str());
-            let combined_text = format!("{} {}", task_text, tags_text).trim().to_string();
-            let tag_str = extract_tags(&combined_text, &current_pbi, sort_tags);
-            let duration = parse_duration(duration_text);
-            log::debug!(
-                "Parsed duration: {} from text: {}",
-                format_duration(&duration),
-                duration_text
-            );
-            results.push((tag_str, duration));
+        if matches.get_flag("accumulate") {
+            return Err(format!("--format {} does not support --accumulate", format).into());
+        }
+        if matches.contains_id("group_by") {
+            return Err(format!("--format {} does not support --group-by", format).into());
+        }
+        if matches.contains_id("gaps") {
+            return Err(format!("--format {} does not support --gaps", format).into());
         }
     }
-
-    results
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use dedent::dedent;
-
-    #[test]
-    fn test_parse_duration_hours() {
-        let duration = parse_duration("3h");
-        assert_eq!(
-            TimeDuration {
-                hours: 3,
-                minutes: 0,
-                seconds: 0
-            },
-            duration
-        );
+/// Writes `rows` to each output as the `report_args` ask: accumulated,
+/// as gaps, grouped, charted, or one per entry with `columns`.
+fn write_report(
+    matches: &ArgMatches,
+    columns: &[&str],
+    rows: &[Row],
+    outputs: &[(&Destination, &str)],
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let group = matches.get_one::<Group>("group_by");
+    if matches.get_flag("chart") {
+        let totals = chart_totals(group.unwrap_or(&Group::Tag), rows, options)?;
+        for (destination, _) in outputs {
+            destination.write(|writer| {
+                chart::write_bars(&totals, writer, options.unit, &options.locale)
+            })?;
+        }
+        return Ok(());
     }
-
-    #[test]
-    fn test_parse_duration_minutes() {
-        let duration = parse_duration("45m");
-        assert_eq!(
-            TimeDuration {
-                hours: 0,
-                minutes: 45,
-                seconds: 0
-            },
-            duration
-        );
+    for (destination, format) in outputs {
+        destination.write(|writer| {
+            if matches.get_flag("accumulate") {
+                let paths = !matches.get_flag("no_paths");
+                return write_accumulated(rows, options, *format == "json", paths, writer);
+            }
+            if let Some(threshold) = matches.get_one::<TimeDuration>("gaps") {
+                let hours = *matches.get_one::<WorkHours>("work_hours").unwrap();
+                return write_gaps(rows, hours, threshold, format, options, writer);
+            }
+            match group {
+                Some(group) => write_groups(group, rows, format, options, writer),
+                None => write_rows(columns, rows, format, options, writer),
+            }
+        })?;
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_parse_duration_seconds() {
-        let duration = parse_duration("30s");
-        assert_eq!(
-            TimeDuration {
-                hours: 0,
-                minutes: 0,
-                seconds: 30
-            },
-            duration
-        );
+/// Writes the first `count` entries with how they were read, reading only
+/// as many notes as it takes.
+fn write_preview(
+    count: usize,
+    entries: &[PathBuf],
+    options: &Options,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut written = 0;
+    for path in entries {
+        if written == count {
+            break;
+        }
+        let parsed = load_entries(path, options)?;
+        if parsed.is_empty() {
+            continue;
+        }
+        let content = read_note(path)?;
+        let raw: Vec<&str> = lines(&content).collect();
+        for entry in parsed.iter().take(count - written) {
+            if written > 0 {
+                writeln!(writer)?;
+            }
+            writeln!(writer, "{}:{}", display_path(path, options), entry.line)?;
+            let line = raw.get(entry.line - 1).copied().unwrap_or_default();
+            writeln!(writer, "  raw:      {}", line.trim())?;
+            writeln!(writer, "  text:     {}", entry.text)?;
+            writeln!(
+                writer,
+                "  duration: {} ({}s, {})",
+                options.unit.format(&entry.duration),
+                entry.duration.total_seconds(),
+                entry.syntax
+            )?;
+            match entry.tags.as_str() {
+                "" => writeln!(writer, "  tags:     (none)")?,
+                tags => writeln!(writer, "  tags:     {}", tags)?,
+            }
+            if let Some(date) = entry.date {
+                writeln!(writer, "  date:     {}", date)?;
+            }
+            if !entry.heading.is_empty() {
+                writeln!(writer, "  heading:  {}", entry.heading)?;
+            }
+            for (name, value) in &entry.fields {
+                writeln!(writer, "  field:{}: {}", name, value)?;
+            }
+            written += 1;
+        }
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_parse_duration_combined_with_spaces() {
-        let duration = parse_duration("2h 10m 15s");
-        assert_eq!(
-            TimeDuration {
-                hours: 2,
-                minutes: 10,
-                seconds: 15
-            },
-            duration
-        );
+fn manifest(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    rows: &[Row],
+    outputs: &[(&Destination, &str)],
+) -> Manifest {
+    let mut total = TimeDuration::default();
+    for row in rows {
+        total += &row.entry.duration;
     }
-
-    #[test]
-    fn test_parse_duration_combined() {
-        let duration = parse_duration("2h15m10s");
-        assert_eq!(
-            TimeDuration {
-                hours: 2,
-                minutes: 15,
-                seconds: 10
-            },
-            duration
-        );
+    let filters = MANIFEST_FILTERS
+        .iter()
+        .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+        .map(|id| {
+            let values = matches
+                .get_raw(id)
+                .unwrap_or_default()
+                .map(|value| value.to_string_lossy().to_string())
+                .collect();
+            (id.replace('_', "-"), values)
+        })
+        .collect();
+    Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        generated: jiff::Timestamp::now(),
+        directories: options.vaults.clone(),
+        files: entries.len(),
+        entries: rows.len(),
+        total,
+        filters,
+        outputs: outputs
+            .iter()
+            .map(|(destination, _)| match &destination.path {
+                Some(path) => path.display().to_string(),
+                None => "-".to_string(),
+            })
+            .collect(),
     }
+}
 
-    #[test]
-    fn test_parse_duration_days() {
-        let duration = parse_duration("1d");
-        assert_eq!(
-            TimeDuration {
-                hours: 8,
-                minutes: 0,
-                seconds: 0
-            },
-            duration
-        );
+/// The totals `--chart` draws: tags from the largest down, and other
+/// groups in order.
+fn chart_totals(
+    group: &Group,
+    rows: &[Row],
+    options: &Options,
+) -> Result<Vec<(String, TimeDuration)>, Box<dyn Error>> {
+    check_group(group, options)?;
+    let totals = query::group_totals(
+        group,
+        &options.sprints,
+        rows.iter()
+            .map(|row| (&row.entry, display_path(row.path, options))),
+    );
+    let mut totals: Vec<(String, TimeDuration)> = totals
+        .into_iter()
+        .map(|(key, (duration, _))| (key, duration))
+        .collect();
+    if *group == Group::Tag {
+        totals.sort_by_key(|(_, duration)| std::cmp::Reverse(duration.total_seconds()));
     }
+    Ok(totals)
+}
 
-    #[test]
-    fn test_parse_duration_invalid() {
-        let duration = parse_duration("invalid");
-        assert_eq!(
-            TimeDuration {
-                hours: 0,
-                minutes: 0,
-                seconds: 0
-            },
-            duration
-        );
+fn check_group(group: &Group, options: &Options) -> Result<(), Box<dyn Error>> {
+    if *group == Group::Author && !options.authors {
+        return Err("grouping by author requires --authors".into());
     }
-
-    #[test]
-    fn test_parse_duration_mixed_valid_invalid() {
-        let duration = parse_duration("2h invalid 30m");
-        assert_eq!(
-            TimeDuration {
-                hours: 2,
-                minutes: 30,
-                seconds: 0
-            },
-            duration
+    if *group == Group::Sprint && options.sprints.is_empty() {
+        return Err(
+            "grouping by sprint requires sprint_start or [[sprint]] tables in the config".into(),
         );
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_extract_tags_with_pbi() {
-        let task_text = "Complete task #tag1 #tag2";
-        let current_pbi = Some("#pbi-123".to_string());
-        let tags = extract_tags(task_text, &current_pbi, false);
-        assert_eq!("#pbi-123,#tag1,#tag2", tags);
+/// The JSON Schema of the records a report writes as `format`, following
+/// the same options as the report itself.
+fn report_schema(
+    matches: &ArgMatches,
+    columns: &[&str],
+    format: &str,
+    options: &Options,
+) -> Result<String, Box<dyn Error>> {
+    let duration = match options.unit {
+        DurationUnit::Compact => Field::Text,
+        _ => Field::Number,
+    };
+    let names = &options.profile.column_names;
+    let name = |column: &str| names.get(column).cloned().unwrap_or(column.to_string());
+    if format == "full-json" {
+        let names: Vec<String> = FULL_COLUMNS.iter().map(|column| name(column)).collect();
+        let fields: Vec<(&str, Field)> = FULL_COLUMNS
+            .iter()
+            .zip(&names)
+            .map(|(column, name)| match *column {
+                "seconds" | "line" => (name.as_str(), Field::Integer),
+                _ => (name.as_str(), Field::OptionalText),
+            })
+            .collect();
+        return Ok(schema::records("timetracker full export", &fields));
     }
-
-    #[test]
-    fn test_extract_tags_ordered() {
-        let task_text = "this #c is a task #a with unordered tags #b";
-        let current_pbi = None;
-        let tags = extract_tags(task_text, &current_pbi, true);
-        assert_eq!("#a,#b,#c", tags);
+    if format != "json" {
+        return Err(format!(
+            "--format {} isn't JSON; --schema describes --format json or full-json",
+            format
+        )
+        .into());
     }
-
-    #[test]
-    fn test_extract_tags_without_pbi() {
-        let task_text = "Complete task #tag1 #tag2";
-        let current_pbi = None;
-        let tags = extract_tags(task_text, &current_pbi, false);
-        assert_eq!("#tag1,#tag2", tags);
+    if matches.get_flag("accumulate") {
+        let mut fields = vec![
+            ("tags", Field::List),
+            ("duration", duration),
+            ("entries", Field::Integer),
+        ];
+        if !matches.get_flag("no_paths") {
+            fields.push(("files", Field::List));
+        }
+        return Ok(schema::records("timetracker accumulated tags", &fields));
     }
-
-    #[test]
-    fn test_extract_tags_no_tags_with_pbi() {
-        let task_text = "Complete task";
-        let current_pbi = Some("#pbi-123".to_string());
-        let tags = extract_tags(task_text, &current_pbi, false);
-        assert_eq!("#pbi-123", tags);
+    if matches.contains_id("gaps") {
+        let fields = [
+            ("date", Field::Text),
+            ("start", Field::Text),
+            ("end", Field::Text),
+            ("duration", duration),
+        ];
+        return Ok(schema::records("timetracker gaps", &fields));
     }
-
-    #[test]
-    fn test_extract_tags_no_tags_no_pbi() {
-        let task_text = "Complete task";
-        let current_pbi = None;
-        let tags = extract_tags(task_text, &current_pbi, false);
-        assert_eq!("", tags);
+    if let Some(group) = matches.get_one::<Group>("group_by") {
+        let key = group.to_string();
+        let fields = [
+            (key.as_str(), Field::OptionalText),
+            ("duration", duration),
+            ("entries", Field::Integer),
+        ];
+        return Ok(schema::records("timetracker groups", &fields));
     }
+    let names: Vec<String> = columns.iter().map(|column| name(column)).collect();
+    let fields: Vec<(&str, Field)> = columns
+        .iter()
+        .zip(&names)
+        .map(|(column, name)| match *column {
+            "tags" => (name.as_str(), Field::List),
+            "duration" => (name.as_str(), duration),
+            _ if column.starts_with("note.") || column.starts_with("field:") => {
+                (name.as_str(), Field::Any)
+            }
+            _ => (name.as_str(), Field::OptionalText),
+        })
+        .collect();
+    Ok(schema::records("timetracker entries", &fields))
+}
 
-    #[test]
-    fn test_extract_tags_sorted() {
-        let task_text = "this #c is a task #a with unordered tags #b";
-        let current_pbi = None;
-        let tags = extract_tags(task_text, &current_pbi, true);
-        assert_eq!(tags, "#a,#b,#c");
+/// A `[[report]]` of a bundle, checked and ready to write.
+struct BundledReport {
+    destination: Destination,
+    format: String,
+    columns: Vec<String>,
+    group: Option<Group>,
+    accumulate: bool,
+    tags: Vec<String>,
+    since: Option<Date>,
+    until: Option<Date>,
+    text_match: Option<Regex>,
+}
+
+impl BundledReport {
+    fn new(report: &Report) -> Result<Self, String> {
+        let destination = Destination {
+            path: report.output.clone().filter(|path| path.as_os_str() != "-"),
+            format: None,
+        };
+        let extension = destination
+            .path
+            .as_ref()
+            .and_then(|path| path.extension()?.to_str())
+            .filter(|extension| REPORT_FORMATS.contains(extension));
+        let format = report
+            .format
+            .as_deref()
+            .or(extension)
+            .unwrap_or("csv")
+            .to_string();
+        if !REPORT_FORMATS.contains(&format.as_str()) {
+            return Err(format!(
+                "unknown output format '{}'; expected one of {}",
+                format,
+                REPORT_FORMATS.join(", ")
+            ));
+        }
+        let group = report
+            .group_by
+            .as_deref()
+            .map(|group| group.parse::<Group>())
+            .transpose()?;
+        if (report.accumulate || group.is_some()) && format != "csv" && format != "json" {
+            return Err(format!(
+                "format {} does not support accumulate or group_by",
+                format
+            ));
+        }
+        let columns = match report.columns.is_empty() {
+            true => vec![
+                "tags".to_string(),
+                "duration".to_string(),
+                "file".to_string(),
+            ],
+            false => report
+                .columns
+                .iter()
+                .map(|column| parse_column(column))
+                .collect::<Result<_, _>>()?,
+        };
+        let date = |date: &Option<String>| {
+            date.as_deref()
+                .map(|d| {
+                    d.parse::<Date>()
+                        .map_err(|e| format!("invalid date '{}': {}", d, e))
+                })
+                .transpose()
+        };
+        Ok(BundledReport {
+            destination,
+            format,
+            columns,
+            group,
+            accumulate: report.accumulate,
+            tags: report.tags.clone(),
+            since: date(&report.since)?,
+            until: date(&report.until)?,
+            text_match: report
+                .text_match
+                .as_deref()
+                .map(|re| Regex::new(re).map_err(|e| e.to_string()))
+                .transpose()?,
+        })
     }
 
-    #[test]
-    fn test_extract_tags_unsorted() {
-        let task_text = "this #c is a task #a with unordered tags #b";
-        let current_pbi = None;
-        let tags = extract_tags(task_text, &current_pbi, false);
-        assert_eq!(tags, "#c,#a,#b");
+    fn matches(&self, entry: &TimeEntry) -> bool {
+        let dated = self.since.is_none() && self.until.is_none()
+            || entry.date.is_some_and(|date| {
+                self.since.is_none_or(|since| date >= since)
+                    && self.until.is_none_or(|until| date <= until)
+            });
+        dated
+            && (self.tags.is_empty() || self.tags.iter().any(|tag| has_tag(&entry.tags, tag)))
+            && self
+                .text_match
+                .as_ref()
+                .is_none_or(|re| re.is_match(&entry.text))
     }
+}
 
-    #[test]
-    fn test_parse_time_entries_with_sorted_tags() {
-        let content = dedent!(
-            r#"
-        # Work on [[123]]
-        - [ ] Task 1 [ timeTracked: 1h ] #c #a #b
-        "#
-        );
-        let entries = parse_time_entries(content, true);
-        assert_eq!("#a,#b,#c,#pbi-123", entries[0].0);
+fn run_bundle(options: &Options, entries: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    if options.profile.reports.is_empty() {
+        return Err("no reports to bundle; define them as [[report]] tables in the config".into());
+    }
+    // Every report is checked before the notes are read.
+    let reports = options
+        .profile
+        .reports
+        .iter()
+        .enumerate()
+        .map(|(index, report)| {
+            let failed = |e: &dyn std::fmt::Display| format!("report {}: {}", index + 1, e);
+            let report = BundledReport::new(report).map_err(|e| failed(&e))?;
+            if let Some(group) = &report.group {
+                check_group(group, options).map_err(|e| failed(&e))?;
+            }
+            Ok(report)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut rows = vec![];
+    for entry in entries {
+        for time_entry in load_entries(entry, options)? {
+            rows.push(Row {
+                entry: time_entry,
+                path: entry,
+                source: NOTES_SOURCE,
+            });
+        }
+    }
+    for report in &reports {
+        let selected: Vec<Row> = rows
+            .iter()
+            .filter(|row| report.matches(&row.entry))
+            .cloned()
+            .collect();
+        let format = report.format.as_str();
+        report.destination.write(|writer| {
+            if report.accumulate {
+                write_accumulated(&selected, options, format == "json", true, writer)
+            } else if let Some(group) = &report.group {
+                write_groups(group, &selected, format, options, writer)
+            } else {
+                let columns: Vec<&str> = report.columns.iter().map(|c| c.as_str()).collect();
+                write_rows(&columns, &selected, format, options, writer)
+            }
+        })?;
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_parse_time_entries_with_unsorted_tags() {
-        let content = dedent!(
-            r#"
-        # Work on [[123]]
-        - [ ] Task 1 [ timeTracked: 1h ] #c #a #b
-        "#
+fn run_import(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    destinations: &[Destination],
+) -> Result<(), Box<dyn Error>> {
+    let outputs = report_destinations(destinations, matches, options)?;
+    if matches.get_flag("activitywatch") {
+        for (destination, _) in outputs {
+            destination.write(|writer| run_activitywatch(matches, options, entries, writer))?;
+        }
+        return Ok(());
+    }
+    check_report_formats(matches, &outputs)?;
+    let map = match matches.get_one::<String>("map") {
+        Some(path) => ImportMap::load(Path::new(path))?,
+        None => ImportMap::default(),
+    };
+    let columns: Vec<&str> = matches
+        .get_many::<String>("columns")
+        .unwrap_or_default()
+        .map(|s| s.as_str())
+        .collect();
+
+    let mut rows = vec![];
+    for entry in entries {
+        for time_entry in load_entries(entry, options)? {
+            rows.push(Row {
+                entry: time_entry,
+                path: entry,
+                source: NOTES_SOURCE,
+            });
+        }
+    }
+    for csv in matches.get_many::<String>("csv").unwrap_or_default() {
+        let path = Path::new(csv);
+        let file = fs::File::open(path).map_err(|e| format!("cannot read {}: {}", csv, e))?;
+        let imported =
+            import::read_csv(file, &map).map_err(|e| format!("cannot import {}: {}", csv, e))?;
+        for time_entry in filter_entries(imported, path, options)? {
+            rows.push(Row {
+                entry: time_entry,
+                path,
+                source: &map.source,
+            });
+        }
+    }
+    write_report(matches, &columns, &rows, &outputs, options)
+}
+
+/// Writes `import --activitywatch`: each day from `--since` (or `--until`)
+/// to `--until` (or today) with tracked time in the notes or active time in
+/// ActivityWatch, as CSV.
+fn run_activitywatch(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let base = matches
+        .get_one::<String>("aw_url")
+        .unwrap()
+        .trim_end_matches('/');
+    let until = options.until.unwrap_or_else(|| jiff::Zoned::now().date());
+    let since = options.since.unwrap_or(until);
+    let tz = TimeZone::system();
+    let start = tz.to_timestamp(since.to_datetime(Time::midnight()))?;
+    let end = tz.to_timestamp(until.tomorrow()?.to_datetime(Time::midnight()))?;
+
+    let mut client = http_client(options, 3);
+    let mut get = |url: String| -> Result<String, Box<dyn Error>> {
+        match client.get(&url, &[])? {
+            (200, body) => Ok(body),
+            (status, _) => Err(format!("{} answered {}", url, status).into()),
+        }
+    };
+    let buckets = get(format!("{}/api/0/buckets/", base))?;
+    let mut events = |kind: &str| -> Result<Vec<activitywatch::Event>, Box<dyn Error>> {
+        let mut events = vec![];
+        for id in activitywatch::bucket_ids(&buckets, kind) {
+            let url = format!(
+                "{}/api/0/buckets/{}/events?start={}&end={}",
+                base, id, start, end
+            );
+            events.extend(activitywatch::parse_events(&get(url)?)?);
+        }
+        Ok(events)
+    };
+    let afk = events("afkstatus")?;
+    if afk.is_empty() {
+        warn!("{} has no AFK events from {} to {}", base, since, until);
+    }
+    let windows = events("currentwindow")?;
+
+    let parsed = load_all(entries, options)?;
+    let min_block = matches.get_one::<TimeDuration>("min_block").unwrap();
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["date", "tracked", "observed", "untracked", "unlogged"])?;
+    for day in activitywatch::compare(&parsed, &afk, &windows, &tz, since, until, min_block) {
+        csv_writer.write_record(day.record(options.unit))?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// A report row: an entry with the file it came from and its `source`.
+#[derive(Clone)]
+struct Row<'a> {
+    entry: TimeEntry,
+    path: &'a Path,
+    source: &'a str,
+}
+
+fn write_rows<'a>(
+    columns: &[&'a str],
+    rows: &[Row],
+    format: &str,
+    options: &'a Options,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if columns.contains(&"author") && !options.authors {
+        return Err("the author column requires --authors".into());
+    }
+    if format == "timesheet" {
+        let entries: Vec<TimeEntry> = rows.iter().map(|row| row.entry.clone()).collect();
+        Timesheet::from_entries(&entries, options.week).write_csv(writer, options.unit)?;
+        return Ok(());
+    }
+    #[cfg(feature = "parquet")]
+    if format == "parquet" {
+        let records: Vec<parquet::Record> = rows
+            .iter()
+            .map(|row| parquet_record(row, options))
+            .collect();
+        return parquet::write(&records, writer);
+    }
+    let names = &options.profile.column_names;
+    let name = |column: &'a str| names.get(column).map_or(column, |name| name.as_str());
+    let rename = |record: Vec<(&'a str, Cell)>| -> Vec<(&'a str, Cell)> {
+        record
+            .into_iter()
+            .map(|(column, cell)| (name(column), cell))
+            .collect()
+    };
+    let mut sink: Box<dyn ReportWriter + '_> = match format {
+        "json" | "full-json" => Box::new(JsonWriter::new(writer)),
+        "full" => {
+            let header: Vec<&str> = FULL_COLUMNS.into_iter().map(name).collect();
+            Box::new(CsvWriter::with_header(writer, &header)?)
+        }
+        "dataview" => Box::new(MarkdownWriter::new(writer)),
+        // Renamed columns are for import templates, which need a header.
+        _ if !names.is_empty() => {
+            let header: Vec<&str> = columns.iter().map(|column| name(column)).collect();
+            Box::new(CsvWriter::with_header(writer, &header)?)
+        }
+        _ => Box::new(CsvWriter::new(writer)),
+    };
+    let mut summary = Summary::default();
+    for row in rows {
+        summary.entries += 1;
+        summary.duration += &row.entry.duration;
+        match format {
+            "full" | "full-json" => {
+                for record in full_records(row, options) {
+                    sink.write_entry(&rename(record))?;
+                }
+            }
+            "dataview" => sink.write_entry(&dataview_record(columns, row, options))?,
+            _ => sink.write_entry(&rename(entry_record(columns, row, options)))?,
+        }
+    }
+    sink.write_summary(&summary)?;
+    sink.finish()
+}
+
+/// A row's `columns` as typed cells: tags as a list, numeric durations as
+/// numbers, and properties with their own types.
+fn entry_record<'a>(columns: &[&'a str], row: &Row, options: &Options) -> Vec<(&'a str, Cell)> {
+    columns
+        .iter()
+        .map(|column| {
+            let cell = match *column {
+                "tags" => Cell::List(tag_list(&row.entry.tags)),
+                "duration" if options.unit != DurationUnit::Compact => {
+                    Cell::Number(options.unit.format(&row.entry.duration))
+                }
+                _ => match column.strip_prefix("note.") {
+                    Some(name) => row
+                        .entry
+                        .properties
+                        .get(name)
+                        .map_or(Cell::Text(String::new()), |value| {
+                            Cell::Property(value.clone())
+                        }),
+                    None => match column.strip_prefix("field:") {
+                        Some(name) => row
+                            .entry
+                            .field(name)
+                            .map_or(Cell::Text(String::new()), Cell::Property),
+                        None => Cell::Text(entry_column(column, row, options)),
+                    },
+                },
+            };
+            (*column, cell)
+        })
+        .collect()
+}
+
+fn tag_list(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Totals entries per tag set with the number of entries and, when `paths`,
+/// each file they came from, listed once in scan order.
+fn write_accumulated(
+    rows: &[Row],
+    options: &Options,
+    json: bool,
+    paths: bool,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    // Allocated entries count toward each of their tags' shares.
+    let step = options
+        .granularity
+        .as_ref()
+        .map_or(1, |(increment, _)| increment.total_seconds());
+    // Rows come grouped by file, so each file's path is displayed once and
+    // its entries borrow it.
+    let mut files: Vec<String> = vec![];
+    let mut shares: Vec<(TimeEntry, usize)> = vec![];
+    let mut last = None;
+    for row in rows {
+        if last != Some(row.path) {
+            files.push(display_path(row.path, options));
+            last = Some(row.path);
+        }
+        let file = files.len() - 1;
+        shares.extend(
+            row.entry
+                .allocate(step)
+                .into_iter()
+                .map(|share| (share, file)),
         );
-        let entries = parse_time_entries(content, false);
-        assert_eq!("#pbi-123,#c,#a,#b", entries[0].0);
-    }
-
-    #[test]
-    fn test_parse_time_entries_with_text_before_and_after() {
-        let content = r#"
-        - [ ] Task 1 [ timeTracked: 1h ] more text
-        "#;
-        let entries = parse_time_entries(content, true);
-        assert_eq!(
-            TimeDuration {
-                hours: 1,
-                minutes: 0,
-                seconds: 0
+    }
+    let accumulated = aggregate::accumulate(
+        shares
+            .iter()
+            .map(|(entry, file)| (entry, files[*file].as_str())),
+    );
+
+    if json {
+        let items: Vec<String> = accumulated
+            .iter()
+            .map(|(tags, total)| {
+                let files: Vec<String> = total.files.iter().map(|p| json::quote(p)).collect();
+                let files = if paths {
+                    format!(",\"files\":[{}]", files.join(","))
+                } else {
+                    String::new()
+                };
+                format!(
+                    "{{\"tags\":{},\"duration\":{},\"entries\":{}{}}}",
+                    json_value("tags", tags, options),
+                    json_value("duration", &options.unit.format(&total.duration), options),
+                    total.entries,
+                    files
+                )
+            })
+            .collect();
+        writeln!(writer, "[{}]", items.join(","))?;
+        return Ok(());
+    }
+
+    let mut csv_writer = Writer::from_writer(writer);
+    for (tags, total) in accumulated {
+        let mut record = vec![
+            tags.to_string(),
+            options.unit.format(&total.duration),
+            total.entries.to_string(),
+        ];
+        if paths {
+            record.push(total.files.join(","));
+        }
+        csv_writer.write_record(&record)?;
+    }
+    csv_writer.flush()?;
+
+    Ok(())
+}
+
+fn entry_column(column: &str, row: &Row, options: &Options) -> String {
+    let entry = &row.entry;
+    match column {
+        "tags" => entry.tags.clone(),
+        "duration" => options.unit.format(&entry.duration),
+        "file" => display_path(row.path, options),
+        "priority" => entry.priority.map(|p| p.to_string()).unwrap_or_default(),
+        "source" => row.source.to_string(),
+        "date" => entry.date.map(|d| d.to_string()).unwrap_or_default(),
+        "weekday" => entry
+            .date
+            .map(|d| d.strftime("%a").to_string())
+            .unwrap_or_default(),
+        "text" if options.plain_text => markdown::strip(&entry.text),
+        "text" => entry.text.clone(),
+        "id" => entry_id(row, options),
+        "identifier" => options.identifiers.find(&entry.tags),
+        "source_syntax" => entry.syntax.to_string(),
+        "h1" => entry.context.heading(1).to_string(),
+        "h2" => entry.context.heading(2).to_string(),
+        "h3" => entry.context.heading(3).to_string(),
+        "parents" => entry.context.parents.join(" > "),
+        "author" => entry.author.clone(),
+        _ => match column.strip_prefix("note.") {
+            Some(name) => entry
+                .properties
+                .get(name)
+                .map(|value| value.to_string())
+                .unwrap_or_default(),
+            None => match column.strip_prefix("field:") {
+                Some(name) => entry
+                    .field(name)
+                    .map(|value| value.to_string())
+                    .unwrap_or_default(),
+                None => unreachable!("unknown column {}", column),
             },
-            entries[0].1
+        },
+    }
+}
+
+/// The `FULL_COLUMNS` cells for a row, repeated for each of its tags so
+/// tags can be grouped on directly. Untagged entries get one record.
+fn full_records(row: &Row, options: &Options) -> Vec<Vec<(&'static str, Cell)>> {
+    let entry = &row.entry;
+    let vault = vault(row, options);
+    let mut tags: Vec<&str> = entry.tags.split(',').filter(|t| !t.is_empty()).collect();
+    if tags.is_empty() {
+        tags.push("");
+    }
+    tags.into_iter()
+        .map(|tag| {
+            let values = [
+                Cell::Text(entry_id(row, options)),
+                Cell::Text(entry_column("date", row, options)),
+                Cell::Text(tag.to_string()),
+                Cell::Number(entry.duration.total_seconds().to_string()),
+                Cell::Text(entry_column("priority", row, options)),
+                Cell::Text(entry.status_name()),
+                Cell::Text(entry_column("text", row, options)),
+                Cell::Text(entry.heading.clone()),
+                Cell::Text(entry_column("file", row, options)),
+                Cell::Number(entry.line.to_string()),
+                Cell::Text(vault.clone()),
+                Cell::Text(row.source.to_string()),
+                Cell::Text(entry.syntax.to_string()),
+            ];
+            FULL_COLUMNS.into_iter().zip(values).collect()
+        })
+        .collect()
+}
+
+/// A row's entry ID, from its note's path relative to the directory it
+/// was found in, so spelling `-d` another way keeps the same IDs.
+fn entry_id(row: &Row, options: &Options) -> String {
+    let relative = PathStyle::Relative.display(row.path, &options.vaults);
+    row.entry.id(Path::new(&relative))
+}
+
+/// The directory searched that a row's note was found in, if any.
+fn vault(row: &Row, options: &Options) -> String {
+    options
+        .vaults
+        .iter()
+        .find(|vault| row.path.starts_with(vault))
+        .map(|vault| vault.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// A row as `--format parquet` writes it, with the values of `--format
+/// full` typed.
+#[cfg(feature = "parquet")]
+fn parquet_record(row: &Row, options: &Options) -> parquet::Record {
+    let entry = &row.entry;
+    let text = |value: String| Some(value).filter(|value| !value.is_empty());
+    parquet::Record {
+        id: entry_id(row, options),
+        date: entry.date,
+        started: entry
+            .date
+            .zip(entry.started)
+            .map(|(date, time)| date.to_datetime(time)),
+        tags: tag_list(&entry.tags),
+        seconds: entry.duration.total_seconds() as i64,
+        priority: text(entry_column("priority", row, options)),
+        status: text(entry.status_name()),
+        text: entry_column("text", row, options),
+        heading: entry.heading.clone(),
+        file: entry_column("file", row, options),
+        line: entry.line as i64,
+        vault: vault(row, options),
+        source: row.source.to_string(),
+        source_syntax: entry.syntax.to_string(),
+    }
+}
+
+/// A row's `columns` as Dataview inline fields. Durations are decimal
+/// `hours` so queries can sum them, files are wikilinks, and properties
+/// and fields drop their `note.` and `field:` prefixes.
+fn dataview_record<'a>(columns: &[&'a str], row: &Row, options: &Options) -> Vec<(&'a str, Cell)> {
+    columns
+        .iter()
+        .map(|column| match *column {
+            "tags" => ("tags", Cell::List(tag_list(&row.entry.tags))),
+            "duration" => (
+                "hours",
+                Cell::Number(DurationUnit::Hours.format(&row.entry.duration)),
+            ),
+            "file" => (
+                "file",
+                Cell::Text(
+                    row.path
+                        .file_stem()
+                        .map(|stem| format!("[[{}]]", stem.to_string_lossy()))
+                        .unwrap_or_default(),
+                ),
+            ),
+            _ => (
+                column
+                    .strip_prefix("note.")
+                    .or_else(|| column.strip_prefix("field:"))
+                    .unwrap_or(column),
+                Cell::Text(entry_column(column, row, options)),
+            ),
+        })
+        .collect()
+}
+
+/// Renders a column's CSV value as JSON: tags become an array, numeric
+/// durations a number, and empty values null.
+fn json_value(column: &str, value: &str, options: &Options) -> String {
+    match column {
+        "tags" => {
+            let tags: Vec<String> = value
+                .split(',')
+                .filter(|t| !t.is_empty())
+                .map(json::quote)
+                .collect();
+            format!("[{}]", tags.join(","))
+        }
+        "duration" if options.unit != DurationUnit::Compact => value.to_string(),
+        _ if value.is_empty() => "null".to_string(),
+        _ => json::quote(value),
+    }
+}
+
+fn run_cooccur(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let parsed = load_all(entries, options)?;
+    let matrix = CooccurrenceMatrix::from_entries(&parsed);
+
+    match output_format(matches, options, &COOCCUR_FORMATS) {
+        "json" => writeln!(writer, "{}", matrix.to_json())?,
+        _ => {
+            let metric = match matches.get_one::<String>("metric").map(|s| s.as_str()) {
+                Some("duration") => Metric::Duration,
+                _ => Metric::Count,
+            };
+            matrix.write_csv(writer, metric, options.unit)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_burndown(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if options.tags.is_empty() {
+        return Err("burndown requires --tag".into());
+    }
+    let mut estimates = vec![];
+    let mut actuals = vec![];
+    for entry in entries {
+        let Some(date) = date_from_path(entry) else {
+            debug!("skipping {}: no date in file name", entry.display());
+            continue;
+        };
+        let content = read_note(entry)?;
+        for (tags, estimate) in parse_estimates(&content, true, &options.units) {
+            if matches_tags(&tags, options) {
+                estimates.push((date, estimate));
+            }
+        }
+        for time_entry in load_entries(entry, options)? {
+            actuals.push((time_entry.date.unwrap_or(date), time_entry.duration));
+        }
+    }
+
+    let rows = burndown::burndown(&estimates, &actuals);
+    match output_format(matches, options, &BURNDOWN_FORMATS) {
+        "chart" => burndown::write_chart(&rows, writer, options.unit, &options.locale),
+        _ => burndown::write_csv(&rows, writer, options.unit),
+    }
+}
+
+fn run_digest(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let month = match matches.get_one::<Date>("month") {
+        Some(month) => *month,
+        None => jiff::Zoned::now()
+            .date()
+            .first_of_month()
+            .checked_sub(jiff::Span::new().months(1))?,
+    };
+    let parsed = load_all(entries, options)?;
+    digest::digest(&parsed, month).write_html(writer, options.unit, &options.locale)
+}
+
+fn run_forecast(
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if options.tags.is_empty() {
+        return Err("forecast requires --tag".into());
+    }
+    let mut estimate = TimeDuration::default();
+    let mut actuals = vec![];
+    for entry in entries {
+        let content = read_note(entry)?;
+        for (tags, task_estimate) in parse_estimates(&content, true, &options.units) {
+            if matches_tags(&tags, options) {
+                estimate += &task_estimate;
+            }
+        }
+        for time_entry in load_entries(entry, options)? {
+            actuals.push((time_entry.date, time_entry.duration));
+        }
+    }
+    if estimate.total_seconds() == 0 {
+        return Err("no timeEstimate on the tagged tasks to forecast".into());
+    }
+
+    let forecast = forecast::forecast(estimate, &actuals, jiff::Zoned::now().date());
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record([
+        "estimate",
+        "tracked",
+        "remaining",
+        "active_days",
+        "earliest",
+        "latest",
+    ])?;
+    csv_writer.write_record(forecast.record(options.unit))?;
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn run_billing(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    if options.profile.rates.is_empty() {
+        return Err("billing requires rates in the config file".into());
+    }
+    let mut parsed = vec![];
+    for entry in entries {
+        let file = display_path(entry, options);
+        parsed.extend(
+            load_entries(entry, options)?
+                .into_iter()
+                .map(|e| (e, file.clone())),
+        );
+    }
+    if let Some(increment) = matches.get_one::<TimeDuration>("round") {
+        let rounding = matches.get_one::<String>("round-mode").unwrap().parse()?;
+        let adjustments =
+            billing::round_entries(&mut parsed, &options.profile.rates, increment, rounding);
+        if let Some(audit) = matches.get_one::<PathBuf>("audit") {
+            let file = fs::File::create(audit)
+                .map_err(|e| format!("failed to create {}: {}", audit.display(), e))?;
+            billing::write_ledger(&adjustments, file, options.unit)?;
+        }
+    }
+    let parsed: Vec<TimeEntry> = parsed.into_iter().map(|(e, _)| e).collect();
+    let mut lines = billing::bill(&parsed, &options.profile.rates, &options.profile.overtime);
+    let currency = matches
+        .get_one::<String>("currency")
+        .or(options.profile.currency.as_ref());
+    if let Some(currency) = currency {
+        let exchange_rate = options
+            .profile
+            .exchange_rates
+            .get(currency)
+            .ok_or_else(|| format!("no exchange rate for '{}' in the config file", currency))?;
+        billing::convert(&mut lines, currency, *exchange_rate);
+    }
+    match output_format(matches, options, &BILLING_FORMATS) {
+        "json" => writeln!(writer, "{}", billing::to_json(&lines))?,
+        _ => billing::write_csv(&lines, writer, options.unit)?,
+    }
+    Ok(())
+}
+
+fn run_doctor(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut files = vec![];
+    for entry in entries {
+        let path = PathBuf::from(display_path(entry, options));
+        files.push((path, read_note(entry)?));
+    }
+    let findings = doctor::check(&files, &options.identifiers, &options.exclusives);
+    for finding in &findings {
+        writeln!(writer, "{}", finding)?;
+    }
+    let (mut parsed, mut total) = (0, 0);
+    for (path, content) in &files {
+        let coverage = parse_coverage(content);
+        let lines = coverage.parsed.len() + coverage.unparsed.len();
+        if matches.get_flag("coverage") && lines > 0 {
+            writeln!(
+                writer,
+                "{}: {} of {} time field lines parsed",
+                path.display(),
+                coverage.parsed.len(),
+                lines
+            )?;
+        }
+        parsed += coverage.parsed.len();
+        total += lines;
+    }
+    writeln!(
+        writer,
+        "{} files checked, {} of {} time field lines parsed, {} findings",
+        files.len(),
+        parsed,
+        total,
+        findings.len()
+    )?;
+    Ok(())
+}
+
+fn run_query(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let query = matches.get_one::<Query>("query").unwrap();
+    let format = output_format(matches, options, &QUERY_FORMATS);
+    let mut rows = vec![];
+    for entry in entries {
+        let file = display_path(entry, options);
+        for time_entry in load_entries(entry, options)? {
+            if query.matches(&time_entry, &file) {
+                rows.push(Row {
+                    entry: time_entry,
+                    path: entry,
+                    source: NOTES_SOURCE,
+                });
+            }
+        }
+    }
+    let Some(group) = &query.group else {
+        return write_rows(
+            &["tags", "duration", "file"],
+            &rows,
+            format,
+            options,
+            writer,
+        );
+    };
+
+    write_groups(group, &rows, format, options, writer)
+}
+
+/// Runs a query over the entries as `--format full` records, writing its
+/// result with a header.
+#[cfg(feature = "sql")]
+fn run_sql(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let format = output_format(matches, options, &SQL_FORMATS);
+    let mut records = vec![];
+    for entry in entries {
+        for time_entry in load_entries(entry, options)? {
+            let row = Row {
+                entry: time_entry,
+                path: entry,
+                source: NOTES_SOURCE,
+            };
+            for record in full_records(&row, options) {
+                records.push(record.into_iter().map(|(_, cell)| cell).collect());
+            }
+        }
+    }
+    let sql = matches.get_one::<String>("sql").unwrap();
+    let table = sql::query("entries", &FULL_COLUMNS, &records, sql)?;
+    let columns: Vec<&str> = table.columns.iter().map(|c| c.as_str()).collect();
+    let mut sink: Box<dyn ReportWriter + '_> = match format {
+        "json" => Box::new(JsonWriter::new(writer)),
+        _ => Box::new(CsvWriter::with_header(writer, &columns)?),
+    };
+    for row in table.rows {
+        let record: Vec<(&str, Cell)> = columns.iter().copied().zip(row).collect();
+        sink.write_entry(&record)?;
+    }
+    sink.finish()
+}
+
+/// Writes the total duration and number of entries per `group` key, with a
+/// header naming the group.
+fn write_groups(
+    group: &Group,
+    rows: &[Row],
+    format: &str,
+    options: &Options,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    check_group(group, options)?;
+    let totals = query::group_totals(
+        group,
+        &options.sprints,
+        rows.iter()
+            .map(|row| (&row.entry, display_path(row.path, options))),
+    );
+    let key = group.to_string();
+    if format == "json" {
+        let items: Vec<String> = totals
+            .iter()
+            .map(|(group, (duration, count))| {
+                format!(
+                    "{{{}:{},\"duration\":{},\"entries\":{}}}",
+                    json::quote(&key),
+                    json_value(&key, group, options),
+                    json_value("duration", &options.unit.format(duration), options),
+                    count
+                )
+            })
+            .collect();
+        writeln!(writer, "[{}]", items.join(","))?;
+        return Ok(());
+    }
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record([key.as_str(), "duration", "entries"])?;
+    for (group, (duration, count)) in totals {
+        csv_writer.write_record(&[group, options.unit.format(&duration), count.to_string()])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn write_gaps(
+    rows: &[Row],
+    hours: WorkHours,
+    threshold: &TimeDuration,
+    format: &str,
+    options: &Options,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let gaps = gaps::find_gaps(rows.iter().map(|row| &row.entry), hours, threshold);
+    let header = ["date", "start", "end", "duration"];
+    if format == "json" {
+        let items: Vec<String> = gaps
+            .iter()
+            .map(|gap| {
+                let fields: Vec<String> = header
+                    .iter()
+                    .zip(gap.record(options.unit))
+                    .map(|(key, value)| {
+                        format!("{}:{}", json::quote(key), json_value(key, &value, options))
+                    })
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            })
+            .collect();
+        writeln!(writer, "[{}]", items.join(","))?;
+        return Ok(());
+    }
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(header)?;
+    for gap in &gaps {
+        csv_writer.write_record(gap.record(options.unit))?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Creates the daily note of `--date` in the first directory searched,
+/// refusing to overwrite one that exists.
+fn run_new_day(
+    matches: &ArgMatches,
+    options: &Options,
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let Some(dir) = options.vaults.first() else {
+        return Err("new-day needs a --dir to create the note in".into());
+    };
+    let date = matches
+        .get_one::<Date>("date")
+        .copied()
+        .unwrap_or_else(|| jiff::Zoned::now().date());
+    let template = match matches.get_one::<PathBuf>("template") {
+        Some(path) => read_note(path)?,
+        None => String::new(),
+    };
+    let pbis: Vec<u64> = matches
+        .get_many::<u64>("pbi")
+        .unwrap_or_default()
+        .copied()
+        .collect();
+    let path = dir.join(format!("{}.md", date));
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    fs::write(&path, scaffold::daily_note(&template, date, &pbis))
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    writeln!(writer, "{}", path.display())?;
+    Ok(())
+}
+
+fn run_retag(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let from = matches.get_one::<String>("from").unwrap();
+    let to = matches.get_one::<String>("to").unwrap();
+    let mut writeback = writeback(matches);
+    for entry in entries {
+        let content = read_note(entry)?;
+        let (retagged, changes) = retag(&content, from, to);
+        if changes.is_empty() {
+            continue;
+        }
+        let path = display_path(entry, options);
+        if writeback.show == Show::List {
+            for change in &changes {
+                writeln!(writer, "{}:{}", path, change.line)?;
+                writeln!(writer, "-{}", change.before)?;
+                writeln!(writer, "+{}", change.after)?;
+            }
+        }
+        let relative = PathStyle::Relative.display(entry, &options.vaults);
+        writeback.apply(
+            entry,
+            Path::new(&relative),
+            &path,
+            (&content, &retagged),
+            &changes,
+            writer,
+        )?;
+    }
+    writeback.finish("changed", "would change", writer)
+}
+
+/// Reads `--write`, its backups, and how changes are shown for a
+/// subcommand taking `writeback_args`.
+fn writeback(matches: &ArgMatches) -> Writeback {
+    let backup = match matches.get_one::<PathBuf>("backup_dir") {
+        Some(dir) => Backup::Dir(dir.clone()),
+        None if matches.get_flag("no_backup") => Backup::None,
+        None => Backup::Beside,
+    };
+    let show = match (matches.get_flag("diff"), matches.get_flag("json_diff")) {
+        (true, _) => Show::Diff,
+        (_, true) => Show::JsonDiff,
+        _ => Show::List,
+    };
+    Writeback::new(matches.get_flag("write"), backup, show)
+}
+
+fn run_suggest_tags(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let min_similarity = *matches.get_one::<f64>("min_similarity").unwrap();
+    let mut writeback = writeback(matches);
+    let mut parsed = vec![];
+    for entry in entries {
+        parsed.push((entry, load_entries(entry, options)?));
+    }
+    let suggester = Suggester::new(parsed.iter().flat_map(|(_, entries)| entries));
+    for (entry, time_entries) in &parsed {
+        let suggestions: BTreeMap<usize, _> = time_entries
+            .iter()
+            .filter(|time_entry| time_entry.tags.is_empty())
+            .filter_map(|time_entry| {
+                let suggestion = suggester.suggest(&time_entry.text, min_similarity)?;
+                Some((time_entry.line, (&time_entry.text, suggestion)))
+            })
+            .collect();
+        if suggestions.is_empty() {
+            continue;
+        }
+        let path = display_path(entry, options);
+        let content = read_note(entry)?;
+        let mut tagged = String::with_capacity(content.len());
+        let mut changes = vec![];
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            let Some((text, suggestion)) = suggestions.get(&(index + 1)) else {
+                tagged.push_str(line);
+                continue;
+            };
+            // Entries from embedded notes or legacy durations aren't
+            // written as they are parsed on the line they're reported at.
+            let holds_entry = parse_time_entries(line, true, &options.units)
+                .iter()
+                .any(|parsed| parsed.tags.is_empty() && parsed.text == **text);
+            if !holds_entry {
+                warn!("{}:{}: cannot add tags to this line", path, index + 1);
+                tagged.push_str(line);
+                continue;
+            }
+            let tags: Vec<&str> = suggestion.tags.split(',').collect();
+            let after = add_tags(line, &tags);
+            let change = Change {
+                line: index + 1,
+                before: line.trim_end_matches(['\r', '\n']).to_string(),
+                after: after.trim_end_matches(['\r', '\n']).to_string(),
+            };
+            if writeback.show == Show::List {
+                writeln!(
+                    writer,
+                    "{}:{}: {} ({:.2} like '{}')",
+                    path,
+                    change.line,
+                    tags.join(" "),
+                    suggestion.similarity,
+                    suggestion.example
+                )?;
+                writeln!(writer, "-{}", change.before)?;
+                writeln!(writer, "+{}", change.after)?;
+            }
+            tagged.push_str(&after);
+            changes.push(change);
+        }
+        if changes.is_empty() {
+            continue;
+        }
+        let relative = PathStyle::Relative.display(entry, &options.vaults);
+        writeback.apply(
+            entry,
+            Path::new(&relative),
+            &path,
+            (&content, &tagged),
+            &changes,
+            writer,
+        )?;
+    }
+    writeback.finish("tagged", "would tag", writer)
+}
+
+/// Accepts a `--jira-url` or `--toggl-url` without a trailing slash. It
+/// must be HTTPS, so the API token is never sent in the clear, unless it is
+/// on this machine.
+fn parse_api_url(url: &str) -> Result<String, String> {
+    let url = url.trim_end_matches('/');
+    if url.starts_with("https://") {
+        return Ok(url.to_string());
+    }
+    let authority = url
+        .strip_prefix("http://")
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or_default();
+    let host = match authority.find(']') {
+        Some(end) => &authority[..=end],
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    match host {
+        "localhost" | "127.0.0.1" | "[::1]" => Ok(url.to_string()),
+        _ => Err(format!(
+            "'{}' must be an https:// URL, so the API token isn't sent in the clear",
+            url
+        )),
+    }
+}
+
+/// An HTTP client retrying `retries` times, keeping to `--rate-limit`.
+fn http_client(options: &Options, retries: u32) -> http::Client {
+    let mut client = http::Client::new(retries);
+    client.interval = options.request_interval;
+    client
+}
+
+/// Accepts a `--rate-limit` of requests a second.
+fn parse_rate(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!(
+            "expected requests a second above 0, got '{}'",
+            text
+        )),
+    }
+}
+
+/// Accepts a `--min-similarity` score from 0 to 1.
+fn parse_similarity(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(score) if (0.0..=1.0).contains(&score) => Ok(score),
+        _ => Err(format!("expected a score from 0 to 1, got '{}'", text)),
+    }
+}
+
+/// Accepts a nonzero duration like `15m`, for `--round`, `--gaps`, and
+/// other thresholds.
+fn parse_increment(text: &str) -> Result<TimeDuration, String> {
+    let duration = parse_duration(text);
+    if duration.total_seconds() == 0 {
+        return Err(format!("'{}' is not a duration like 15m", text));
+    }
+    Ok(duration)
+}
+
+/// The `--granularity` increment and rounding, from the command line or
+/// else the profile; `None` when durations keep their seconds.
+fn granularity(
+    matches: &ArgMatches,
+    profile: &Profile,
+) -> Result<Option<(TimeDuration, Rounding)>, Box<dyn Error>> {
+    let setting = |id: &str, configured: &Option<String>| {
+        matches
+            .get_one::<String>(id)
+            .or(configured.as_ref())
+            .cloned()
+    };
+    let increment = match setting("granularity", &profile.granularity).as_deref() {
+        None | Some("second") => return Ok(None),
+        Some("minute") => TimeDuration::from_seconds(60),
+        Some(other) => {
+            return Err(
+                format!("unknown granularity '{}'; expected second or minute", other).into(),
+            );
+        }
+    };
+    let rounding = match setting("granularity_rounding", &profile.granularity_rounding) {
+        Some(rounding) => rounding.parse()?,
+        None => Rounding::Nearest,
+    };
+    Ok(Some((increment, rounding)))
+}
+
+/// Reads a `--columns` item: one of `ENTRY_COLUMNS`, `note.NAME` for a
+/// frontmatter property, or `field:NAME` for an inline field.
+fn parse_column(column: &str) -> Result<String, String> {
+    if ENTRY_COLUMNS.contains(&column)
+        || column.strip_prefix("note.").is_some_and(|n| !n.is_empty())
+        || column.strip_prefix("field:").is_some_and(|n| !n.is_empty())
+    {
+        Ok(column.to_string())
+    } else {
+        Err(format!(
+            "unknown column '{}'; expected note.NAME, field:NAME, or one of {}",
+            column,
+            ENTRY_COLUMNS.join(", ")
+        ))
+    }
+}
+
+/// Accepts a `#tag` argument.
+fn parse_tag(tag: &str) -> Result<String, String> {
+    if tag.len() > 1 && tag.starts_with('#') && !tag.contains(char::is_whitespace) {
+        Ok(tag.to_string())
+    } else {
+        Err(format!("expected a tag like '#meetings', got '{}'", tag))
+    }
+}
+
+fn run_verify_issues(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let base = matches.get_one::<String>("jira_url").unwrap();
+    // Only read from the environment, where other users can't see it as
+    // they can arguments.
+    let token = std::env::var("JIRA_TOKEN").ok();
+    let authorization = token.map(|token| format!("Bearer {}", token));
+    let headers: Vec<(&str, &str)> = authorization
+        .iter()
+        .map(|value| ("Authorization", value.as_str()))
+        .collect();
+    let parsed = load_all(entries, options)?;
+    let project = matches.get_one::<String>("project").map(|s| s.as_str());
+    let usages = issues::usages(&parsed, &options.identifiers, project);
+    let count = usages.len();
+    let mut client = http_client(options, 3);
+    let findings = issues::check(usages, matches.get_flag("check_resolved"), |key| {
+        let url = format!("{}/rest/api/2/issue/{}?fields=resolutiondate", base, key);
+        match client.get(&url, &headers)? {
+            (200, body) => Ok(issues::parse_status(&body)),
+            (404, _) => Ok(issues::Status::Missing),
+            (status, _) => Err(format!("{} answered {}", url, status).into()),
+        }
+    })?;
+    for finding in &findings {
+        writeln!(writer, "{}", finding)?;
+    }
+    if !findings.is_empty() {
+        return Err(format!("{} of {} issue tags dangling", findings.len(), count).into());
+    }
+    writeln!(writer, "{} issue tags verified", count)?;
+    Ok(())
+}
+
+fn run_notify(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let template = match matches.get_one::<PathBuf>("payload_template") {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?,
+        None => notify::DEFAULT_TEMPLATE.to_string(),
+    };
+    let parsed = load_all(entries, options)?;
+    let values = notify::values(
+        &parsed,
+        options.unit,
+        options.since.map(|d| d.to_string()),
+        options.until.map(|d| d.to_string()),
+    );
+    let payload = notify::render(&template, &values)?;
+    match matches.get_one::<String>("webhook") {
+        Some(url) if !matches.get_flag("dry_run") => {
+            let mut client = http_client(options, *matches.get_one::<u32>("retries").unwrap());
+            let status = client.post(url, &payload)?;
+            info!("{} answered {}", url, status);
+        }
+        _ => writeln!(writer, "{}", payload)?,
+    }
+    Ok(())
+}
+
+fn run_serve(
+    matches: &ArgMatches,
+    options: &Options,
+    scan: &dyn Fn() -> Result<Vec<PathBuf>, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let address = matches.get_one::<String>("listen").unwrap();
+    let listener =
+        TcpListener::bind(address).map_err(|e| format!("cannot listen on {}: {}", address, e))?;
+    info!(
+        "serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+    let on_change = matches.get_one::<String>("on_change");
+    let last = RefCell::new(None);
+    metrics::serve(listener, metrics::TIMEOUT, || {
+        let parsed = load_all(&scan()?, options)?;
+        let body = metrics::render(&parsed, jiff::Zoned::now().date());
+        if let Some(command) = on_change
+            && last.replace(Some(body.clone())).as_ref() != Some(&body)
+        {
+            run_hook(command);
+        }
+        Ok(body)
+    })
+}
+
+/// Runs an `--on-change` command through the shell, waiting for it. A
+/// failing command is logged, so it can't stop the daemon running it.
+fn run_hook(command: &str) {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    debug!("running {}", command);
+    match std::process::Command::new(shell)
+        .args([flag, command])
+        .status()
+    {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("--on-change command '{}' failed: {}", command, status),
+        Err(e) => warn!("cannot run --on-change command '{}': {}", command, e),
+    }
+}
+
+/// Serves `parseFile`, `summary`, and `invalidate` requests on stdin until
+/// it closes, keeping parsed notes between requests.
+fn run_rpc(
+    options: &Options,
+    scan: &dyn Fn() -> Result<Vec<PathBuf>, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut cache = rpc::Cache::default();
+    let load = |path: &Path| load_entries(path, options);
+    let path_param = |params: &Json| -> Result<Option<PathBuf>, RpcError> {
+        match params.get("path") {
+            None | Some(Json::Null) => Ok(None),
+            Some(Json::String(path)) => Ok(Some(PathBuf::from(path))),
+            Some(_) => Err(RpcError::invalid_params("path must be a string")),
+        }
+    };
+    let date_param = |params: &Json, name: &str| -> Result<Option<Date>, RpcError> {
+        match params.get(name) {
+            None | Some(Json::Null) => Ok(None),
+            Some(Json::String(date)) => date
+                .parse()
+                .map(Some)
+                .map_err(|_| RpcError::invalid_params(format!("{} must be YYYY-MM-DD", name))),
+            Some(_) => Err(RpcError::invalid_params(format!(
+                "{} must be a string",
+                name
+            ))),
+        }
+    };
+    rpc::serve(
+        std::io::stdin().lock(),
+        &mut std::io::stdout(),
+        |method, params| match method {
+            "parseFile" => {
+                let path = path_param(params)?
+                    .ok_or_else(|| RpcError::invalid_params("parseFile needs a path"))?;
+                match params.get("content") {
+                    None | Some(Json::Null) => {}
+                    Some(Json::String(content)) => {
+                        let entries = parse_note(&path, content.as_bytes(), options)
+                            .map_err(RpcError::failed)?;
+                        cache.set_unsaved(&path, entries);
+                    }
+                    Some(_) => return Err(RpcError::invalid_params("content must be a string")),
+                }
+                let entries = cache.entries(&path, load).map_err(RpcError::failed)?;
+                Ok(rpc::entries_json(entries, options.unit))
+            }
+            "summary" => {
+                let (since, until) = (date_param(params, "since")?, date_param(params, "until")?);
+                let paths = scan().map_err(RpcError::failed)?;
+                let mut entries = vec![];
+                for path in &paths {
+                    let parsed = cache.entries(path, load).map_err(RpcError::failed)?;
+                    entries.extend(
+                        parsed
+                            .iter()
+                            .filter(|entry| {
+                                since.is_none_or(|since| {
+                                    entry.date.is_some_and(|date| date >= since)
+                                }) && until.is_none_or(|until| {
+                                    entry.date.is_some_and(|date| date <= until)
+                                })
+                            })
+                            .cloned(),
+                    );
+                }
+                Ok(rpc::summary_json(paths.len(), &entries, options.unit))
+            }
+            "invalidate" => {
+                let path = path_param(params)?;
+                Ok(cache.invalidate(path.as_deref()).to_string())
+            }
+            _ => Err(RpcError {
+                code: rpc::METHOD_NOT_FOUND,
+                message: format!("unknown method '{}'", method),
+            }),
+        },
+    )
+}
+
+fn run_streak(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let parsed = load_all(entries, options)?;
+    let today = jiff::Zoned::now().date();
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["goal", "this_week", "current", "longest"])?;
+    for goal in matches.get_many::<Goal>("goal").unwrap() {
+        let streak = streak::streak(&parsed, goal, today);
+        csv_writer.write_record(&[
+            goal.to_string(),
+            options.unit.format(&streak.this_week),
+            streak.current.to_string(),
+            streak.longest.to_string(),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Lists every target's periods from `--since` (or the first dated entry)
+/// to `--until` (or today).
+fn run_targets(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut budgets = Target::from_config(&options.profile.targets)?;
+    for target in matches.get_many::<Target>("target").unwrap_or_default() {
+        budgets.retain(|budget| budget.tag != target.tag);
+        budgets.push(target.clone());
+    }
+    if budgets.is_empty() {
+        return Err("no targets; add a [targets] table to the config or give --target".into());
+    }
+    let parsed = load_all(entries, options)?;
+    let today = jiff::Zoned::now().date();
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["target", "period", "total", "status"])?;
+    let first = parsed.iter().filter_map(|entry| entry.date).min();
+    if let Some(since) = options.since.or(first) {
+        let until = options.until.unwrap_or(today);
+        for actual in targets::actuals(&parsed, &budgets, since, until, today) {
+            csv_writer.write_record(actual.record(options.unit))?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn run_sessions(
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let parsed = load_all(entries, options)?;
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["identifier", "start", "end", "days", "total", "gap"])?;
+    for run in runs::runs(&parsed, &options.identifiers) {
+        csv_writer.write_record(run.record(options.unit))?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Prints the tag hierarchy for `export --tags-json`.
+fn run_export(
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let parsed = load_all(entries, options)?;
+    let tree = tagtree::tag_tree(&parsed);
+    writeln!(writer, "{}", tagtree::to_json(&tree, &parsed))?;
+    Ok(())
+}
+
+/// Lists the sparse weekdays from `--since` (or the first daily note) to
+/// `--until` (or today).
+fn run_gaps(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut notes = vec![];
+    let mut parsed = vec![];
+    for entry in entries {
+        if let Some(date) = date_from_path(entry) {
+            notes.push((date, display_path(entry, options)));
+        }
+        parsed.extend(load_entries(entry, options)?);
+    }
+    notes.sort();
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["date", "weekday", "tracked", "note"])?;
+    if let Some(since) = options.since.or(notes.first().map(|(date, _)| *date)) {
+        let until = options.until.unwrap_or_else(|| jiff::Zoned::now().date());
+        let threshold = matches.get_one::<TimeDuration>("below").unwrap();
+        for day in gaps::sparse_days(&notes, &parsed, since, until, threshold) {
+            csv_writer.write_record(day.record(options.unit))?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Writes the reconciliation plan as CSV, asking on stderr whether to go
+/// ahead with each create and update unless `--auto`. Declined ones are
+/// skipped.
+fn run_reconcile(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let remote = match matches.get_one::<String>("toggl_url") {
+        Some(base) => fetch_toggl(base, options)?,
+        None => read_remote(matches, options)?,
+    };
+    let parsed = load_all(entries, options)?;
+
+    let mut plan = reconcile::plan(&parsed, &remote, matches.get_flag("update"));
+    if !matches.get_flag("auto") {
+        let mut answers = std::io::stdin().lines();
+        for worklog in &mut plan {
+            if worklog.action == Action::Skip {
+                continue;
+            }
+            let mut prompt = std::io::stderr();
+            write!(prompt, "{}? [y/N] ", worklog)?;
+            prompt.flush()?;
+            let answer = answers.next().transpose()?.unwrap_or_default();
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                worklog.action = Action::Skip;
+            }
+        }
+    }
+    let mut csv_writer = Writer::from_writer(writer);
+    csv_writer.write_record(["action", "date", "tags", "local", "remote"])?;
+    for worklog in &plan {
+        csv_writer.write_record(worklog.record(options.unit))?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Reads the remote worklogs from the `--remote` export.
+fn read_remote(matches: &ArgMatches, options: &Options) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    let map = match matches.get_one::<String>("map") {
+        Some(path) => ImportMap::load(Path::new(path))?,
+        None => ImportMap::default(),
+    };
+    if map.date.is_none() {
+        return Err("reconciling needs the remote's dates; set date in the --map file".into());
+    }
+    let path = matches.get_one::<PathBuf>("remote").unwrap();
+    let file =
+        fs::File::open(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    let remote = import::read_csv(file, &map)
+        .map_err(|e| format!("cannot import {}: {}", path.display(), e))?;
+    filter_entries(remote, path, options)
+}
+
+/// Fetches the remote worklogs from the Toggl Track API at `base`, from
+/// `--since` through `--until` or today.
+fn fetch_toggl(base: &str, options: &Options) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    // Only read from the environment, where other users can't see it as
+    // they can arguments.
+    let token = std::env::var("TOGGL_API_TOKEN")
+        .map_err(|_| "set $TOGGL_API_TOKEN to fetch worklogs from Toggl Track")?;
+    let authorization = http::basic_auth(&token, "api_token");
+    let since = options
+        .since
+        .ok_or("fetching from Toggl Track needs --since")?;
+    let until = options.until.unwrap_or_else(|| jiff::Zoned::now().date());
+    // Toggl dates the range in UTC; a day either side covers every time
+    // zone, and the date filters drop what falls outside it here.
+    let url = format!(
+        "{}/api/v9/me/time_entries?start_date={}&end_date={}",
+        base,
+        since.yesterday()?,
+        until.tomorrow()?.tomorrow()?
+    );
+    let headers = [("Authorization", authorization.as_str())];
+    match http_client(options, 3).get(&url, &headers)? {
+        (200, body) => {
+            let remote = import::read_toggl(&body, &jiff::tz::TimeZone::system())
+                .map_err(|e| format!("cannot import {}: {}", url, e))?;
+            filter_entries(remote, Path::new(base), options)
+        }
+        (status, _) => Err(format!("{} answered {}", url, status).into()),
+    }
+}
+
+fn run_verify(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let path = matches.get_one::<PathBuf>("baseline").unwrap();
+    let file =
+        fs::File::open(path).map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+    let baseline = verify::read_baseline(file)?;
+    let parsed = load_all(entries, options)?;
+    let discrepancies = verify::compare(&baseline, &parsed, options.unit);
+    for discrepancy in &discrepancies {
+        writeln!(writer, "{}", discrepancy)?;
+    }
+    if !discrepancies.is_empty() {
+        return Err(format!(
+            "{} discrepancies against {}",
+            discrepancies.len(),
+            path.display()
+        )
+        .into());
+    }
+    writeln!(
+        writer,
+        "{} rows of {} reproduced",
+        baseline.len(),
+        path.display()
+    )?;
+    Ok(())
+}
+
+fn run_stats(
+    matches: &ArgMatches,
+    options: &Options,
+    entries: &[PathBuf],
+    writer: &mut dyn Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut files = vec![];
+    for entry in entries {
+        files.push((date_from_path(entry), load_entries(entry, options)?));
+    }
+    let stats = Stats::from_files(&files, *matches.get_one::<usize>("top").unwrap());
+    match output_format(matches, options, &STATS_FORMATS) {
+        "json" => writeln!(writer, "{}", stats.to_json())?,
+        _ => stats.write_text(writer, options.unit, &options.locale)?,
+    }
+    Ok(())
+}
+
+/// The command's `--format`, or the profile's format when not given on the
+/// command line and `supported` by the command.
+fn output_format<'a>(matches: &'a ArgMatches, options: &'a Options, supported: &[&str]) -> &'a str {
+    let format = matches.get_one::<String>("format").unwrap();
+    if matches.value_source("format") == Some(ValueSource::CommandLine) {
+        return format;
+    }
+    match &options.profile.format {
+        Some(profile_format) if supported.contains(&profile_format.as_str()) => profile_format,
+        Some(profile_format) => {
+            warn!("ignoring unsupported profile format '{}'", profile_format);
+            format
+        }
+        None => format,
+    }
+}
+
+fn matches_priority(entry: &TimeEntry, options: &Options) -> bool {
+    options.priorities.is_empty() || options.priorities.contains(&entry.priority)
+}
+
+/// Whether the entry's checkbox is one of `--checkbox-states`, counting `X`
+/// as `x`. Entries that aren't tasks have no checkbox and always match.
+fn matches_checkbox(entry: &TimeEntry, options: &Options) -> bool {
+    let status = match entry.status {
+        None => return true,
+        Some('X') => 'x',
+        Some(status) => status,
+    };
+    options.checkbox_states.is_empty() || options.checkbox_states.contains(&status)
+}
+
+/// Reads a `--checkbox-states` item: a status name or a checkbox character.
+fn parse_checkbox_state(s: &str) -> Result<char, String> {
+    match s {
+        "todo" => Ok(' '),
+        "done" => Ok('x'),
+        "cancelled" => Ok('-'),
+        "in-progress" => Ok('/'),
+        _ => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(if c == 'X' { 'x' } else { c }),
+                _ => Err(format!(
+                    "invalid checkbox state '{}'; expected a character or todo, done, in-progress, cancelled",
+                    s
+                )),
+            }
+        }
+    }
+}
+
+/// Whether the entry's date is within `--since`/`--until`. Undated entries
+/// only match when neither is given.
+fn matches_date(entry: &TimeEntry, options: &Options) -> bool {
+    if options.since.is_none() && options.until.is_none() {
+        return true;
+    }
+    entry.date.is_some_and(|date| {
+        options.since.is_none_or(|since| date >= since)
+            && options.until.is_none_or(|until| date <= until)
+    })
+}
+
+/// Whether the entry is on (or off) a weekend as `--only-weekends` or
+/// `--exclude-weekends` ask. Undated entries only match when neither is
+/// given.
+fn matches_weekend(entry: &TimeEntry, options: &Options) -> bool {
+    options
+        .weekends
+        .is_none_or(|weekends| entry.date.is_some_and(|d| is_weekend(d) == weekends))
+}
+
+fn matches_text(entry: &TimeEntry, options: &Options) -> bool {
+    options
+        .text_match
+        .as_ref()
+        .is_none_or(|re| re.is_match(&entry.text))
+        && !options
+            .text_exclude
+            .as_ref()
+            .is_some_and(|re| re.is_match(&entry.text))
+}
+
+fn matches_tags(tags: &str, options: &Options) -> bool {
+    options.tags.is_empty() || options.tags.iter().any(|tag| has_tag(tags, tag))
+}
+
+/// Parses the time entries of every file, in order.
+fn load_all(paths: &[PathBuf], options: &Options) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    let mut parsed = vec![];
+    for path in paths {
+        parsed.extend(load_entries(path, options)?);
+    }
+    Ok(parsed)
+}
+
+/// Parses a file's time entries, closing or flagging any open sessions.
+fn load_entries(path: &Path, options: &Options) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    debug!("parsing {}", path.display());
+    let note = timings::time(Phase::Io, || open_note(path, options.map_notes))?;
+    timings::time(Phase::Parsing, || {
+        // Embedded notes and legacy durations may add entries without a
+        // field.
+        if !options.embeds && !options.legacy_durations && !mentions_time_fields(note.bytes()) {
+            debug!("no time fields in {}", path.display());
+            return Ok(vec![]);
+        }
+        parse_note(path, note.bytes(), options)
+    })
+}
+
+/// Parses the time entries of a note's `content`, as `load_entries` does
+/// once it has read the note at `path`.
+fn parse_note(
+    path: &Path,
+    content: &[u8],
+    options: &Options,
+) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    let front = front_matter(content);
+    let properties = parse_properties(&front);
+    if let Some(key) = &options.archived_key
+        && properties.get(key) == Some(&Value::Bool(true))
+    {
+        debug!("skipping archived note {}", path.display());
+        return Ok(vec![]);
+    }
+    // Embeds and legacy durations rewrite the note, so only they need all
+    // of it as text.
+    let whole = || {
+        std::str::from_utf8(content)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))
+    };
+    // Entries from embedded notes are reported at the embed's line.
+    let expanded = match options.embeds && path.is_file() {
+        true => Some(embed::expand(whole()?, path)),
+        false => None,
+    };
+    let (mut parsed, lines) = match &expanded {
+        Some(expanded) => (expanded.content.as_bytes(), Some(&expanded.lines)),
+        None => (content, None),
+    };
+    let legacy = match options.legacy_durations {
+        true => {
+            let text = match &expanded {
+                Some(expanded) => expanded.content.as_str(),
+                None => whole()?,
+            };
+            Some(rewrite_legacy_durations(text, &options.units))
+        }
+        false => None,
+    };
+    if let Some(legacy) = &legacy {
+        parsed = legacy.content.as_bytes();
+    }
+    let scan = scan_note(parsed, true, &options.units);
+    let mut entries = scan.entries;
+    if let Some(legacy) = &legacy {
+        for entry in &mut entries {
+            if legacy.lines.contains(&entry.line) {
+                entry.syntax = Syntax::Legacy;
+            }
+        }
+    }
+    if log::log_enabled!(log::Level::Info) {
+        let scanned: Vec<usize> = scan
+            .lines
+            .iter()
+            .map(|&line| lines.map_or(line, |lines| lines[line - 1]))
+            .collect();
+        let coverage = coverage(content, &scanned);
+        for line in &coverage.unparsed {
+            debug!("{}:{}: time field not parsed", path.display(), line);
+        }
+        info!(
+            "{}: {} of {} time field lines parsed",
+            path.display(),
+            coverage.parsed.len(),
+            coverage.parsed.len() + coverage.unparsed.len()
         );
     }
+
+    let date = date_from_path(path);
+    let mut sessions = scan.sessions;
+    if let Some(lines) = lines {
+        for entry in &mut entries {
+            entry.line = lines[entry.line - 1];
+        }
+        for session in &mut sessions {
+            session.entry.line = lines[session.entry.line - 1];
+        }
+    }
+    for session in sessions {
+        let closed = match (options.close_at, date) {
+            (Some(close_at), Some(date)) => {
+                let now = jiff::Zoned::now().datetime();
+                close_session(date, session.started, close_at, now)
+            }
+            _ => None,
+        };
+        match closed {
+            Some(duration) => entries.push(TimeEntry {
+                duration,
+                started: Some(session.started),
+                ..session.entry
+            }),
+            None => warn!(
+                "{}:{}: open session started at {}",
+                path.display(),
+                session.entry.line,
+                session.started
+            ),
+        }
+    }
+
+    let note_date = match options.date_source {
+        DateSource::Frontmatter => frontmatter_date(&front).or(date),
+        _ => date,
+    };
+    for entry in &mut entries {
+        entry.date = match options.date_source {
+            DateSource::Completion => entry.completed.or(note_date),
+            _ => note_date,
+        };
+    }
+    if !properties.is_empty() {
+        for entry in &mut entries {
+            entry.properties = properties.clone();
+        }
+    }
+    if options.frontmatter_tags {
+        let note_tags = frontmatter_tags(&properties);
+        for entry in &mut entries {
+            let mut tags: Vec<&str> = entry.tags.split(',').filter(|t| !t.is_empty()).collect();
+            for tag in &note_tags {
+                if !tags.contains(&tag.as_str()) {
+                    tags.push(tag);
+                }
+            }
+            tags.sort();
+            entry.tags = tags.join(",");
+        }
+    }
+    // Notes inside archives have no history to blame and keep no author.
+    if options.authors && path.is_file() {
+        let authors = git::line_authors(path)?;
+        for entry in &mut entries {
+            entry.author = authors.get(entry.line - 1).cloned().unwrap_or_default();
+        }
+    }
+    filter_entries(entries, path, options)
+}
+
+/// Runs entries through the script hook and the tag, priority, checkbox,
+/// date, weekend, and text filters, in line order.
+fn filter_entries(
+    mut entries: Vec<TimeEntry>,
+    path: &Path,
+    options: &Options,
+) -> Result<Vec<TimeEntry>, Box<dyn Error>> {
+    #[cfg(feature = "scripting")]
+    if let Some(hook) = &options.hook {
+        let mut processed = vec![];
+        for entry in entries {
+            processed.extend(hook.apply(entry, path)?);
+        }
+        entries = processed;
+    }
+    if let Some((increment, rounding)) = &options.granularity {
+        for entry in &mut entries {
+            entry.duration = rounding.round(&entry.duration, increment);
+        }
+    }
+    if let Some(tag) = &options.default_tag {
+        for entry in entries.iter_mut().filter(|e| e.tags.is_empty()) {
+            entry.tags = tag.clone();
+        }
+    }
+    for entry in &entries {
+        for tag in options.identifiers.invalid(&entry.tags) {
+            warn!(
+                "{}:{}: {} doesn't match its identifier format",
+                path.display(),
+                entry.line,
+                tag
+            );
+        }
+        for violation in options.exclusives.violations(&entry.tags) {
+            warn!("{}:{}: {}", path.display(), entry.line, violation);
+        }
+    }
+    entries.retain(|e| {
+        matches_tags(&e.tags, options)
+            && matches_priority(e, options)
+            && matches_checkbox(e, options)
+            && matches_date(e, options)
+            && matches_weekend(e, options)
+            && matches_text(e, options)
+    });
+    entries.sort_by_key(|e| e.line);
+    // Only entries left in the report count as untagged.
+    let untagged = entries.iter().filter(|e| e.tags.is_empty());
+    if options.require_tags
+        && let Some(entry) = untagged.clone().next()
+    {
+        return Err(format!("{}:{}: entry has no tags", path.display(), entry.line).into());
+    }
+    UNTAGGED.fetch_add(untagged.count(), Ordering::Relaxed);
+    Ok(entries)
+}
+
+fn display_path(path: &Path, options: &Options) -> String {
+    match options.path_style {
+        Some(style) => style.display(path, &options.vaults),
+        None => path.to_string_lossy().into_owned(),
+    }
 }
@@ -0,0 +1,81 @@
+// src/manifest.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::json::quote;
+use jiff::Timestamp;
+use std::path::PathBuf;
+
+/// Where a report came from: what was scanned, what was kept, and by which
+/// build, so a generated artifact can be traced back to its inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub version: String,
+    pub generated: Timestamp,
+    pub directories: Vec<PathBuf>,
+    /// Notes read, including files given directly.
+    pub files: usize,
+    /// Entries left after filtering.
+    pub entries: usize,
+    pub total: TimeDuration,
+    /// The filtering options given, with their values as written.
+    pub filters: Vec<(String, Vec<String>)>,
+    /// The files the report was written to, `-` for stdout.
+    pub outputs: Vec<String>,
+}
+
+impl Manifest {
+    /// The manifest as a JSON object, with the total in `unit` as well as
+    /// in seconds.
+    pub fn to_json(&self, unit: DurationUnit) -> String {
+        let list = |values: &mut dyn Iterator<Item = String>| {
+            format!(
+                "[{}]",
+                values.map(|v| quote(&v)).collect::<Vec<_>>().join(",")
+            )
+        };
+        let filters: Vec<String> = self
+            .filters
+            .iter()
+            .map(|(name, values)| format!("{}:{}", quote(name), list(&mut values.iter().cloned())))
+            .collect();
+        format!(
+            "{{\"version\":{},\"generated\":{},\"directories\":{},\"files\":{},\"entries\":{},\"total\":{},\"total_seconds\":{},\"filters\":{{{}}},\"outputs\":{}}}",
+            quote(&self.version),
+            quote(&self.generated.to_string()),
+            list(&mut self.directories.iter().map(|d| d.display().to_string())),
+            self.files,
+            self.entries,
+            quote(&unit.format(&self.total)),
+            self.total.total_seconds(),
+            filters.join(","),
+            list(&mut self.outputs.iter().cloned())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+
+    #[test]
+    fn test_to_json() {
+        let manifest = Manifest {
+            version: "1.0.0".to_string(),
+            generated: "2025-01-06T09:00:00Z".parse().unwrap(),
+            directories: vec![PathBuf::from("Journals")],
+            files: 3,
+            entries: 5,
+            total: parse_duration("2h30m"),
+            filters: vec![(
+                "tag".to_string(),
+                vec!["#dev".to_string(), "#ops".to_string()],
+            )],
+            outputs: vec!["-".to_string()],
+        };
+        assert_eq!(
+            r##"{"version":"1.0.0","generated":"2025-01-06T09:00:00Z","directories":["Journals"],"files":3,"entries":5,"total":"2h30m","total_seconds":9000,"filters":{"tag":["#dev","#ops"]},"outputs":["-"]}"##,
+            manifest.to_json(DurationUnit::Compact)
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// src/markdown.rs
+
+use regex::{Captures, Regex};
+
+/// `text` without its Markdown syntax, for descriptions read outside
+/// Obsidian: checkboxes, emphasis, highlights, and strikethrough are
+/// dropped, links and wikilinks become their text (a wikilink's alias, or
+/// its page), and inline code its content. Code is kept as written.
+pub fn strip(text: &str) -> String {
+    // Code spans are set aside as private-use placeholders, so their
+    // content isn't taken for syntax, then put back.
+    let re_code = Regex::new(r"(`+)(.+?)(`+)").unwrap();
+    let re_placeholder = Regex::new("\u{E000}(\\d+)\u{E001}").unwrap();
+    let mut code = vec![];
+    let text = re_code.replace_all(text, |cap: &Captures| match cap[1] == cap[3] {
+        true => {
+            code.push(cap[2].trim().to_string());
+            format!("\u{E000}{}\u{E001}", code.len() - 1)
+        }
+        false => cap[0].to_string(),
+    });
+    let text = strip_syntax(&text);
+    let text = re_placeholder.replace_all(&text, |cap: &Captures| {
+        code[cap[1].parse::<usize>().unwrap()].clone()
+    });
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Strips the syntax besides inline code.
+fn strip_syntax(text: &str) -> String {
+    let re_checkbox = Regex::new(r"(^|\s)\[.\](\s|$)").unwrap();
+    let re_wikilink = Regex::new(r"!?\[\[([^\]|#^]*)(?:[#^][^\]|]*)?(?:\|([^\]]*))?\]\]").unwrap();
+    let re_link = Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap();
+    let re_strong = Regex::new(r"(\*\*|__|~~|==)(\S(?:.*?\S)?)(\*\*|__|~~|==)").unwrap();
+    let re_em = Regex::new(r"(^|[^\w*])([*_])(\S(?:.*?\S)?)([*_])($|[^\w*])").unwrap();
+
+    let text = re_checkbox.replace_all(text, "$1$2");
+    let text = re_wikilink.replace_all(&text, |cap: &Captures| match cap.get(2) {
+        Some(alias) if !alias.as_str().trim().is_empty() => alias.as_str().to_string(),
+        _ => cap[1].to_string(),
+    });
+    let text = re_link.replace_all(&text, "$1");
+    let text = re_strong.replace_all(&text, |cap: &Captures| match cap[1] == cap[3] {
+        true => cap[2].to_string(),
+        false => cap[0].to_string(),
+    });
+    re_em
+        .replace_all(&text, |cap: &Captures| match cap[2] == cap[4] {
+            true => format!("{}{}{}", &cap[1], &cap[3], &cap[5]),
+            false => cap[0].to_string(),
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip() {
+        assert_eq!(
+            "Review Design doc with Acme and spec #dev",
+            strip(
+                "Review **[[Design doc]]** with [[Clients/Acme|Acme]] and [spec](https://example.com) #dev"
+            )
+        );
+        assert_eq!(
+            "Fix the parse_duration bug in *args",
+            strip("Fix the _`parse_duration`_ ==bug== in `*args`")
+        );
+        assert_eq!(
+            "Plan sprint with team",
+            strip("[ ] Plan *sprint* with __team__")
+        );
+        assert_eq!("Notes Heading", strip("[[Notes#Heading|Notes Heading]]"));
+        assert_eq!("Notes", strip("![[Notes#Heading]]"));
+        assert_eq!(
+            "snake_case and 2 * 3 * 4",
+            strip("snake_case and 2 * 3 * 4")
+        );
+    }
+}
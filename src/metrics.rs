@@ -0,0 +1,175 @@
+// src/metrics.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::parser::TimeEntry;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// How long a scrape may take to send its request or read the answer.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Renders entries in the Prometheus text format: tracked hours per tag,
+/// overall and for `today`, and the number of entries. An entry counts
+/// toward each of its tags.
+pub fn render(entries: &[TimeEntry], today: Date) -> String {
+    let mut total: BTreeMap<&str, TimeDuration> = BTreeMap::new();
+    let mut today_total: BTreeMap<&str, TimeDuration> = BTreeMap::new();
+    for entry in entries {
+        for tag in entry.tags.split(',').filter(|t| !t.is_empty()) {
+            *total.entry(tag).or_default() += &entry.duration;
+            if entry.date == Some(today) {
+                *today_total.entry(tag).or_default() += &entry.duration;
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (name, help, values) in [
+        ("timetracker_hours_total", "Hours tracked per tag.", &total),
+        (
+            "timetracker_hours_today",
+            "Hours tracked today per tag.",
+            &today_total,
+        ),
+    ] {
+        writeln!(out, "# HELP {} {}", name, help).unwrap();
+        writeln!(out, "# TYPE {} gauge", name).unwrap();
+        for (tag, duration) in values {
+            writeln!(
+                out,
+                "{}{{tag=\"{}\"}} {}",
+                name,
+                escape_label(tag),
+                DurationUnit::Hours.format(duration)
+            )
+            .unwrap();
+        }
+    }
+    writeln!(out, "# HELP timetracker_entries Number of time entries.").unwrap();
+    writeln!(out, "# TYPE timetracker_entries gauge").unwrap();
+    writeln!(out, "timetracker_entries {}", entries.len()).unwrap();
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Answers HTTP requests on `listener` for good, serving `GET /metrics`
+/// with the body from `metrics`, called anew for every scrape. A client
+/// silent for `timeout` is dropped, so it can't hold up the next.
+pub fn serve(
+    listener: TcpListener,
+    timeout: Duration,
+    metrics: impl Fn() -> Result<String, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    for stream in listener.incoming() {
+        let result = stream
+            .map_err(Box::from)
+            .and_then(|stream| handle(stream, timeout, &metrics));
+        if let Err(e) = result {
+            log::warn!("metrics request failed: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle(
+    mut stream: TcpStream,
+    timeout: Duration,
+    metrics: &impl Fn() -> Result<String, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Drain the headers so the client doesn't see a reset.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    log::debug!("metrics request: {}", request.trim_end());
+
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = if !request.starts_with("GET ") {
+        ("405 Method Not Allowed", String::new())
+    } else if path == "/metrics" {
+        match metrics() {
+            Ok(body) => ("200 OK", body),
+            Err(e) => ("500 Internal Server Error", format!("{}\n", e)),
+        }
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+    use std::io::Read;
+
+    #[test]
+    fn test_render() {
+        let entries = vec![
+            TimeEntry::test("#dev,#pbi-1", "1h30m").dated(date(2025, 1, 2)),
+            TimeEntry::test("#dev", "30m").dated(date(2025, 1, 3)),
+        ];
+        assert_eq!(
+            "# HELP timetracker_hours_total Hours tracked per tag.\n\
+             # TYPE timetracker_hours_total gauge\n\
+             timetracker_hours_total{tag=\"#dev\"} 2\n\
+             timetracker_hours_total{tag=\"#pbi-1\"} 1.5\n\
+             # HELP timetracker_hours_today Hours tracked today per tag.\n\
+             # TYPE timetracker_hours_today gauge\n\
+             timetracker_hours_today{tag=\"#dev\"} 0.5\n\
+             # HELP timetracker_entries Number of time entries.\n\
+             # TYPE timetracker_entries gauge\n\
+             timetracker_entries 2\n",
+            render(&entries, date(2025, 1, 3))
+        );
+    }
+
+    #[test]
+    fn test_serve_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let _ = serve(listener, Duration::from_millis(100), || {
+                Ok("timetracker_entries 0\n".to_string())
+            });
+        });
+        // A client that never sends its request doesn't stall the others.
+        let _silent = TcpStream::connect(address).unwrap();
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\ntimetracker_entries 0\n"));
+        assert!(get("/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
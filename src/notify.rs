@@ -0,0 +1,112 @@
+// src/notify.rs
+
+use crate::aggregate;
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::json;
+use crate::parser::TimeEntry;
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+
+/// Payload sent when no `--payload-template` is given.
+pub const DEFAULT_TEMPLATE: &str =
+    r#"{"summary": {{summary}}, "total": {{total}}, "entries": {{entries}}, "tags": {{tags}}}"#;
+
+/// The JSON values a payload template can refer to as `{{name}}`:
+/// `summary` (a sentence), `total`, `entries`, `tags` (an array of
+/// `{"tags", "duration", "entries"}` per tag set), `since`, and `until`.
+/// Durations are strings in the compact unit and numbers otherwise.
+pub fn values(
+    entries: &[TimeEntry],
+    unit: DurationUnit,
+    since: Option<String>,
+    until: Option<String>,
+) -> BTreeMap<&'static str, String> {
+    let duration = |d: &TimeDuration| match unit {
+        DurationUnit::Compact => json::quote(&unit.format(d)),
+        _ => unit.format(d),
+    };
+    let mut total = TimeDuration::default();
+    for entry in entries {
+        total += &entry.duration;
+    }
+    let tags: Vec<String> = aggregate::accumulate(entries.iter().map(|e| (e, "")))
+        .iter()
+        .map(|(tags, aggregate)| {
+            format!(
+                "{{\"tags\":{},\"duration\":{},\"entries\":{}}}",
+                json::quote(tags),
+                duration(&aggregate.duration),
+                aggregate.entries
+            )
+        })
+        .collect();
+    let date = |date: Option<String>| date.map_or("null".to_string(), |d| json::quote(&d));
+    BTreeMap::from([
+        (
+            "summary",
+            json::quote(&format!(
+                "Tracked {} in {} entries",
+                DurationUnit::Compact.format(&total),
+                entries.len()
+            )),
+        ),
+        ("total", duration(&total)),
+        ("entries", entries.len().to_string()),
+        ("tags", format!("[{}]", tags.join(","))),
+        ("since", date(since)),
+        ("until", date(until)),
+    ])
+}
+
+/// Replaces each `{{name}}` in `template` with its value, failing on names
+/// without one.
+pub fn render(template: &str, values: &BTreeMap<&str, String>) -> Result<String, String> {
+    let re_placeholder = Regex::new(r"\{\{\s*([\w-]+)\s*\}\}").unwrap();
+    let mut unknown = None;
+    let rendered = re_placeholder.replace_all(template, |cap: &Captures| {
+        values.get(&cap[1]).cloned().unwrap_or_else(|| {
+            unknown.get_or_insert_with(|| cap[1].to_string());
+            String::new()
+        })
+    });
+    match unknown {
+        Some(name) => Err(format!(
+            "unknown placeholder '{{{{{}}}}}'; expected one of {}",
+            name,
+            values.keys().copied().collect::<Vec<_>>().join(", ")
+        )),
+        None => Ok(rendered.into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_values() {
+        let entries = [
+            TimeEntry::test("#dev", "1h"),
+            TimeEntry::test("#ops", "30m"),
+        ];
+        let values = values(
+            &entries,
+            DurationUnit::Hours,
+            Some("2025-01-06".into()),
+            None,
+        );
+        assert_eq!(
+            r#"{"text": "Tracked 1h30m in 2 entries", "hours": 1.5, "from": "2025-01-06", "to": null}"#,
+            render(
+                r#"{"text": {{summary}}, "hours": {{ total }}, "from": {{since}}, "to": {{until}}}"#,
+                &values
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            r##"[{"tags":"#dev","duration":1,"entries":1},{"tags":"#ops","duration":0.5,"entries":1}]"##,
+            values["tags"]
+        );
+        assert!(render("{{nope}}", &values).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+// src/outline.rs
+
+/// Where an entry sits in its note: the headings above it and the list
+/// items it is nested under, each outermost first.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Context {
+    /// Enclosing headings as (level, text without the `#`s). Levels can
+    /// skip, as in an H3 directly under an H1.
+    pub headings: Vec<(usize, String)>,
+    /// Text of the enclosing list items, without markers, checkboxes, or
+    /// time fields.
+    pub parents: Vec<String>,
+}
+
+impl Context {
+    /// The text of the enclosing heading at `level` (1 for H1), or empty.
+    pub fn heading(&self, level: usize) -> &str {
+        self.headings
+            .iter()
+            .find(|(l, _)| *l == level)
+            .map_or("", |(_, text)| text.as_str())
+    }
+
+    /// The headings and then the parent items, joined with ` > `.
+    pub fn path(&self) -> String {
+        self.headings
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .chain(self.parents.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+}
+
+/// A list item still open while scanning, so nested items and their
+/// entries can inherit from it.
+#[derive(Debug, Clone)]
+struct Item {
+    indent: usize,
+    text: String,
+    tags: Vec<String>,
+}
+
+/// Tracks a note's heading stack and list nesting line by line.
+#[derive(Debug, Default)]
+pub(crate) struct Outline {
+    headings: Vec<(usize, String)>,
+    /// Tags written on the innermost heading, inherited until the next one.
+    heading_tags: Vec<String>,
+    items: Vec<Item>,
+}
+
+impl Outline {
+    /// Enters a heading, closing the headings at its level or deeper and
+    /// every open list item.
+    pub(crate) fn heading(&mut self, level: usize, text: &str, tags: Vec<String>) {
+        while self.headings.last().is_some_and(|(l, _)| *l >= level) {
+            self.headings.pop();
+        }
+        self.headings.push((level, text.to_string()));
+        self.heading_tags = tags;
+        self.items.clear();
+    }
+
+    /// Enters a list item at `indent`, closing the items at that indent or
+    /// deeper. Returns the item's context and the tags it inherits.
+    pub(crate) fn item(
+        &mut self,
+        indent: usize,
+        text: &str,
+        tags: Vec<String>,
+    ) -> (Context, Vec<String>) {
+        while self.items.last().is_some_and(|item| item.indent >= indent) {
+            self.items.pop();
+        }
+        let scope = (
+            self.context(self.items.len()),
+            self.inherited(self.items.len()),
+        );
+        self.items.push(Item {
+            indent,
+            text: text.to_string(),
+            tags,
+        });
+        scope
+    }
+
+    /// The context and inherited tags of a line continuing the innermost
+    /// item, which the line's text is part of rather than a parent of.
+    pub(crate) fn continuation(&self) -> (Context, Vec<String>) {
+        let parents = self.items.len().saturating_sub(1);
+        (self.context(parents), self.inherited(self.items.len()))
+    }
+
+    /// The context and inherited tags of a line that isn't a list item.
+    /// Open items stay open, so items after it still nest under them.
+    pub(crate) fn outside(&self) -> (Context, Vec<String>) {
+        (self.context(0), self.inherited(0))
+    }
+
+    fn context(&self, parents: usize) -> Context {
+        Context {
+            headings: self.headings.clone(),
+            parents: self.items[..parents]
+                .iter()
+                .map(|item| item.text.clone())
+                .collect(),
+        }
+    }
+
+    fn inherited(&self, items: usize) -> Vec<String> {
+        self.heading_tags
+            .iter()
+            .chain(self.items[..items].iter().flat_map(|item| &item.tags))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_context() {
+        let mut outline = Outline::default();
+        outline.heading(1, "Project", vec![]);
+        outline.heading(2, "Monday", vec!["#week".to_string()]);
+        outline.item(0, "Backend", vec!["#dev".to_string()]);
+        let (context, tags) = outline.item(2, "Fix login", vec![]);
+        assert_eq!("Project", context.heading(1));
+        assert_eq!("Monday", context.heading(2));
+        assert_eq!("", context.heading(3));
+        assert_eq!(vec!["Backend".to_string()], context.parents);
+        assert_eq!("Project > Monday > Backend", context.path());
+        assert_eq!(vec!["#week".to_string(), "#dev".to_string()], tags);
+
+        let (context, _) = outline.continuation();
+        assert_eq!(vec!["Backend".to_string()], context.parents);
+
+        outline.heading(2, "Tuesday", vec![]);
+        let (context, tags) = outline.item(2, "Review", vec![]);
+        assert_eq!(
+            vec![(1, "Project".to_string()), (2, "Tuesday".to_string())],
+            context.headings
+        );
+        assert!(context.parents.is_empty());
+        assert!(tags.is_empty());
+    }
+}
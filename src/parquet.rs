@@ -0,0 +1,210 @@
+// src/parquet.rs
+
+use ::parquet::basic::Compression;
+use ::parquet::data_type::{ByteArray, ByteArrayType, DataType, Int32Type, Int64Type};
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+use ::parquet::schema::parser::parse_message_type;
+use jiff::civil::{Date, DateTime};
+use jiff::tz::TimeZone;
+use std::error::Error;
+use std::io::Write;
+use std::sync::Arc;
+
+/// The columns of `--format parquet`, typed for analytics tools. Dates are
+/// days since the epoch and `started` is a local time without a zone.
+const SCHEMA: &str = "
+message timetracker_entry {
+    REQUIRED BYTE_ARRAY id (UTF8);
+    OPTIONAL INT32 date (DATE);
+    OPTIONAL INT64 started (TIMESTAMP(MICROS,false));
+    REQUIRED group tags (LIST) {
+        REPEATED group list {
+            REQUIRED BYTE_ARRAY element (UTF8);
+        }
+    }
+    REQUIRED INT64 seconds;
+    OPTIONAL BYTE_ARRAY priority (UTF8);
+    OPTIONAL BYTE_ARRAY status (UTF8);
+    REQUIRED BYTE_ARRAY text (UTF8);
+    REQUIRED BYTE_ARRAY heading (UTF8);
+    REQUIRED BYTE_ARRAY file (UTF8);
+    REQUIRED INT64 line;
+    REQUIRED BYTE_ARRAY vault (UTF8);
+    REQUIRED BYTE_ARRAY source (UTF8);
+    REQUIRED BYTE_ARRAY source_syntax (UTF8);
+}
+";
+
+/// One entry as a Parquet row, with the fields of `--format full` but its
+/// tags as a list rather than a row each.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    pub id: String,
+    pub date: Option<Date>,
+    /// The `timeStarted` time on the entry's date.
+    pub started: Option<DateTime>,
+    pub tags: Vec<String>,
+    pub seconds: i64,
+    pub priority: Option<String>,
+    pub status: Option<String>,
+    pub text: String,
+    pub heading: String,
+    pub file: String,
+    pub line: i64,
+    pub vault: String,
+    pub source: String,
+    pub source_syntax: String,
+}
+
+/// Writes `records` as a Parquet file with a single row group.
+pub fn write<W: Write>(records: &[Record], mut writer: W) -> Result<(), Box<dyn Error>> {
+    let schema = Arc::new(parse_message_type(SCHEMA)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    // The file writer needs `Send`, which report destinations aren't.
+    let mut buffer = vec![];
+    let mut file = SerializedFileWriter::new(&mut buffer, schema, properties)?;
+    let mut group = file.next_row_group()?;
+
+    let text = |value: &dyn Fn(&Record) -> &str| -> Vec<ByteArray> {
+        records.iter().map(|r| ByteArray::from(value(r))).collect()
+    };
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.id), None, None)?;
+    let dates: Vec<Option<i32>> = records
+        .iter()
+        .map(|r| r.date.map(days_since_epoch).transpose())
+        .collect::<Result<_, _>>()?;
+    write_optional::<Int32Type>(&mut group, dates)?;
+    let started: Vec<Option<i64>> = records
+        .iter()
+        .map(|r| {
+            r.started
+                .map(|started| {
+                    Ok::<_, jiff::Error>(TimeZone::UTC.to_timestamp(started)?.as_microsecond())
+                })
+                .transpose()
+        })
+        .collect::<Result<_, _>>()?;
+    write_optional::<Int64Type>(&mut group, started)?;
+
+    // A tag list is written flat: each tag continues its row's list, and an
+    // empty list is a single undefined value.
+    let (mut tags, mut defined, mut repeated) = (vec![], vec![], vec![]);
+    for record in records {
+        if record.tags.is_empty() {
+            defined.push(0);
+            repeated.push(0);
+        }
+        for (index, tag) in record.tags.iter().enumerate() {
+            tags.push(ByteArray::from(tag.as_str()));
+            defined.push(1);
+            repeated.push(i16::from(index > 0));
+        }
+    }
+    write_column::<ByteArrayType>(&mut group, &tags, Some(&defined), Some(&repeated))?;
+
+    let seconds: Vec<i64> = records.iter().map(|r| r.seconds).collect();
+    write_column::<Int64Type>(&mut group, &seconds, None, None)?;
+    let optional = |value: &dyn Fn(&Record) -> &Option<String>| -> Vec<Option<ByteArray>> {
+        records
+            .iter()
+            .map(|r| value(r).as_deref().map(ByteArray::from))
+            .collect()
+    };
+    write_optional::<ByteArrayType>(&mut group, optional(&|r| &r.priority))?;
+    write_optional::<ByteArrayType>(&mut group, optional(&|r| &r.status))?;
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.text), None, None)?;
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.heading), None, None)?;
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.file), None, None)?;
+    let lines: Vec<i64> = records.iter().map(|r| r.line).collect();
+    write_column::<Int64Type>(&mut group, &lines, None, None)?;
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.vault), None, None)?;
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.source), None, None)?;
+    write_column::<ByteArrayType>(&mut group, &text(&|r| &r.source_syntax), None, None)?;
+
+    group.close()?;
+    file.close()?;
+    writer.write_all(&buffer)?;
+    Ok(())
+}
+
+fn days_since_epoch(date: Date) -> Result<i32, jiff::Error> {
+    Ok(date.since(jiff::civil::date(1970, 1, 1))?.get_days())
+}
+
+/// Writes the next column of the row group.
+fn write_column<T: DataType>(
+    group: &mut SerializedRowGroupWriter<'_, &mut Vec<u8>>,
+    values: &[T::T],
+    defined: Option<&[i16]>,
+    repeated: Option<&[i16]>,
+) -> Result<(), Box<dyn Error>> {
+    let mut column = group
+        .next_column()?
+        .ok_or("more columns written than in the Parquet schema")?;
+    column.typed::<T>().write_batch(values, defined, repeated)?;
+    column.close()?;
+    Ok(())
+}
+
+/// Writes the next column, a nullable one, of the row group.
+fn write_optional<T: DataType>(
+    group: &mut SerializedRowGroupWriter<'_, &mut Vec<u8>>,
+    values: Vec<Option<T::T>>,
+) -> Result<(), Box<dyn Error>> {
+    let defined: Vec<i16> = values.iter().map(|v| i16::from(v.is_some())).collect();
+    let values: Vec<T::T> = values.into_iter().flatten().collect();
+    write_column::<T>(group, &values, Some(&defined), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+    use jiff::civil::date;
+
+    #[test]
+    fn test_write() {
+        let records = [
+            Record {
+                id: "a1".to_string(),
+                date: Some(date(2025, 1, 3)),
+                started: Some(date(2025, 1, 3).at(9, 30, 0, 0)),
+                tags: vec!["#dev".to_string(), "#pbi-7".to_string()],
+                seconds: 5400,
+                priority: Some("high".to_string()),
+                text: "Task A".to_string(),
+                line: 1,
+                ..Default::default()
+            },
+            Record {
+                id: "b2".to_string(),
+                seconds: 60,
+                line: 2,
+                ..Default::default()
+            },
+        ];
+        let mut file = tempfile::tempfile().unwrap();
+        write(&records, &mut file).unwrap();
+        let reader = SerializedFileReader::new(file).unwrap();
+        assert_eq!(2, reader.metadata().file_metadata().num_rows());
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert!(rows[0].contains("date: 2025-01-03"), "{}", rows[0]);
+        assert!(
+            rows[0].contains(r##"tags: ["#dev", "#pbi-7"]"##),
+            "{}",
+            rows[0]
+        );
+        assert!(rows[0].contains("seconds: 5400"), "{}", rows[0]);
+        assert!(rows[1].contains("date: null"), "{}", rows[1]);
+        assert!(rows[1].contains("tags: []"), "{}", rows[1]);
+    }
+}
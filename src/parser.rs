@@ -0,0 +1,1312 @@
+// src/parser.rs
+
+use crate::duration::{TimeDuration, Units, format_duration};
+use crate::outline::{Context, Outline};
+use crate::properties::Value;
+use jiff::civil::{Date, Time};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Finds tags following Obsidian's rules: any Unicode letters, numbers,
+/// `_`, `-`, and `/` for nested tags, with at least one non-numeric
+/// character.
+fn find_tags(text: &str) -> Vec<String> {
+    static RE_TAGS: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"#[\p{L}\p{M}\p{N}_/-]+").unwrap());
+    let mut tags: Vec<String> = vec![];
+    for m in RE_TAGS.find_iter(text) {
+        let tag = m.as_str().to_string();
+        if tag[1..].chars().all(|c| c.is_numeric()) {
+            continue;
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+pub fn extract_tags(task_text: &str, current_pbi: &Option<String>, sort_tags: bool) -> String {
+    let mut tags = find_tags(task_text);
+
+    if let Some(pbi) = current_pbi
+        && !tags.iter().any(|t| t == pbi)
+    {
+        tags.insert(0, pbi.clone());
+    }
+
+    if sort_tags {
+        tags.sort();
+    }
+
+    if tags.is_empty() {
+        "".to_string()
+    } else {
+        tags.join(",")
+    }
+}
+
+/// Finds allocation tags like `#clientA(75%)`, which split an entry's
+/// duration between tags, as (tag, percent).
+fn find_allocations(text: &str) -> Vec<(String, u32)> {
+    static RE_ALLOCATION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(#[\p{L}\p{M}\p{N}_/-]+)\((\d{1,3})%\)").unwrap());
+    RE_ALLOCATION
+        .captures_iter(text)
+        .filter_map(|cap| Some((cap[1].to_string(), cap[2].parse().ok()?)))
+        .collect()
+}
+
+/// Returns whether a comma-joined tag list (as produced by `extract_tags`)
+/// contains `tag`.
+pub fn has_tag(tags: &str, tag: &str) -> bool {
+    tags.split(',').any(|t| t == tag)
+}
+
+/// A note's lines, split at `\n` like git and Obsidian do, without the
+/// `\r` of Windows line endings (even doubled, `\r\r\n`, as some sync
+/// tools leave them) or a leading byte order mark.
+pub fn lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .strip_prefix('\u{feff}')
+        .unwrap_or(content)
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+}
+
+/// A note's raw lines, split like `lines` splits text, so each can be
+/// read as text only if it is needed.
+fn byte_lines(content: &[u8]) -> impl Iterator<Item = &[u8]> {
+    content
+        .strip_prefix(b"\xef\xbb\xbf")
+        .unwrap_or(content)
+        .split(|&b| b == b'\n')
+        .map(|line| {
+            let end = line.iter().rposition(|&b| b != b'\r').map_or(0, |i| i + 1);
+            &line[..end]
+        })
+}
+
+/// Width of a line's leading whitespace, counting tabs as four columns.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Blanks out inline code spans and backslash-escaped `\[...]`, so fields
+/// written as documentation aren't parsed. Every masked byte becomes a
+/// space, keeping byte offsets the same as in `line`.
+pub(crate) fn mask_code(line: &str) -> String {
+    let bytes = line.as_bytes();
+    let mut masked = bytes.to_vec();
+    let run_at = |at: usize| bytes[at..].iter().take_while(|&&b| b == b'`').count();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if i + 1 < bytes.len() && matches!(bytes[i + 1], b'[' | b'`') => {
+                let mut end = i + 2;
+                if bytes[i + 1] == b'[' {
+                    end = line[end..].find(']').map_or(end, |offset| end + offset + 1);
+                    masked[i..end].fill(b' ');
+                }
+                i = end;
+            }
+            b'`' => {
+                let run = run_at(i);
+                // A span closes at the next run of exactly as many backticks.
+                let mut j = i + run;
+                let mut close = None;
+                while j < bytes.len() {
+                    if bytes[j] != b'`' {
+                        j += 1;
+                    } else if run_at(j) == run {
+                        close = Some(j + run);
+                        break;
+                    } else {
+                        j += run_at(j);
+                    }
+                }
+                match close {
+                    Some(end) => {
+                        masked[i..end].fill(b' ');
+                        i = end;
+                    }
+                    None => i += run,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    // Masked ranges start and end on ASCII bytes, so whole characters become
+    // spaces and the result is still UTF-8.
+    String::from_utf8(masked).unwrap()
+}
+
+/// Task priority, from the Tasks plugin emojis or a `[priority: ...]` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Lowest,
+    Low,
+    Medium,
+    High,
+    Highest,
+}
+
+impl Priority {
+    fn from_emoji(line: &str) -> Option<Priority> {
+        [
+            ("🔺", Priority::Highest),
+            ("⏫", Priority::High),
+            ("🔼", Priority::Medium),
+            ("🔽", Priority::Low),
+            ("⏬", Priority::Lowest),
+        ]
+        .into_iter()
+        .find(|(emoji, _)| line.contains(emoji))
+        .map(|(_, priority)| priority)
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Priority::Lowest => "lowest",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Highest => "highest",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "lowest" => Ok(Priority::Lowest),
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            "highest" => Ok(Priority::Highest),
+            _ => Err(format!("unknown priority '{}'", s)),
+        }
+    }
+}
+
+/// How an entry's time was written, to follow migrations between formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Syntax {
+    /// A bracketed field, `[timeTracked: 1h]`.
+    #[default]
+    Field,
+    /// A Dataview inline field, `[timeTracked:: 1h]`.
+    Dataview,
+    /// A `timeStarted`/`timeEnded` clock range.
+    Clock,
+    /// A row of an imported CSV export.
+    Import,
+    /// A trailing parenthetical duration, `(2h15m)`, read with
+    /// `--legacy-durations`.
+    Legacy,
+}
+
+impl fmt::Display for Syntax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Syntax::Field => "field",
+            Syntax::Dataview => "dataview",
+            Syntax::Clock => "clock",
+            Syntax::Import => "import",
+            Syntax::Legacy => "legacy",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A task line carrying a `timeTracked` field, or a `timeStarted` and
+/// `timeEnded` pair. `line` is 1-based.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub tags: String,
+    pub duration: TimeDuration,
+    /// The task's text without its list marker, checkbox, or time fields.
+    pub text: String,
+    pub line: usize,
+    pub priority: Option<Priority>,
+    /// The Tasks plugin completion date (`✅ 2025-01-03`).
+    pub completed: Option<Date>,
+    /// The date the entry is reported under, resolved by the caller from a
+    /// `DateSource`.
+    pub date: Option<Date>,
+    /// The task's checkbox character (`' '`, `'x'`, `'-'`, ...), or `None`
+    /// for a plain list item or line.
+    pub status: Option<char>,
+    /// The text of the heading the entry is under, without its `#`s.
+    pub heading: String,
+    /// The `timeStarted` time, if the entry has one.
+    pub started: Option<Time>,
+    pub syntax: Syntax,
+    pub context: Context,
+    /// The git author of the entry's line, filled in by the caller when
+    /// attributing entries with `git blame`.
+    pub author: String,
+    /// Shares of the duration per tag, in percent adding up to 100, from
+    /// allocation tags like `#clientA(75%)`.
+    pub allocation: Vec<(String, u32)>,
+    /// The note's frontmatter properties, filled in by the caller.
+    pub properties: BTreeMap<String, Value>,
+    /// Inline fields on the entry's line besides the time and priority
+    /// ones, like `[client:: Acme]`, by name.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl TimeEntry {
+    /// The inline field `name` on the entry's line, else the note's
+    /// property of that name.
+    pub fn field(&self, name: &str) -> Option<Value> {
+        match self.fields.get(name) {
+            Some(value) => Some(Value::Text(value.clone())),
+            None => self.properties.get(name).cloned(),
+        }
+    }
+
+    /// A stable key for reconciling reports across runs: a 64-bit FNV-1a
+    /// hash of `path`, `line`, and the task text, in hex. It changes when
+    /// the entry is edited or moves to another line. Pass the path relative
+    /// to the searched directory for IDs that don't depend on how it's
+    /// spelled.
+    pub fn id(&self, path: &Path) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let key = format!("{}\0{}\0{}", path.to_string_lossy(), self.line, self.text);
+        for byte in key.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Splits the entry per its allocation, one share per allocated tag
+    /// carrying that tag and the entry's unallocated ones. Shares are
+    /// rounded to multiples of `step` seconds, and the last takes the
+    /// remainder so the total is unchanged. An entry without an allocation
+    /// is returned as is.
+    pub fn allocate(&self, step: u64) -> Vec<TimeEntry> {
+        if self.allocation.is_empty() {
+            return vec![self.clone()];
+        }
+        let allocated = |tag: &str| self.allocation.iter().any(|(t, _)| t == tag);
+        let total = self.duration.total_seconds();
+        let mut remaining = total;
+        let mut shares = vec![];
+        for (index, (tag, percent)) in self.allocation.iter().enumerate() {
+            let seconds = if index + 1 == self.allocation.len() {
+                remaining
+            } else {
+                let step = step.max(1);
+                (total * *percent as u64 + 50 * step) / (100 * step) * step
+            };
+            remaining -= seconds.min(remaining);
+            shares.push(TimeEntry {
+                tags: self
+                    .tags
+                    .split(',')
+                    .filter(|t| !allocated(t) || t == tag)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                duration: TimeDuration::from_seconds(seconds),
+                allocation: vec![],
+                ..self.clone()
+            });
+        }
+        shares
+    }
+
+    /// The checkbox status as a word: `todo`, `done`, `cancelled`, or
+    /// `in-progress`, other Tasks statuses as their character, and empty
+    /// when the entry isn't a task.
+    pub fn status_name(&self) -> String {
+        match self.status {
+            None => String::new(),
+            Some(' ') => "todo".to_string(),
+            Some('x' | 'X') => "done".to_string(),
+            Some('-') => "cancelled".to_string(),
+            Some('/') => "in-progress".to_string(),
+            Some(c) => c.to_string(),
+        }
+    }
+}
+
+/// Builds entries for tests.
+#[cfg(test)]
+impl TimeEntry {
+    /// An entry tagged `tags`, comma-joined, lasting `duration`, like `1h30m`.
+    pub fn test(tags: &str, duration: &str) -> Self {
+        TimeEntry {
+            tags: tags.to_string(),
+            duration: crate::duration::parse_duration(duration),
+            ..Default::default()
+        }
+    }
+
+    pub fn dated(self, date: Date) -> Self {
+        TimeEntry {
+            date: Some(date),
+            ..self
+        }
+    }
+
+    pub fn with_text(self, text: &str) -> Self {
+        TimeEntry {
+            text: text.to_string(),
+            ..self
+        }
+    }
+}
+
+/// A `timeStarted` marker with neither a `timeEnded` nor a `timeTracked`
+/// field, i.e. a session still in progress. `entry` has no duration yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenSession {
+    pub entry: TimeEntry,
+    pub started: Time,
+}
+
+/// Time fields found on a single line, with the entry they describe.
+struct FieldLine {
+    entry: TimeEntry,
+    tracked: Option<TimeDuration>,
+    estimate: Option<TimeDuration>,
+    started: Option<Time>,
+    ended: Option<Time>,
+}
+
+impl FieldLine {
+    fn tracked_or_clocked(&self) -> Option<TimeDuration> {
+        if let Some(tracked) = &self.tracked {
+            return Some(tracked.clone());
+        }
+        let (started, ended) = (self.started?, self.ended?);
+        let seconds = ended.duration_since(started).as_secs();
+        // A session ending "before" it started ran past midnight.
+        let seconds = if seconds < 0 {
+            seconds + 86400
+        } else {
+            seconds
+        };
+        Some(TimeDuration::from_seconds(seconds as u64))
+    }
+}
+
+/// What one pass over a note finds: its entries, its open sessions, and
+/// the 1-based numbers of every line that yielded an entry, estimate, or
+/// open session.
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub entries: Vec<TimeEntry>,
+    pub sessions: Vec<OpenSession>,
+    pub lines: Vec<usize>,
+}
+
+/// Scans a note's raw `content` once, reading as text only the lines that
+/// may be headings, list items, or hold fields. Invalid UTF-8 in those is
+/// replaced.
+pub fn scan_note(content: &[u8], sort_tags: bool, units: &Units) -> Scan {
+    let mut scan = Scan::default();
+    for line in scan_field_lines(content, sort_tags, units) {
+        scan.lines.push(line.entry.line);
+        if let Some(duration) = line.tracked_or_clocked() {
+            scan.entries.push(TimeEntry {
+                duration,
+                started: line.started,
+                ..line.entry
+            });
+        } else if line.tracked.is_none()
+            && line.ended.is_none()
+            && let Some(started) = line.started
+        {
+            scan.sessions.push(OpenSession {
+                entry: line.entry,
+                started,
+            });
+        }
+    }
+    scan
+}
+
+pub fn parse_time_entries(content: &str, sort_tags: bool, units: &Units) -> Vec<TimeEntry> {
+    scan_note(content.as_bytes(), sort_tags, units).entries
+}
+
+pub fn parse_open_sessions(content: &str, sort_tags: bool, units: &Units) -> Vec<OpenSession> {
+    scan_note(content.as_bytes(), sort_tags, units).sessions
+}
+
+/// Returns every `timeEstimate` field as (tags, estimate), whether or not the
+/// line also tracks time.
+pub fn parse_estimates(
+    content: &str,
+    sort_tags: bool,
+    units: &Units,
+) -> Vec<(String, TimeDuration)> {
+    scan_field_lines(content.as_bytes(), sort_tags, units)
+        .into_iter()
+        .filter_map(|line| line.estimate.map(|estimate| (line.entry.tags, estimate)))
+        .collect()
+}
+
+/// Lines of a note that mention a time field, split into those that parsed
+/// and those that didn't. Line numbers are 1-based.
+#[derive(Debug, Default, PartialEq)]
+pub struct Coverage {
+    pub parsed: Vec<usize>,
+    pub unparsed: Vec<usize>,
+}
+
+static RE_MENTION: LazyLock<regex::bytes::Regex> = LazyLock::new(|| {
+    regex::bytes::Regex::new(r"(?i)time\s*(?:tracked|estimate|started|ended)").unwrap()
+});
+
+/// Whether a note's raw content mentions a time field at all, checked on
+/// bytes so notes without one are skipped before being read as text.
+pub fn mentions_time_fields(content: &[u8]) -> bool {
+    RE_MENTION.is_match(content)
+}
+
+/// Finds every line mentioning `timeTracked`, `timeEstimate`, `timeStarted`,
+/// or `timeEnded` in any spelling, and whether it yielded an entry,
+/// estimate, or open session.
+pub fn parse_coverage(content: &str) -> Coverage {
+    let scan = scan_note(content.as_bytes(), false, &Units::default());
+    coverage(content.as_bytes(), &scan.lines)
+}
+
+/// Splits the lines of a note's raw `content` mentioning a time field
+/// outside code by whether they are among the `parsed` lines of its scan.
+pub fn coverage(content: &[u8], parsed: &[usize]) -> Coverage {
+    let mut coverage = Coverage::default();
+    for (index, line) in byte_lines(content).enumerate() {
+        if !RE_MENTION.is_match(line)
+            || !RE_MENTION.is_match(mask_code(&String::from_utf8_lossy(line)).as_bytes())
+        {
+            continue;
+        }
+        if parsed.contains(&(index + 1)) {
+            coverage.parsed.push(index + 1);
+        } else {
+            coverage.unparsed.push(index + 1);
+        }
+    }
+    coverage
+}
+
+/// A note with its legacy durations rewritten as fields.
+#[derive(Debug, Default, PartialEq)]
+pub struct Legacy {
+    pub content: String,
+    /// The 1-based numbers of the rewritten lines.
+    pub lines: Vec<usize>,
+}
+
+/// Rewrites each list item without time fields that ends in a
+/// parenthetical duration, optionally followed by tags, like
+/// `- did the thing (2h15m) #tag`, as if the duration were a
+/// `[timeTracked: 2h15m]` field. Lines keep their numbers.
+pub fn rewrite_legacy_durations(content: &str, units: &Units) -> Legacy {
+    static RE_LEGACY: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"^(?P<item>\s*(?:[-*+]|\d+[.)])\s.*?)\s*\((?P<value>[^()]+)\)(?P<tags>(?:\s+#[\p{L}\p{M}\p{N}_/-]+)*)\s*$",
+        )
+        .unwrap()
+    });
+    static RE_FIELD: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)time\s*(?:tracked|started|ended)").unwrap());
+    let mut legacy = Legacy::default();
+    for (index, line) in lines(content).enumerate() {
+        match RE_LEGACY.captures(line) {
+            Some(cap) if units.is_duration(&cap["value"]) && !RE_FIELD.is_match(line) => {
+                legacy.content.push_str(&format!(
+                    "{}{} [timeTracked: {}]",
+                    &cap["item"],
+                    &cap["tags"],
+                    cap["value"].trim()
+                ));
+                legacy.lines.push(index + 1);
+            }
+            _ => legacy.content.push_str(line),
+        }
+        legacy.content.push('\n');
+    }
+    legacy
+}
+
+fn scan_field_lines(content: &[u8], sort_tags: bool, units: &Units) -> Vec<FieldLine> {
+    let mut results = vec![];
+    let mut current_pbi: Option<String> = None;
+    let mut outline = Outline::default();
+    // The last list item as (indent, text, status) while indented
+    // continuation lines directly beneath it may still add fields to it.
+    let mut item: Option<(usize, String, Option<char>)> = None;
+
+    static RE_HEADING: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)^#+\s+Work on \[\[(\d+)\]\]").unwrap());
+    // Matches any Markdown header
+    static RE_GENERIC_HEADING: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)^#+\s+").unwrap());
+    static RE_LIST_ITEM: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s").unwrap());
+    static RE_MARKER: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s+(?:\[.\]\s+)?").unwrap());
+    static RE_CHECKBOX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s+\[(.)\]").unwrap());
+    static RE_COMPLETED: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"✅\s*(\d{4}-\d{2}-\d{2})").unwrap());
+    static RE_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"\[\s*(?P<key>timeTracked|timeEstimate|timeStarted|timeEnded|priority)\s*::?\s*(?P<value>[^\]]+)\]",
+        )
+        .unwrap()
+    });
+    // Not preceded by `[` nor followed by `(`, so wikilinks and Markdown
+    // links aren't read as fields. Keys may have several words.
+    static RE_INLINE_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"(?:^|[^\[])\[\s*(?P<key>[A-Za-z][\w -]*?)\s*::?\s*(?P<value>[^\[\]]+)\](?P<link>\()?",
+        )
+        .unwrap()
+    });
+
+    for (index, raw) in byte_lines(content).enumerate() {
+        // Any other line has no fields and is no heading or item, so it
+        // only ends the item above unless it continues it.
+        let start = raw.iter().position(|b| !b.is_ascii_whitespace());
+        let is_text = raw.first() == Some(&b'#')
+            || raw.contains(&b'[')
+            || start.is_some_and(|i| matches!(raw[i], b'-' | b'*' | b'+' | b'0'..=b'9' | 0x80..));
+        if !is_text {
+            let indent = indent_width(std::str::from_utf8(&raw[..start.unwrap_or(0)]).unwrap());
+            if start.is_none()
+                || item
+                    .as_ref()
+                    .is_none_or(|(item_indent, _, _)| indent <= *item_indent)
+            {
+                item = None;
+            }
+            continue;
+        }
+        let line = String::from_utf8_lossy(raw);
+        let line = line.as_ref();
+        log::debug!("Processing line: {}", line);
+        if RE_GENERIC_HEADING.is_match(line) {
+            if let Some(cap) = RE_HEADING.captures(line) {
+                current_pbi = Some(format!("#pbi-{}", &cap[1]));
+                log::debug!("Found PBI: {:?}", current_pbi);
+            } else {
+                current_pbi = None; // Reset PBI when encountering a generic Markdown header
+                log::debug!("Resetting current PBI due to generic header");
+            }
+            let level = line.len() - line.trim_start_matches('#').len();
+            let text = RE_GENERIC_HEADING.replace(line, "");
+            outline.heading(level, text.trim(), find_tags(line));
+            item = None;
+            continue;
+        }
+        log::debug!("Current PBI: {:?}", current_pbi);
+
+        // Fields are found in the masked line and cut out of the real one.
+        let masked = mask_code(line);
+        let mut task_text = line.to_string();
+        for m in RE_FIELD
+            .find_iter(&masked)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            task_text.replace_range(m.range(), " ");
+        }
+        let mut text = RE_MARKER
+            .replace(&task_text, "")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let indent = indent_width(line);
+        let mut status = None;
+        let (context, inherited) = if RE_LIST_ITEM.is_match(line) {
+            status = RE_CHECKBOX
+                .captures(line)
+                .and_then(|cap| cap[1].chars().next());
+            item = Some((indent, text.clone(), status));
+            outline.item(indent, &text, find_tags(line))
+        } else if let Some((item_indent, item_text, item_status)) = &item
+            && indent > *item_indent
+            && !line.trim().is_empty()
+        {
+            // A continuation line belongs to the item above it.
+            text = format!("{} {}", item_text, text).trim().to_string();
+            status = *item_status;
+            outline.continuation()
+        } else {
+            item = None;
+            outline.outside()
+        };
+
+        let mut tracked = None;
+        let mut syntax = Syntax::Clock;
+        let mut estimate = None;
+        let mut started = None;
+        let mut ended = None;
+        let mut priority = Priority::from_emoji(&text);
+        for cap in RE_FIELD.captures_iter(&masked) {
+            let value = cap["value"].trim();
+            match &cap["key"] {
+                "priority" => match value.parse() {
+                    Ok(field_priority) => priority = Some(field_priority),
+                    Err(e) => log::warn!("{} on line {}", e, index + 1),
+                },
+                "timeStarted" | "timeEnded" => {
+                    let Ok(time) = value.parse::<Time>() else {
+                        log::warn!("Invalid time '{}' on line {}", value, index + 1);
+                        continue;
+                    };
+                    match &cap["key"] {
+                        "timeStarted" => started = started.or(Some(time)),
+                        _ => ended = ended.or(Some(time)),
+                    }
+                }
+                key => {
+                    let duration = units.parse(value);
+                    log::debug!(
+                        "Parsed {}: {} from text: {}",
+                        key,
+                        format_duration(&duration),
+                        value
+                    );
+                    match key {
+                        "timeTracked" if tracked.is_none() => {
+                            tracked = Some(duration);
+                            syntax = if cap[0].contains("::") {
+                                Syntax::Dataview
+                            } else {
+                                Syntax::Field
+                            };
+                        }
+                        "timeTracked" => {}
+                        _ => estimate = estimate.or(Some(duration)),
+                    }
+                }
+            }
+        }
+        if tracked.is_none() && estimate.is_none() && started.is_none() {
+            continue;
+        }
+
+        let fields = RE_INLINE_FIELD
+            .captures_iter(&masked)
+            .filter(|cap| cap.name("link").is_none() && !RE_FIELD.is_match(&cap[0]))
+            .map(|cap| (cap["key"].to_string(), cap["value"].trim().to_string()))
+            .collect();
+        let combined_text = format!("{} {}", inherited.join(" "), text)
+            .trim()
+            .to_string();
+        let mut allocation = find_allocations(&text);
+        let percent: u32 = allocation.iter().map(|(_, p)| p).sum();
+        if !allocation.is_empty() && percent != 100 {
+            log::warn!(
+                "Allocations on line {} add up to {}%, not 100%; not splitting",
+                index + 1,
+                percent
+            );
+            allocation.clear();
+        }
+        results.push(FieldLine {
+            entry: TimeEntry {
+                tags: extract_tags(&combined_text, &current_pbi, sort_tags),
+                line: index + 1,
+                priority,
+                completed: RE_COMPLETED
+                    .captures(&text)
+                    .and_then(|cap| cap[1].parse().ok()),
+                text,
+                status,
+                heading: context
+                    .headings
+                    .last()
+                    .map(|(_, text)| text.clone())
+                    .unwrap_or_default(),
+                context,
+                syntax,
+                allocation,
+                fields,
+                ..Default::default()
+            },
+            tracked,
+            estimate,
+            started,
+            ended,
+        });
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::{TimeDuration, parse_duration};
+    use dedent::dedent;
+
+    #[test]
+    fn test_rewrite_legacy_durations() {
+        let content = dedent!(
+            r#"
+        # Notes
+        - did the thing (2h15m) #dev
+        - [x] Call with Acme (45 m)
+        - Split #clientA(75%) #clientB(25%) (1h)
+        - Meeting (see notes)
+        - Review (3 days later)
+        - Logged (1h) [timeTracked: 2h]
+        Not a list item (1h)
+        "#
+        );
+        let legacy = rewrite_legacy_durations(content, &Units::default());
+        assert_eq!(vec![2, 3, 4], legacy.lines);
+        let entries = parse_time_entries(&legacy.content, true, &Units::default());
+        let parsed: Vec<(&str, &str, String)> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.tags.as_str(),
+                    e.text.as_str(),
+                    format_duration(&e.duration),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("#dev", "did the thing #dev", "2h15m".to_string()),
+                ("", "Call with Acme", "45m".to_string()),
+                (
+                    "#clientA,#clientB",
+                    "Split #clientA(75%) #clientB(25%)",
+                    "1h".to_string()
+                ),
+                ("", "Logged (1h)", "2h".to_string()),
+            ],
+            parsed
+        );
+        assert_eq!(content.lines().count(), legacy.content.lines().count());
+    }
+
+    #[test]
+    fn test_extract_tags_with_pbi() {
+        let task_text = "Complete task #tag1 #tag2";
+        let current_pbi = Some("#pbi-123".to_string());
+        let tags = extract_tags(task_text, &current_pbi, false);
+        assert_eq!("#pbi-123,#tag1,#tag2", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_ordered() {
+        let task_text = "this #c is a task #a with unordered tags #b";
+        let current_pbi = None;
+        let tags = extract_tags(task_text, &current_pbi, true);
+        assert_eq!("#a,#b,#c", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_without_pbi() {
+        let task_text = "Complete task #tag1 #tag2";
+        let current_pbi = None;
+        let tags = extract_tags(task_text, &current_pbi, false);
+        assert_eq!("#tag1,#tag2", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_no_tags_with_pbi() {
+        let task_text = "Complete task";
+        let current_pbi = Some("#pbi-123".to_string());
+        let tags = extract_tags(task_text, &current_pbi, false);
+        assert_eq!("#pbi-123", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_no_tags_no_pbi() {
+        let task_text = "Complete task";
+        let current_pbi = None;
+        let tags = extract_tags(task_text, &current_pbi, false);
+        assert_eq!("", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_sorted() {
+        let task_text = "this #c is a task #a with unordered tags #b";
+        let current_pbi = None;
+        let tags = extract_tags(task_text, &current_pbi, true);
+        assert_eq!(tags, "#a,#b,#c");
+    }
+
+    #[test]
+    fn test_extract_tags_unsorted() {
+        let task_text = "this #c is a task #a with unordered tags #b";
+        let current_pbi = None;
+        let tags = extract_tags(task_text, &current_pbi, false);
+        assert_eq!(tags, "#c,#a,#b");
+    }
+
+    #[test]
+    fn test_parse_time_entries_with_sorted_tags() {
+        let content = dedent!(
+            r#"
+        # Work on [[123]]
+        - [ ] Task 1 [ timeTracked: 1h ] #c #a #b
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!("#a,#b,#c,#pbi-123", entries[0].tags);
+    }
+
+    #[test]
+    fn test_parse_time_entries_with_unsorted_tags() {
+        let content = dedent!(
+            r#"
+        # Work on [[123]]
+        - [ ] Task 1 [ timeTracked: 1h ] #c #a #b
+        "#
+        );
+        let entries = parse_time_entries(content, false, &Units::default());
+        assert_eq!("#pbi-123,#c,#a,#b", entries[0].tags);
+    }
+
+    #[test]
+    fn test_parse_time_entries_sub_bullets_inherit_parent_tags() {
+        let content = dedent!(
+            r#"
+        - [ ] Migrate database #clientA
+            - [x] write script [timeTracked: 1h]
+            - [x] dry run [timeTracked: 30m] #ops
+                - [x] fix constraint [timeTracked: 15m]
+        - [ ] Unrelated [timeTracked: 10m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        let tags: Vec<&str> = entries.iter().map(|e| e.tags.as_str()).collect();
+        assert_eq!(vec!["#clientA", "#clientA,#ops", "#clientA,#ops", ""], tags);
+    }
+
+    #[test]
+    fn test_parse_time_entries_heading_tags_inherited() {
+        let content = dedent!(
+            r#"
+        ## Afternoon #clientA
+        - [ ] Call [timeTracked: 1h]
+            - [x] notes #followup [timeTracked: 15m]
+        # Work on [[7]] #clientB
+        - [ ] Build [timeTracked: 2h]
+        ## Evening
+        - [ ] Read [timeTracked: 30m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        let tags: Vec<&str> = entries.iter().map(|e| e.tags.as_str()).collect();
+        assert_eq!(
+            vec!["#clientA", "#clientA,#followup", "#clientB,#pbi-7", ""],
+            tags
+        );
+    }
+
+    #[test]
+    fn test_parse_time_entries_completion_date() {
+        let content = dedent!(
+            r#"
+        - [x] Ship release [timeTracked: 1h] ✅ 2025-01-03
+        - [ ] Still open [timeTracked: 30m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(Some(jiff::civil::date(2025, 1, 3)), entries[0].completed);
+        assert_eq!(None, entries[1].completed);
+    }
+
+    #[test]
+    fn test_parse_time_entries_text() {
+        let content = dedent!(
+            r#"
+        - [x] Run  migration #ops [timeTracked: 1h] ⏫
+        1. Review [timeTracked: 30m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!("Run migration #ops ⏫", entries[0].text);
+        assert_eq!("Review", entries[1].text);
+    }
+
+    #[test]
+    fn test_parse_inline_fields() {
+        let entries = parse_time_entries(
+            "- [ ] Call [client:: Acme] [phase: design] [Billing code: ACME 7] [[Meeting: notes]] [see: docs](https://example.com) [timeTracked: 1h] [priority: high]",
+            true,
+            &Units::default(),
+        );
+        assert_eq!(
+            BTreeMap::from([
+                ("Billing code".to_string(), "ACME 7".to_string()),
+                ("client".to_string(), "Acme".to_string()),
+                ("phase".to_string(), "design".to_string()),
+            ]),
+            entries[0].fields
+        );
+        assert_eq!(
+            Some(Value::Text("Acme".to_string())),
+            entries[0].field("client")
+        );
+    }
+
+    #[test]
+    fn test_mentions_time_fields() {
+        assert!(mentions_time_fields(b"- [ ] Task [timeTracked: 1h]"));
+        assert!(mentions_time_fields(b"- [ ] Task [Time Started:: 09:00]"));
+        assert!(!mentions_time_fields(b"- [ ] Task (1h) \xff"));
+    }
+
+    #[test]
+    fn test_entry_id_is_stable() {
+        let entries = parse_time_entries(
+            "- [ ] Task [timeTracked: 1h]\n- [ ] Task [timeTracked: 1h]",
+            true,
+            &Units::default(),
+        );
+        let path = Path::new("2025-01-01.md");
+        assert_eq!("3831b64029f872af", entries[0].id(path));
+        assert_ne!(entries[0].id(path), entries[1].id(path));
+        assert_ne!(
+            entries[0].id(path),
+            entries[0].id(Path::new("2025-01-02.md"))
+        );
+    }
+
+    #[test]
+    fn test_parse_time_entries_continuation_line() {
+        let content = dedent!(
+            r#"
+        - [ ] Migrate the billing database to the new cluster #clientA ⏫
+          [timeTracked: 2h]
+            - [x] dry run #ops
+              with rollback [timeTracked: 30m]
+
+          [timeTracked: 1h]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(3, entries.len());
+        assert_eq!("#clientA", entries[0].tags);
+        assert_eq!(
+            "Migrate the billing database to the new cluster #clientA ⏫",
+            entries[0].text
+        );
+        assert_eq!(Some(Priority::High), entries[0].priority);
+        assert_eq!(2, entries[0].line);
+        assert_eq!("#clientA,#ops", entries[1].tags);
+        assert_eq!("dry run #ops with rollback", entries[1].text);
+        // A blank line ends the item, so the last field stands alone.
+        assert_eq!("", entries[2].tags);
+    }
+
+    #[test]
+    fn test_ignore_fields_in_inline_code() {
+        let content = dedent!(
+            r#"
+        - [ ] Document `[timeTracked: 1h]` syntax #docs
+        - [ ] Escaped \[timeTracked: 2h] and ``a `[timeTracked: 3h]` b`` too
+        - [ ] Real one `code` #dev [timeTracked: 30m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(1, entries.len());
+        assert_eq!(3, entries[0].line);
+        assert_eq!("Real one `code` #dev", entries[0].text);
+        assert_eq!(
+            Coverage {
+                parsed: vec![3],
+                unparsed: vec![]
+            },
+            parse_coverage(content)
+        );
+    }
+
+    #[test]
+    fn test_entry_syntax() {
+        let content = dedent!(
+            r#"
+        - [ ] Field [timeTracked: 1h]
+        - [ ] Dataview [timeTracked:: 2h] [priority:: high]
+        - [ ] Clock [timeStarted: 09:00] [timeEnded: 09:30]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(
+            vec![Syntax::Field, Syntax::Dataview, Syntax::Clock],
+            entries.iter().map(|e| e.syntax).collect::<Vec<_>>()
+        );
+        assert_eq!(TimeDuration::new(2, 0, 0), entries[1].duration);
+        assert_eq!(Some(Priority::High), entries[1].priority);
+    }
+
+    #[test]
+    fn test_status_and_heading() {
+        let content = dedent!(
+            r#"
+        - Plain [timeTracked: 1m]
+        ## Work on [[7]] #dev
+        - [x] Done [timeTracked: 2m]
+        - [-] Dropped
+          [timeTracked: 3m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(
+            vec!["", "done", "cancelled"],
+            entries.iter().map(|e| e.status_name()).collect::<Vec<_>>()
+        );
+        assert_eq!("", entries[0].heading);
+        assert_eq!("Work on [[7]] #dev", entries[2].heading);
+    }
+
+    #[test]
+    fn test_entry_context() {
+        let content = dedent!(
+            r#"
+        # Acme
+        ## Backend
+        - Auth #dev
+          - [ ] Fix login [timeTracked: 1h]
+        ### Notes
+        - [ ] Write up [timeTracked: 30m]
+        ## Frontend
+        - [ ] Polish [timeTracked: 15m]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(
+            Context {
+                headings: vec![(1, "Acme".to_string()), (2, "Backend".to_string())],
+                parents: vec!["Auth #dev".to_string()],
+            },
+            entries[0].context
+        );
+        assert_eq!("#dev", entries[0].tags);
+        assert_eq!("Acme > Backend > Notes", entries[1].context.path());
+        assert_eq!("Frontend", entries[2].context.heading(2));
+        assert_eq!("", entries[2].context.heading(3));
+    }
+
+    #[test]
+    fn test_allocation() {
+        let content = dedent!(
+            r#"
+        - [ ] Migration #dev #clientA(75%) #clientB(25%) [timeTracked: 4h10m]
+        - [ ] Review #clientA(50%) #clientB(25%) [timeTracked: 1h]
+        "#
+        );
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(
+            vec![("#clientA".to_string(), 75), ("#clientB".to_string(), 25)],
+            entries[0].allocation
+        );
+        let shares: Vec<(String, String)> = entries[0]
+            .allocate(1)
+            .iter()
+            .map(|share| (share.tags.clone(), format_duration(&share.duration)))
+            .collect();
+        assert_eq!(
+            vec![
+                ("#clientA,#dev".to_string(), "3h7m30s".to_string()),
+                ("#clientB,#dev".to_string(), "1h2m30s".to_string()),
+            ],
+            shares
+        );
+        assert!(entries[1].allocation.is_empty());
+        assert_eq!(1, entries[1].allocate(1).len());
+        let minutes: Vec<String> = entries[0]
+            .allocate(60)
+            .iter()
+            .map(|share| format_duration(&share.duration))
+            .collect();
+        assert_eq!(vec!["3h8m", "1h2m"], minutes);
+    }
+
+    #[test]
+    fn test_parse_coverage() {
+        let content = dedent!(
+            r#"
+        - [ ] Parsed [timeTracked: 1h]
+        - [ ] Missing colon [timeTracked 1h]
+        - [ ] Wrong case [timetracked: 1h]
+        - [ ] Only an end [timeEnded: 10:00]
+        - [ ] Estimate [timeEstimate: 2h]
+        - [ ] No fields
+        "#
+        );
+        assert_eq!(
+            Coverage {
+                parsed: vec![1, 5],
+                unparsed: vec![2, 3, 4],
+            },
+            parse_coverage(content)
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let content = dedent!(
+            r#"
+        # Work on [[7]] #dev
+        - [ ] Design [timeTracked: 1h] [client: Acme]
+          - Review `[timeTracked: 2h]` #qa [timeTracked:: 30m]
+        Note [timeTracked 1h]
+        "#
+        );
+        let expected = parse_time_entries(content, false, &Units::default());
+        assert_eq!(2, expected.len());
+        // Windows endings, then endings mixed with doubled `\r`s and
+        // trailing whitespace, then a byte order mark.
+        let crlf = content.replace('\n', "\r\n");
+        let mixed: String = content
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(index, line)| match index % 3 {
+                0 => line.replace('\n', "\r\n"),
+                1 => line.replace('\n', " \t\r\r\n"),
+                _ => line.to_string(),
+            })
+            .collect();
+        for variant in [crlf, mixed, format!("\u{feff}{}", content)] {
+            assert_eq!(
+                expected,
+                parse_time_entries(&variant, false, &Units::default())
+            );
+            assert_eq!(parse_coverage(content), parse_coverage(&variant));
+        }
+    }
+
+    #[test]
+    fn test_parse_estimates() {
+        let content = dedent!(
+            r#"
+        # Work on [[123]]
+        - [ ] Task 1 [timeEstimate: 4h] [timeTracked: 1h]
+        - [ ] Task 2 [timeEstimate: 2h]
+        - [ ] Task 3 [timeTracked: 30m]
+        "#
+        );
+        let estimates = parse_estimates(content, true, &Units::default());
+        assert_eq!(
+            vec![
+                ("#pbi-123".to_string(), parse_duration("4h")),
+                ("#pbi-123".to_string(), parse_duration("2h")),
+            ],
+            estimates
+        );
+        assert_eq!(
+            2,
+            parse_time_entries(content, true, &Units::default()).len()
+        );
+    }
+
+    #[test]
+    fn test_parse_open_sessions() {
+        let content = dedent!(
+            r#"
+        - [ ] Still going #ops [timeStarted: 09:15]
+        - [x] Done #ops [timeStarted: 08:00] [timeEnded: 09:15]
+        - [x] Logged #ops [timeStarted: 07:00] [timeTracked: 30m]
+        "#
+        );
+        let sessions = parse_open_sessions(content, true, &Units::default());
+        assert_eq!(1, sessions.len());
+        assert_eq!(Time::constant(9, 15, 0, 0), sessions[0].started);
+        assert_eq!(1, sessions[0].entry.line);
+
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(
+            vec![parse_duration("1h15m"), parse_duration("30m")],
+            entries
+                .iter()
+                .map(|e| e.duration.clone())
+                .collect::<Vec<_>>()
+        );
+
+        // One pass finds both, reading only the lines that matter as text.
+        let mut raw = content.as_bytes().to_vec();
+        raw.extend(b"\nPlain \xff text\n- [ ] Odd \xff #dev [timeTracked: 1h]\n");
+        let scan = scan_note(&raw, true, &Units::default());
+        assert_eq!(1, scan.sessions.len());
+        assert_eq!(3, scan.entries.len());
+        assert_eq!("Odd \u{fffd} #dev", scan.entries[2].text);
+        assert_eq!(vec![1, 2, 3, 5], scan.lines);
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        let content = dedent!(
+            r#"
+        - [ ] Urgent fix ⏫ [timeTracked: 1h]
+        - [ ] Cleanup 🔽 [timeTracked: 30m]
+        - [ ] Review [priority: Highest] [timeTracked: 15m]
+        - [ ] Chores [timeTracked: 10m]
+        "#
+        );
+        let priorities: Vec<Option<Priority>> =
+            parse_time_entries(content, true, &Units::default())
+                .iter()
+                .map(|e| e.priority)
+                .collect();
+        assert_eq!(
+            vec![
+                Some(Priority::High),
+                Some(Priority::Low),
+                Some(Priority::Highest),
+                None
+            ],
+            priorities
+        );
+    }
+
+    #[test]
+    fn test_extract_tags_unicode() {
+        let tags = extract_tags(
+            "planning #möte and #日本語 with #team/backend",
+            &None,
+            false,
+        );
+        assert_eq!("#möte,#日本語,#team/backend", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_skips_numeric_only() {
+        let tags = extract_tags("issue #123 for #pbi-123", &None, false);
+        assert_eq!("#pbi-123", tags);
+    }
+
+    #[test]
+    fn test_extract_tags_deduplicates() {
+        let tags = extract_tags("#a #b #a", &None, false);
+        assert_eq!("#a,#b", tags);
+    }
+
+    #[test]
+    fn test_parse_time_entries_with_text_before_and_after() {
+        let content = r#"
+        - [ ] Task 1 [ timeTracked: 1h ] more text
+        "#;
+        let entries = parse_time_entries(content, true, &Units::default());
+        assert_eq!(TimeDuration::new(1, 0, 0), entries[0].duration);
+    }
+}
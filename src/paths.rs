@@ -0,0 +1,107 @@
+// src/paths.rs
+
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// How reports show a note's path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// The file name alone.
+    Basename,
+    /// The path below the directory it was found in.
+    Relative,
+    Absolute,
+    /// Every folder but the last shortened to its first letter, like
+    /// `J…/2025/2025-01-03.md`.
+    Abbrev,
+}
+
+impl FromStr for PathStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "basename" => Ok(PathStyle::Basename),
+            "relative" => Ok(PathStyle::Relative),
+            "absolute" => Ok(PathStyle::Absolute),
+            "abbrev" => Ok(PathStyle::Abbrev),
+            _ => Err(format!(
+                "unknown path style '{}'; expected basename, relative, absolute, or abbrev",
+                s
+            )),
+        }
+    }
+}
+
+impl PathStyle {
+    /// Shows `path`, found in one of `roots` unless it was given directly,
+    /// in this style. Paths that can't be shown so are shown as given.
+    pub fn display(&self, path: &Path, roots: &[PathBuf]) -> String {
+        let shown = match self {
+            PathStyle::Basename => path.file_name().map(PathBuf::from),
+            PathStyle::Relative => roots
+                .iter()
+                .find_map(|root| path.strip_prefix(root).ok())
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .map(PathBuf::from),
+            PathStyle::Absolute => std::path::absolute(path).ok(),
+            PathStyle::Abbrev => Some(abbreviate(path)),
+        };
+        shown
+            .as_deref()
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn abbreviate(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    let mut abbreviated = PathBuf::new();
+    for (index, component) in components.iter().enumerate() {
+        match component {
+            // The file and the folder holding it are kept whole.
+            Component::Normal(name) if index + 2 < components.len() => {
+                let name = name.to_string_lossy();
+                let mut chars = name.chars();
+                let first: String = chars.by_ref().take(1).collect();
+                match chars.next() {
+                    Some(_) => abbreviated.push(format!("{}…", first)),
+                    None => abbreviated.push(first),
+                }
+            }
+            component => abbreviated.push(component),
+        }
+    }
+    abbreviated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let roots = [PathBuf::from("vault/Journals")];
+        let path = Path::new("vault/Journals/2025/2025-01-03.md");
+        assert_eq!("2025-01-03.md", PathStyle::Basename.display(path, &roots));
+        assert_eq!(
+            Path::new("2025").join("2025-01-03.md").to_string_lossy(),
+            PathStyle::Relative.display(path, &roots)
+        );
+        assert_eq!(
+            "other/a.md",
+            PathStyle::Relative.display(Path::new("other/a.md"), &roots)
+        );
+        assert!(Path::new(&PathStyle::Absolute.display(path, &roots)).is_absolute());
+        assert_eq!(
+            Path::new("v…/J…/2025/2025-01-03.md").to_string_lossy(),
+            PathStyle::Abbrev.display(path, &roots)
+        );
+        assert_eq!(
+            Path::new("/h…/x/notes/a.md").to_string_lossy(),
+            PathStyle::Abbrev.display(Path::new("/home/x/notes/a.md"), &roots)
+        );
+        assert!("short".parse::<PathStyle>().is_err());
+    }
+}
@@ -0,0 +1,240 @@
+// src/properties.rs
+
+use crate::json;
+use crate::parser::lines;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// A note property's value, typed as Obsidian's properties do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Text, dates, and links, without quotes.
+    Text(String),
+    /// A number as written, like `120` or `0.5`.
+    Number(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Value {
+    /// The value as JSON: text as a string (`null` when empty), numbers and
+    /// checkboxes unquoted, and lists as arrays of strings.
+    pub fn to_json(&self) -> String {
+        match self {
+            Value::Text(text) if text.is_empty() => "null".to_string(),
+            Value::Text(text) => json::quote(text),
+            Value::Number(number) => number.clone(),
+            Value::Bool(value) => value.to_string(),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| json::quote(item)).collect();
+                format!("[{}]", items.join(","))
+            }
+        }
+    }
+
+    /// The keys the value is grouped under: each item of a list, or the
+    /// value itself.
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            Value::List(items) => items.clone(),
+            value => vec![value.to_string()],
+        }
+    }
+}
+
+/// Lists are joined with commas, like tags.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Text(text) | Value::Number(text) => f.write_str(text),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::List(items) => f.write_str(&items.join(",")),
+        }
+    }
+}
+
+fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = text
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    text
+}
+
+fn scalar(text: &str) -> Value {
+    let text = text.trim();
+    if text != unquote(text) {
+        return Value::Text(unquote(text).to_string());
+    }
+    let re_number = Regex::new(r"^-?\d+(?:\.\d+)?$").unwrap();
+    match text {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if re_number.is_match(text) => Value::Number(text.to_string()),
+        _ => Value::Text(text.to_string()),
+    }
+}
+
+/// Reads the properties in a note's frontmatter, as written by Obsidian's
+/// properties editor: `key: value` lines, with lists either inline
+/// (`[a, b]`) or as indented `- item` lines below the key.
+/// The leading `---` block of a note's raw `content`, the only part its
+/// properties are read from, as text.
+pub fn front_matter(content: &[u8]) -> Cow<'_, str> {
+    let content = content.strip_prefix(b"\xef\xbb\xbf").unwrap_or(content);
+    let mut end = 0;
+    for (index, line) in content.split_inclusive(|&b| b == b'\n').enumerate() {
+        let fence = line.trim_ascii_end() == b"---";
+        if index == 0 && !fence {
+            break;
+        }
+        end += line.len();
+        if index > 0 && fence {
+            break;
+        }
+    }
+    String::from_utf8_lossy(&content[..end])
+}
+
+pub fn parse_properties(content: &str) -> BTreeMap<String, Value> {
+    let mut properties = BTreeMap::new();
+    let mut lines = lines(content).peekable();
+    if lines.next().is_none_or(|line| line.trim_end() != "---") {
+        return properties;
+    }
+    static RE_PROPERTY: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^([^\s:#-][^:]*):(?:\s+(.*))?$").unwrap());
+    static RE_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s+-\s*(.*)$").unwrap());
+    while let Some(line) = lines.next() {
+        if line.trim_end() == "---" {
+            break;
+        }
+        let Some(cap) = RE_PROPERTY.captures(line.trim_end()) else {
+            continue;
+        };
+        let key = cap[1].trim().to_string();
+        let text = cap.get(2).map_or("", |m| m.as_str().trim());
+        let value = if let Some(inline) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            Value::List(
+                inline
+                    .split(',')
+                    .map(|item| unquote(item).to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            )
+        } else if text.is_empty() {
+            let mut items = vec![];
+            while let Some(cap) = lines.peek().and_then(|line| RE_ITEM.captures(line)) {
+                items.push(unquote(&cap[1]).to_string());
+                lines.next();
+            }
+            if items.is_empty() {
+                Value::Text(String::new())
+            } else {
+                Value::List(items)
+            }
+        } else {
+            scalar(text)
+        };
+        properties.insert(key, value);
+    }
+    properties
+}
+
+/// The note tags set in frontmatter as Obsidian reads them, from `tags`
+/// (or `tag`) as a list or as comma- or space-separated text, each with a
+/// leading `#`.
+pub fn frontmatter_tags(properties: &BTreeMap<String, Value>) -> Vec<String> {
+    let Some(value) = ["tags", "tag"].iter().find_map(|key| properties.get(*key)) else {
+        return vec![];
+    };
+    let items = match value {
+        Value::List(items) => items.clone(),
+        value => vec![value.to_string()],
+    };
+    let mut tags: Vec<String> = vec![];
+    for tag in items
+        .iter()
+        .flat_map(|item| item.split(|c: char| c == ',' || c.is_whitespace()))
+        .map(|tag| tag.trim_start_matches('#'))
+        .filter(|tag| !tag.is_empty())
+    {
+        let tag = format!("#{}", tag);
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    #[test]
+    fn test_parse_properties() {
+        let content = dedent!(
+            r#"
+        ---
+        client: Acme Corp
+        billable: true
+        rate: 120.5
+        invoice: "42"
+        projects:
+          - "[[Website]]"
+          - Hosting
+        aliases: [acme, 'ACME']
+        due:
+        ---
+        - [ ] Task [timeTracked: 1h]
+        client: not a property
+        "#
+        );
+        let properties = parse_properties(content);
+        assert_eq!(Value::Text("Acme Corp".into()), properties["client"]);
+        assert_eq!(Value::Bool(true), properties["billable"]);
+        assert_eq!(Value::Number("120.5".into()), properties["rate"]);
+        assert_eq!(Value::Text("42".into()), properties["invoice"]);
+        assert_eq!(
+            Value::List(vec!["[[Website]]".into(), "Hosting".into()]),
+            properties["projects"]
+        );
+        assert_eq!("acme,ACME", properties["aliases"].to_string());
+        assert_eq!("null", properties["due"].to_json());
+        assert_eq!(
+            r#"["[[Website]]","Hosting"]"#,
+            properties["projects"].to_json()
+        );
+        assert_eq!(7, properties.len());
+        assert!(parse_properties("client: Acme").is_empty());
+
+        let front = front_matter(content.as_bytes());
+        assert!(front.ends_with("due:\n---\n"));
+        assert_eq!(properties, parse_properties(&front));
+        assert_eq!("", front_matter(b"client: Acme\n---\n"));
+    }
+
+    #[test]
+    fn test_frontmatter_tags() {
+        let tags = |content: &str| frontmatter_tags(&parse_properties(content));
+        assert_eq!(
+            vec!["#clientA", "#deepwork"],
+            tags("---\ntags: [clientA, \"#deepwork\"]\n---\n")
+        );
+        assert_eq!(
+            vec!["#projects/site", "#ops"],
+            tags("---\ntags:\n  - projects/site\n  - ops\n---\n")
+        );
+        assert_eq!(vec!["#a", "#b"], tags("---\ntag: a, b a\n---\n"));
+        assert!(tags("---\nclient: Acme\n---\n").is_empty());
+    }
+}
@@ -0,0 +1,619 @@
+// src/query.rs
+
+use crate::duration::{TimeDuration, parse_duration};
+use crate::parser::{Priority, TimeEntry, has_tag};
+use crate::sprint::Sprints;
+use jiff::civil::Date;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A parsed `query` expression: conditions on entries joined with `AND` and
+/// `OR` (`AND` binds tighter, parentheses group), optionally followed by
+/// `GROUP BY` one of tag, day, week, month, file, priority, or hour-of-day.
+///
+/// ```text
+/// tag = #pbi-123 AND date >= 2025-01-01 GROUP BY week
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    filter: Option<Expr>,
+    pub group: Option<Group>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Condition(Condition),
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Tag(Op, String),
+    Date(Op, Date),
+    Duration(Op, TimeDuration),
+    Priority(Op, Option<Priority>),
+    Text(Op, Text),
+    File(Op, Text),
+}
+
+#[derive(Debug, Clone)]
+enum Text {
+    Exact(String),
+    Pattern(Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `~`: a regex match for text and files, a substring of a tag for tags.
+    Match,
+}
+
+/// What `GROUP BY` totals entries by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Group {
+    /// Each of an entry's tags; an entry counts toward every tag it carries.
+    Tag,
+    Day,
+    /// The ISO week, like `2025-W02`.
+    Week,
+    Month,
+    /// The configured sprint, like `2025.03`; entries outside every sprint
+    /// fall in an empty group.
+    Sprint,
+    File,
+    Priority,
+    /// The hour of the day, like `09:00`, for entries with a `timeStarted`
+    /// time. Their time is split across the hours it spans; other entries
+    /// fall in an empty group.
+    HourOfDay,
+    /// The git author of the entry's line, with `--authors`.
+    Author,
+    /// A frontmatter property of the entry's note, written `note.NAME`. An
+    /// entry counts toward each item of a list.
+    Property(String),
+    /// An inline field on the entry's line, like `[client:: Acme]`, else
+    /// the note's frontmatter property of that name, written `field:NAME`.
+    Field(String),
+}
+
+impl Op {
+    fn compare(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+            Op::Match => false,
+        }
+    }
+}
+
+impl Text {
+    fn matches(&self, op: Op, value: &str) -> bool {
+        match self {
+            Text::Pattern(re) => re.is_match(value),
+            Text::Exact(text) => op.compare(value.cmp(text)),
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, entry: &TimeEntry, file: &str) -> bool {
+        match self {
+            Condition::Tag(Op::Match, tag) => {
+                entry.tags.split(',').any(|t| t.contains(tag.as_str()))
+            }
+            Condition::Tag(op, tag) => has_tag(&entry.tags, tag) == (*op == Op::Eq),
+            Condition::Date(op, date) => entry.date.is_some_and(|d| op.compare(d.cmp(date))),
+            Condition::Duration(op, duration) => op.compare(
+                entry
+                    .duration
+                    .total_seconds()
+                    .cmp(&duration.total_seconds()),
+            ),
+            Condition::Priority(op, priority) => (entry.priority == *priority) == (*op == Op::Eq),
+            Condition::Text(op, text) => text.matches(*op, &entry.text),
+            Condition::File(op, text) => text.matches(*op, file),
+        }
+    }
+}
+
+impl Expr {
+    fn matches(&self, entry: &TimeEntry, file: &str) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(entry, file) && b.matches(entry, file),
+            Expr::Or(a, b) => a.matches(entry, file) || b.matches(entry, file),
+            Expr::Condition(condition) => condition.matches(entry, file),
+        }
+    }
+}
+
+impl Query {
+    /// Whether the entry, read from `file`, satisfies the query's conditions.
+    pub fn matches(&self, entry: &TimeEntry, file: &str) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|expr| expr.matches(entry, file))
+    }
+}
+
+/// Totals entries, given with their file, per `group` key along with the
+/// number of entries in each. Undated entries fall in an empty date group.
+/// `sprints` are the sprints grouped by for `Group::Sprint`.
+pub fn group_totals<'a>(
+    group: &Group,
+    sprints: &Sprints,
+    rows: impl IntoIterator<Item = (&'a TimeEntry, String)>,
+) -> BTreeMap<String, (TimeDuration, usize)> {
+    let mut totals: BTreeMap<String, (TimeDuration, usize)> = BTreeMap::new();
+    for (entry, file) in rows {
+        let date = |f: fn(Date) -> String| entry.date.map(f).unwrap_or_default();
+        if *group == Group::HourOfDay {
+            for (key, duration) in hours_of_day(entry) {
+                let total = totals.entry(key).or_default();
+                total.0 += &duration;
+                total.1 += 1;
+            }
+            continue;
+        }
+        let keys = match group {
+            Group::Tag => entry
+                .tags
+                .split(',')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect(),
+            Group::Day => vec![date(|d| d.to_string())],
+            Group::Week => vec![date(|d| {
+                let week = d.iso_week_date();
+                format!("{:04}-W{:02}", week.year(), week.week())
+            })],
+            Group::Month => vec![date(|d| format!("{:04}-{:02}", d.year(), d.month()))],
+            Group::Sprint => vec![
+                entry
+                    .date
+                    .and_then(|d| sprints.sprint(d))
+                    .map(|sprint| sprint.name)
+                    .unwrap_or_default(),
+            ],
+            Group::File => vec![file],
+            Group::Priority => vec![entry.priority.map(|p| p.to_string()).unwrap_or_default()],
+            Group::Author => vec![entry.author.clone()],
+            Group::Property(name) => entry
+                .properties
+                .get(name)
+                .map_or_else(|| vec![String::new()], |value| value.keys()),
+            Group::Field(name) => entry
+                .field(name)
+                .map_or_else(|| vec![String::new()], |value| value.keys()),
+            Group::HourOfDay => unreachable!(),
+        };
+        for key in keys {
+            let total = totals.entry(key).or_default();
+            total.0 += &entry.duration;
+            total.1 += 1;
+        }
+    }
+    totals
+}
+
+/// Splits an entry's duration over the hours of the day it spans from its
+/// start time, wrapping past midnight.
+fn hours_of_day(entry: &TimeEntry) -> Vec<(String, TimeDuration)> {
+    let Some(started) = entry.started else {
+        return vec![(String::new(), entry.duration.clone())];
+    };
+    let mut hour = started.hour() as u64;
+    let mut offset = started.minute() as u64 * 60 + started.second() as u64;
+    let mut remaining = entry.duration.total_seconds();
+    let mut hours = vec![];
+    while remaining > 0 {
+        let seconds = remaining.min(3600 - offset);
+        hours.push((
+            format!("{:02}:00", hour),
+            TimeDuration::from_seconds(seconds),
+        ));
+        remaining -= seconds;
+        hour = (hour + 1) % 24;
+        offset = 0;
+    }
+    hours
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Group::Tag => "tag",
+            Group::Day => "day",
+            Group::Week => "week",
+            Group::Month => "month",
+            Group::Sprint => "sprint",
+            Group::File => "file",
+            Group::Priority => "priority",
+            Group::HourOfDay => "hour-of-day",
+            Group::Author => "author",
+            Group::Property(name) => return write!(f, "note.{}", name),
+            Group::Field(name) => return write!(f, "field:{}", name),
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Group {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = s
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("note."))
+            .and(s.get(5..))
+            .filter(|name| !name.is_empty())
+        {
+            return Ok(Group::Property(name.to_string()));
+        }
+        if let Some(name) = s
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("field:"))
+            .and(s.get(6..))
+            .filter(|name| !name.is_empty())
+        {
+            return Ok(Group::Field(name.to_string()));
+        }
+        match s.to_lowercase().as_str() {
+            "tag" => Ok(Group::Tag),
+            "day" | "date" => Ok(Group::Day),
+            "week" => Ok(Group::Week),
+            "month" => Ok(Group::Month),
+            "sprint" => Ok(Group::Sprint),
+            "file" => Ok(Group::File),
+            "priority" => Ok(Group::Priority),
+            "hour-of-day" | "hour" => Ok(Group::HourOfDay),
+            "author" => Ok(Group::Author),
+            _ => Err(format!("cannot group by '{}'", s)),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let filter = match parser.peek() {
+            None => None,
+            Some(token) if token.eq_ignore_ascii_case("GROUP") => None,
+            Some(_) => Some(parser.or()?),
+        };
+        let group = if parser.keyword("GROUP") {
+            if !parser.keyword("BY") {
+                return Err("expected BY after GROUP".to_string());
+            }
+            Some(parser.next("a field to group by")?.parse()?)
+        } else {
+            None
+        };
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected '{}'", token));
+        }
+        Ok(Query { filter, group })
+    }
+}
+
+/// Splits a query into words, operators, parentheses, and quoted strings
+/// (returned without their quotes).
+fn tokenize(query: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' || c == '~' {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if "=!<>".contains(c) {
+            chars.next();
+            let mut op = c.to_string();
+            if chars.next_if_eq(&'=').is_some() {
+                op.push('=');
+            }
+            tokens.push(op);
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let text: String = chars.by_ref().take_while(|&q| q != c).collect();
+            tokens.push(text);
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"()~=!<>".contains(*c)) {
+                word.push(c);
+            }
+            tokens.push(word);
+        }
+    }
+    if tokens.iter().any(|t| t == "!") {
+        return Err("expected '!=' in query".to_string());
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.as_str())
+    }
+
+    fn next(&mut self, expected: &str) -> Result<String, String> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or_else(|| format!("expected {} at the end of the query", expected))?;
+        self.position += 1;
+        Ok(token.clone())
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.keyword("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.primary()?));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some("(") {
+            self.position += 1;
+            let expr = self.or()?;
+            if self.next("')'")? != ")" {
+                return Err("expected ')'".to_string());
+            }
+            return Ok(expr);
+        }
+        let field = self.next("a field")?.to_lowercase();
+        let op = match self.next("an operator")?.as_str() {
+            "=" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "~" => Op::Match,
+            other => {
+                return Err(format!(
+                    "expected an operator after {}, got '{}'",
+                    field, other
+                ));
+            }
+        };
+        let value = self.next("a value")?;
+        let unsupported = || format!("{} does not support this operator", field);
+        let text = |value: String| match op {
+            Op::Match => Regex::new(&value)
+                .map(Text::Pattern)
+                .map_err(|e| e.to_string()),
+            _ => Ok(Text::Exact(value)),
+        };
+        let condition = match field.as_str() {
+            "tag" if matches!(op, Op::Eq | Op::Ne | Op::Match) => Condition::Tag(op, value),
+            "date" if op != Op::Match => Condition::Date(
+                op,
+                value
+                    .parse()
+                    .map_err(|_| format!("expected a YYYY-MM-DD date, got '{}'", value))?,
+            ),
+            "duration" if op != Op::Match => Condition::Duration(op, parse_duration(&value)),
+            "priority" if matches!(op, Op::Eq | Op::Ne) => Condition::Priority(
+                op,
+                match value.as_str() {
+                    "none" => None,
+                    _ => Some(value.parse()?),
+                },
+            ),
+            "text" => Condition::Text(op, text(value)?),
+            "file" => Condition::File(op, text(value)?),
+            "tag" | "date" | "duration" | "priority" => return Err(unsupported()),
+            _ => return Err(format!("unknown field '{}'", field)),
+        };
+        Ok(Expr::Condition(condition))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::Value;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_query_conditions() {
+        let query: Query = "tag = #pbi-1 AND (date >= 2025-01-06 OR duration > 2h)"
+            .parse()
+            .unwrap();
+        assert_eq!(None, query.group);
+        assert!(query.matches(
+            &TimeEntry::test("#dev,#pbi-1", "1h").dated(date(2025, 1, 6)),
+            ""
+        ));
+        assert!(query.matches(&TimeEntry::test("#pbi-1", "3h").dated(date(2025, 1, 1)), ""));
+        assert!(!query.matches(&TimeEntry::test("#pbi-1", "1h").dated(date(2025, 1, 1)), ""));
+        assert!(!query.matches(&TimeEntry::test("#pbi-1", "1h"), ""));
+        assert!(!query.matches(
+            &TimeEntry::test("#pbi-12", "3h").dated(date(2025, 1, 6)),
+            ""
+        ));
+
+        let query: Query = "text ~ '(?i)work' AND priority = none AND tag != #ops"
+            .parse()
+            .unwrap();
+        let work = |tags: &str| TimeEntry::test(tags, "1h").with_text("Work on it");
+        assert!(query.matches(&work("#dev"), ""));
+        assert!(!query.matches(&work("#ops"), ""));
+        assert!(!query.matches(&TimeEntry::test("#dev", "1h"), ""));
+    }
+
+    #[test]
+    fn test_query_group_by_week() {
+        let query: Query = "GROUP BY week".parse().unwrap();
+        assert_eq!(Some(Group::Week), query.group);
+        let entries = [
+            TimeEntry::test("#dev", "1h").dated(date(2025, 1, 3)),
+            TimeEntry::test("#dev", "2h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#ops", "30m").dated(date(2025, 1, 7)),
+        ];
+        let totals = group_totals(
+            &Group::Week,
+            &Sprints::default(),
+            entries.iter().map(|e| (e, String::new())),
+        );
+        assert_eq!(
+            vec![
+                ("2025-W01".to_string(), (parse_duration("1h"), 1)),
+                ("2025-W02".to_string(), (parse_duration("2h30m"), 2)),
+            ],
+            totals.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_group_by_property() {
+        let query: Query = "tag = #dev GROUP BY note.Projects".parse().unwrap();
+        let group = query.group.unwrap();
+        assert_eq!(Group::Property("Projects".to_string()), group);
+        assert_eq!("note.Projects", group.to_string());
+        let with_projects = |projects: &[&str]| TimeEntry {
+            properties: BTreeMap::from([(
+                "Projects".to_string(),
+                Value::List(projects.iter().map(|p| p.to_string()).collect()),
+            )]),
+            ..TimeEntry::test("#dev", "1h")
+        };
+        let entries = [
+            with_projects(&["Website", "Hosting"]),
+            with_projects(&["Website"]),
+            TimeEntry::test("#dev", "30m"),
+        ];
+        let totals = group_totals(
+            &group,
+            &Sprints::default(),
+            entries.iter().map(|e| (e, String::new())),
+        );
+        assert_eq!(
+            vec![
+                (String::new(), (parse_duration("30m"), 1)),
+                ("Hosting".to_string(), (parse_duration("1h"), 1)),
+                ("Website".to_string(), (parse_duration("2h"), 2)),
+            ],
+            totals.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_group_by_field() {
+        let query: Query = "GROUP BY field:client".parse().unwrap();
+        let group = query.group.unwrap();
+        assert_eq!(Group::Field("client".to_string()), group);
+        assert_eq!("field:client", group.to_string());
+        let with = |field: Option<&str>, property: Option<&str>| TimeEntry {
+            fields: field
+                .map(|f| BTreeMap::from([("client".to_string(), f.to_string())]))
+                .unwrap_or_default(),
+            properties: property
+                .map(|p| BTreeMap::from([("client".to_string(), Value::Text(p.to_string()))]))
+                .unwrap_or_default(),
+            ..TimeEntry::test("#dev", "1h")
+        };
+        let entries = [
+            with(Some("Acme"), Some("Globex")),
+            with(None, Some("Globex")),
+            with(None, None),
+        ];
+        let totals = group_totals(
+            &group,
+            &Sprints::default(),
+            entries.iter().map(|e| (e, String::new())),
+        );
+        assert_eq!(
+            vec![
+                (String::new(), (parse_duration("1h"), 1)),
+                ("Acme".to_string(), (parse_duration("1h"), 1)),
+                ("Globex".to_string(), (parse_duration("1h"), 1)),
+            ],
+            totals.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_group_by_hour_of_day() {
+        let started = |time: &str, duration: &str| TimeEntry {
+            started: Some(time.parse().unwrap()),
+            ..TimeEntry::test("#dev", duration)
+        };
+        let entries = [
+            started("09:30", "1h45m"),
+            started("23:30", "1h"),
+            started("10:00", "30m"),
+            TimeEntry::test("#dev", "1h"),
+        ];
+        let totals = group_totals(
+            &Group::HourOfDay,
+            &Sprints::default(),
+            entries.iter().map(|e| (e, String::new())),
+        );
+        assert_eq!(
+            vec![
+                ("".to_string(), (parse_duration("1h"), 1)),
+                ("00:00".to_string(), (parse_duration("30m"), 1)),
+                ("09:00".to_string(), (parse_duration("30m"), 1)),
+                ("10:00".to_string(), (parse_duration("1h30m"), 2)),
+                ("11:00".to_string(), (parse_duration("15m"), 1)),
+                ("23:00".to_string(), (parse_duration("30m"), 1)),
+            ],
+            totals.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_query_errors() {
+        assert!("tag < #dev".parse::<Query>().is_err());
+        assert!("date >= yesterday".parse::<Query>().is_err());
+        assert!("size = 1".parse::<Query>().is_err());
+        assert!("tag = #dev GROUP week".parse::<Query>().is_err());
+        assert!("tag = #dev GROUP BY year".parse::<Query>().is_err());
+        assert!("(tag = #dev".parse::<Query>().is_err());
+        assert!("tag = #dev extra".parse::<Query>().is_err());
+    }
+}
@@ -0,0 +1,148 @@
+// src/reconcile.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::parser::TimeEntry;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What pushing a worklog should do about the remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// The remote has no worklog for the day and tag set.
+    Create,
+    /// The remote's worklog has a different duration.
+    Update,
+    /// Leave the remote as it is.
+    Skip,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Skip => "skip",
+        })
+    }
+}
+
+/// The time on a tag set in a day, as tracked in the notes and as already
+/// logged on the remote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Worklog {
+    pub date: Option<Date>,
+    pub tags: String,
+    pub local: TimeDuration,
+    pub remote: Option<TimeDuration>,
+    pub action: Action,
+}
+
+impl Worklog {
+    /// The worklog as CSV fields: action, date, tags, and the local and
+    /// remote durations in `unit`.
+    pub fn record(&self, unit: DurationUnit) -> [String; 5] {
+        [
+            self.action.to_string(),
+            self.date.map(|d| d.to_string()).unwrap_or_default(),
+            self.tags.clone(),
+            unit.format(&self.local),
+            self.remote
+                .as_ref()
+                .map(|d| unit.format(d))
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl fmt::Display for Worklog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let date = self.date.map(|d| d.to_string()).unwrap_or_default();
+        write!(
+            f,
+            "{} {} {} {}",
+            self.action,
+            date,
+            self.tags,
+            DurationUnit::Compact.format(&self.local)
+        )?;
+        if let Some(remote) = &self.remote {
+            write!(f, " (remote {})", DurationUnit::Compact.format(remote))?;
+        }
+        Ok(())
+    }
+}
+
+fn totals(entries: &[TimeEntry]) -> BTreeMap<(Option<Date>, &str), TimeDuration> {
+    let mut totals: BTreeMap<(Option<Date>, &str), TimeDuration> = BTreeMap::new();
+    for entry in entries {
+        *totals.entry((entry.date, &entry.tags)).or_default() += &entry.duration;
+    }
+    totals
+}
+
+/// Matches the local entries' totals per day and tag set with the remote
+/// worklogs', so a push creates the missing ones and skips those already
+/// logged. Changed worklogs are updated only when `update`. Worklogs only
+/// the remote has are left alone.
+pub fn plan(local: &[TimeEntry], remote: &[TimeEntry], update: bool) -> Vec<Worklog> {
+    let remote = totals(remote);
+    totals(local)
+        .into_iter()
+        .map(|((date, tags), local)| {
+            let remote = remote.get(&(date, tags)).cloned();
+            let action = match &remote {
+                None => Action::Create,
+                Some(remote) if *remote != local && update => Action::Update,
+                Some(_) => Action::Skip,
+            };
+            Worklog {
+                date,
+                tags: tags.to_string(),
+                local,
+                remote,
+                action,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_plan() {
+        let local = [
+            TimeEntry::test("#dev", "1h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#dev", "30m").dated(date(2025, 1, 6)),
+            TimeEntry::test("#ops", "1h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#dev", "2h").dated(date(2025, 1, 7)),
+        ];
+        let remote = [
+            TimeEntry::test("#dev", "1h30m").dated(date(2025, 1, 6)),
+            TimeEntry::test("#ops", "45m").dated(date(2025, 1, 6)),
+            TimeEntry::test("#qa", "1h").dated(date(2025, 1, 6)),
+        ];
+        let records = |update| -> Vec<String> {
+            plan(&local, &remote, update)
+                .iter()
+                .map(|worklog| worklog.record(DurationUnit::Compact).join(","))
+                .collect()
+        };
+        assert_eq!(
+            vec![
+                "skip,2025-01-06,#dev,1h30m,1h30m",
+                "skip,2025-01-06,#ops,1h,45m",
+                "create,2025-01-07,#dev,2h,",
+            ],
+            records(false)
+        );
+        assert_eq!("update,2025-01-06,#ops,1h,45m", records(true)[1]);
+        assert_eq!(
+            "update 2025-01-06 #ops 1h (remote 45m)",
+            plan(&local, &remote, true)[1].to_string()
+        );
+    }
+}
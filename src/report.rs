@@ -0,0 +1,227 @@
+// src/report.rs
+
+use crate::duration::TimeDuration;
+use crate::json;
+use crate::properties::Value;
+use csv::Writer;
+use std::error::Error;
+use std::io::Write;
+
+/// A value in a report record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    /// Text, `null` in JSON when empty.
+    Text(String),
+    /// A number as written, unquoted in JSON.
+    Number(String),
+    /// Strings like tags, comma-joined in CSV and an array in JSON.
+    List(Vec<String>),
+    /// A note property, keeping its type in JSON.
+    Property(Value),
+}
+
+impl Cell {
+    /// The cell as CSV text.
+    pub fn text(&self) -> String {
+        match self {
+            Cell::Text(text) | Cell::Number(text) => text.clone(),
+            Cell::List(items) => items.join(","),
+            Cell::Property(value) => value.to_string(),
+        }
+    }
+
+    /// The cell as a JSON value.
+    pub fn to_json(&self) -> String {
+        match self {
+            Cell::Text(text) if text.is_empty() => "null".to_string(),
+            Cell::Text(text) => json::quote(text),
+            Cell::Number(number) => number.clone(),
+            Cell::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| json::quote(item)).collect();
+                format!("[{}]", items.join(","))
+            }
+            Cell::Property(value) => value.to_json(),
+        }
+    }
+}
+
+/// Totals over the entries a report wrote.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Summary {
+    pub entries: usize,
+    pub duration: TimeDuration,
+}
+
+/// A sink for report records. Each record is a list of (column, cell)
+/// pairs with the same columns in the same order throughout a report.
+/// Implement it to send reports somewhere other than the built-in formats,
+/// like straight into a database.
+pub trait ReportWriter {
+    fn write_entry(&mut self, record: &[(&str, Cell)]) -> Result<(), Box<dyn Error>>;
+
+    /// Receives the totals once every entry is written. The built-in
+    /// writers leave them out, so their output stays a plain list of
+    /// entries.
+    fn write_summary(&mut self, _summary: &Summary) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Completes the report; nothing may be written after.
+    fn finish(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+/// Writes records as CSV rows, optionally after a header row.
+pub struct CsvWriter<W: Write> {
+    writer: Writer<W>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        CsvWriter {
+            writer: Writer::from_writer(writer),
+        }
+    }
+
+    /// Writes `header` right away, so even an empty report has it.
+    pub fn with_header(writer: W, header: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let mut csv_writer = Self::new(writer);
+        csv_writer.writer.write_record(header)?;
+        Ok(csv_writer)
+    }
+}
+
+impl<W: Write> ReportWriter for CsvWriter<W> {
+    fn write_entry(&mut self, record: &[(&str, Cell)]) -> Result<(), Box<dyn Error>> {
+        self.writer
+            .write_record(record.iter().map(|(_, cell)| cell.text()))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes records as a JSON array of objects on one line.
+pub struct JsonWriter<W: Write> {
+    writer: W,
+    written: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        JsonWriter { writer, written: 0 }
+    }
+}
+
+impl<W: Write> ReportWriter for JsonWriter<W> {
+    fn write_entry(&mut self, record: &[(&str, Cell)]) -> Result<(), Box<dyn Error>> {
+        let fields: Vec<String> = record
+            .iter()
+            .map(|(column, cell)| format!("{}:{}", json::quote(column), cell.to_json()))
+            .collect();
+        let separator = if self.written == 0 { "[" } else { "," };
+        write!(self.writer, "{}{{{}}}", separator, fields.join(","))?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.written == 0 {
+            write!(self.writer, "[")?;
+        }
+        writeln!(self.writer, "]")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes each record as a Markdown list item of Dataview inline fields,
+/// like `- [tags:: #dev] [hours:: 1.5]`. Empty cells are left out, and
+/// lists are space-separated.
+pub struct MarkdownWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> MarkdownWriter<W> {
+    pub fn new(writer: W) -> Self {
+        MarkdownWriter { writer }
+    }
+}
+
+impl<W: Write> ReportWriter for MarkdownWriter<W> {
+    fn write_entry(&mut self, record: &[(&str, Cell)]) -> Result<(), Box<dyn Error>> {
+        let fields: Vec<String> = record
+            .iter()
+            .map(|(key, cell)| match cell {
+                Cell::List(items) => (key, items.join(" ")),
+                cell => (key, cell.text()),
+            })
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!("[{}:: {}]", key, value))
+            .collect();
+        writeln!(self.writer, "- {}", fields.join(" "))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(sink: &mut dyn ReportWriter) {
+        let records = [
+            vec![
+                ("tags", Cell::List(vec!["#dev".into(), "#ops".into()])),
+                ("hours", Cell::Number("1.5".into())),
+                ("text", Cell::Text("Fix, then test".into())),
+            ],
+            vec![
+                ("tags", Cell::List(vec![])),
+                ("hours", Cell::Number("0.25".into())),
+                ("text", Cell::Text(String::new())),
+            ],
+        ];
+        for record in &records {
+            sink.write_entry(record).unwrap();
+        }
+        sink.write_summary(&Summary::default()).unwrap();
+        sink.finish().unwrap();
+    }
+
+    #[test]
+    fn test_writers() {
+        let mut output = vec![];
+        write(&mut CsvWriter::with_header(&mut output, &["tags", "hours", "text"]).unwrap());
+        assert_eq!(
+            "tags,hours,text\n\"#dev,#ops\",1.5,\"Fix, then test\"\n,0.25,\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let mut output = vec![];
+        write(&mut JsonWriter::new(&mut output));
+        assert_eq!(
+            r##"[{"tags":["#dev","#ops"],"hours":1.5,"text":"Fix, then test"},{"tags":[],"hours":0.25,"text":null}]"##
+                .to_string()
+                + "\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let mut output = vec![];
+        write(&mut MarkdownWriter::new(&mut output));
+        assert_eq!(
+            "- [tags:: #dev #ops] [hours:: 1.5] [text:: Fix, then test]\n- [hours:: 0.25]\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let mut output = vec![];
+        JsonWriter::new(&mut output).finish().unwrap();
+        assert_eq!("[]\n", String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,74 @@
+// src/retag.rs
+
+use crate::parser::mask_code;
+pub use crate::writeback::Change;
+use regex::Regex;
+
+/// Replaces the tag `from` with `to` wherever it appears as a whole tag, so
+/// `#meetins` doesn't touch `#meetins-old`. Tags in inline code are left
+/// alone. Returns the new content, with line endings kept, and the lines
+/// that changed.
+pub fn retag(content: &str, from: &str, to: &str) -> (String, Vec<Change>) {
+    let re_tag = Regex::new(&format!(
+        r"{}(?:[^\p{{L}}\p{{M}}\p{{N}}_/-]|$)",
+        regex::escape(from)
+    ))
+    .unwrap();
+    let mut output = String::with_capacity(content.len());
+    let mut changes = vec![];
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let masked = mask_code(line);
+        let mut after = String::with_capacity(line.len());
+        let mut last = 0;
+        for m in re_tag.find_iter(&masked) {
+            after.push_str(&line[last..m.start()]);
+            after.push_str(to);
+            last = m.start() + from.len();
+        }
+        after.push_str(&line[last..]);
+        if after != line {
+            changes.push(Change {
+                line: index + 1,
+                before: line.trim_end_matches(['\r', '\n']).to_string(),
+                after: after.trim_end_matches(['\r', '\n']).to_string(),
+            });
+        }
+        output.push_str(&after);
+    }
+    (output, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    #[test]
+    fn test_retag() {
+        let content = dedent!(
+            r#"
+        - [ ] Sync #meetins [timeTracked: 1h]
+        - [ ] Old #meetins-2024 and `#meetins` in code
+        - [ ] Twice #meetins,#meetins
+        "#
+        );
+        let (output, changes) = retag(&format!("{}\r\n", content), "#meetins", "#meetings");
+        assert_eq!(
+            dedent!(
+                r#"
+            - [ ] Sync #meetings [timeTracked: 1h]
+            - [ ] Old #meetins-2024 and `#meetins` in code
+            - [ ] Twice #meetings,#meetings
+            "#
+            )
+            .to_string()
+                + "\r\n",
+            output
+        );
+        assert_eq!(
+            vec![1, 3],
+            changes.iter().map(|c| c.line).collect::<Vec<_>>()
+        );
+        assert_eq!("- [ ] Twice #meetins,#meetins", changes[1].before);
+    }
+}
@@ -0,0 +1,315 @@
+// src/rpc.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::json::{self, Json};
+use crate::parser::TimeEntry;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// A method that couldn't do what was asked, like parse a missing note.
+pub const FAILED: i64 = -32000;
+
+/// A JSON-RPC error response's code and message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn invalid_params(message: impl std::fmt::Display) -> Self {
+        RpcError {
+            code: INVALID_PARAMS,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn failed(message: impl std::fmt::Display) -> Self {
+        RpcError {
+            code: FAILED,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Answers JSON-RPC 2.0 requests, one per line of `reader`, until it ends.
+/// `handle` gets each request's method and params (`null` when left out)
+/// and returns the result as JSON text. Responses are written a line each;
+/// notifications, requests without an `id`, get none.
+pub fn serve(
+    reader: impl BufRead,
+    writer: &mut dyn Write,
+    mut handle: impl FnMut(&str, &Json) -> Result<String, RpcError>,
+) -> Result<(), Box<dyn Error>> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match json::parse(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                respond(
+                    writer,
+                    &Json::Null,
+                    Err(RpcError {
+                        code: PARSE_ERROR,
+                        message: e,
+                    }),
+                )?;
+                continue;
+            }
+        };
+        let id = request.get("id").cloned();
+        let response = match request.get("method").and_then(Json::as_str) {
+            Some(method) => handle(method, request.get("params").unwrap_or(&Json::Null)),
+            None => Err(RpcError {
+                code: INVALID_REQUEST,
+                message: "request without a method".to_string(),
+            }),
+        };
+        if let Some(id) = id {
+            respond(writer, &id, response)?;
+        }
+    }
+    Ok(())
+}
+
+fn respond(
+    writer: &mut dyn Write,
+    id: &Json,
+    response: Result<String, RpcError>,
+) -> Result<(), Box<dyn Error>> {
+    let body = match response {
+        Ok(result) => format!("\"result\":{}", result),
+        Err(error) => format!(
+            "\"error\":{{\"code\":{},\"message\":{}}}",
+            error.code,
+            json::quote(&error.message)
+        ),
+    };
+    writeln!(writer, "{{\"jsonrpc\":\"2.0\",\"id\":{},{}}}", id, body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A note's entries as last parsed, with the modification time they were
+/// parsed at, or `None` for content sent by the editor but not yet saved.
+struct Cached {
+    modified: Option<SystemTime>,
+    entries: Vec<TimeEntry>,
+}
+
+/// Parsed notes kept between requests. Notes are parsed again once they
+/// change on disk, except for unsaved content, which is kept until it is
+/// invalidated.
+#[derive(Default)]
+pub struct Cache {
+    notes: BTreeMap<PathBuf, Cached>,
+}
+
+impl Cache {
+    /// The entries of `path`, parsed with `load` unless the cache has them
+    /// for the note as it is.
+    pub fn entries(
+        &mut self,
+        path: &Path,
+        load: impl FnOnce(&Path) -> Result<Vec<TimeEntry>, Box<dyn Error>>,
+    ) -> Result<&[TimeEntry], Box<dyn Error>> {
+        let key = key(path);
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let fresh = self
+            .notes
+            .get(&key)
+            .is_some_and(|cached| cached.modified.is_none() || cached.modified == modified);
+        if !fresh {
+            let entries = load(path)?;
+            self.notes.insert(key.clone(), Cached { modified, entries });
+        }
+        Ok(&self.notes[&key].entries)
+    }
+
+    /// Keeps `entries`, parsed from unsaved content, as the note's until
+    /// it is invalidated.
+    pub fn set_unsaved(&mut self, path: &Path, entries: Vec<TimeEntry>) {
+        self.notes.insert(
+            key(path),
+            Cached {
+                modified: None,
+                entries,
+            },
+        );
+    }
+
+    /// Drops `path`, or every note for `None`, returning how many were
+    /// dropped.
+    pub fn invalidate(&mut self, path: Option<&Path>) -> usize {
+        match path {
+            Some(path) => usize::from(self.notes.remove(&key(path)).is_some()),
+            None => std::mem::take(&mut self.notes).len(),
+        }
+    }
+}
+
+/// Notes are cached by absolute path, as the editor names them.
+fn key(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// A note's entries for `parseFile`, with their total.
+pub fn entries_json(entries: &[TimeEntry], unit: DurationUnit) -> String {
+    let mut total = TimeDuration::default();
+    let items: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            total += &entry.duration;
+            let tags: Vec<String> = entry
+                .tags
+                .split(',')
+                .filter(|t| !t.is_empty())
+                .map(json::quote)
+                .collect();
+            format!(
+                "{{\"line\":{},\"text\":{},\"tags\":[{}],\"seconds\":{},\"duration\":{},\"date\":{},\"syntax\":{}}}",
+                entry.line,
+                json::quote(&entry.text),
+                tags.join(","),
+                entry.duration.total_seconds(),
+                json::quote(&unit.format(&entry.duration)),
+                entry
+                    .date
+                    .map_or("null".to_string(), |date| json::quote(&date.to_string())),
+                json::quote(&entry.syntax.to_string())
+            )
+        })
+        .collect();
+    format!(
+        "{{\"entries\":[{}],\"seconds\":{},\"duration\":{}}}",
+        items.join(","),
+        total.total_seconds(),
+        json::quote(&unit.format(&total))
+    )
+}
+
+/// The totals for `summary`: notes, entries, and time, overall and per tag,
+/// tags with the most time first.
+pub fn summary_json<'a>(
+    files: usize,
+    entries: impl IntoIterator<Item = &'a TimeEntry>,
+    unit: DurationUnit,
+) -> String {
+    let mut total = TimeDuration::default();
+    let mut count = 0;
+    let mut tags: BTreeMap<&str, (TimeDuration, usize)> = BTreeMap::new();
+    for entry in entries {
+        total += &entry.duration;
+        count += 1;
+        for tag in entry.tags.split(',').filter(|t| !t.is_empty()) {
+            let (duration, entries) = tags.entry(tag).or_default();
+            *duration += &entry.duration;
+            *entries += 1;
+        }
+    }
+    let mut tags: Vec<_> = tags.into_iter().collect();
+    tags.sort_by(|(a, (x, _)), (b, (y, _))| {
+        y.total_seconds().cmp(&x.total_seconds()).then(a.cmp(b))
+    });
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|(tag, (duration, entries))| {
+            format!(
+                "{{\"tag\":{},\"seconds\":{},\"duration\":{},\"entries\":{}}}",
+                json::quote(tag),
+                duration.total_seconds(),
+                json::quote(&unit.format(duration)),
+                entries
+            )
+        })
+        .collect();
+    format!(
+        "{{\"files\":{},\"entries\":{},\"seconds\":{},\"duration\":{},\"tags\":[{}]}}",
+        files,
+        count,
+        total.total_seconds(),
+        json::quote(&unit.format(&total)),
+        tags.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+
+    #[test]
+    fn test_serve() {
+        let requests = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"echo","params":{"text":"hi"}}"#,
+            r#"{"jsonrpc":"2.0","method":"echo","params":{"text":"unheard"}}"#,
+            r#"{"jsonrpc":"2.0","id":"b","method":"nope"}"#,
+            "{oops",
+        ]
+        .join("\n");
+        let mut output = vec![];
+        serve(
+            requests.as_bytes(),
+            &mut output,
+            |method, params| match method {
+                "echo" => Ok(params.get("text").unwrap().to_string()),
+                _ => Err(RpcError {
+                    code: METHOD_NOT_FOUND,
+                    message: format!("no method {}", method),
+                }),
+            },
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(3, lines.len());
+        assert_eq!(r#"{"jsonrpc":"2.0","id":1,"result":"hi"}"#, lines[0]);
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","id":"b","error":{"code":-32601,"message":"no method nope"}}"#,
+            lines[1]
+        );
+        assert!(lines[2].starts_with(r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"#));
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.md");
+        std::fs::write(&path, "").unwrap();
+        let entry = |duration: &str| TimeEntry {
+            tags: "#dev".to_string(),
+            duration: parse_duration(duration),
+            ..Default::default()
+        };
+        let mut cache = Cache::default();
+        let mut loads = 0;
+        for _ in 0..2 {
+            cache
+                .entries(&path, |_| {
+                    loads += 1;
+                    Ok(vec![entry("1h")])
+                })
+                .unwrap();
+        }
+        assert_eq!(1, loads);
+
+        cache.set_unsaved(&path, vec![entry("2h"), entry("30m")]);
+        let entries = cache.entries(&path, |_| unreachable!()).unwrap();
+        assert_eq!(
+            r##"{"files":1,"entries":2,"seconds":9000,"duration":"2h30m","tags":[{"tag":"#dev","seconds":9000,"duration":"2h30m","entries":2}]}"##,
+            summary_json(1, entries, DurationUnit::Compact)
+        );
+        assert_eq!(1, cache.invalidate(Some(&path)));
+        assert_eq!(0, cache.invalidate(None));
+    }
+}
@@ -0,0 +1,131 @@
+// src/runs.rs
+
+use crate::date::is_weekend;
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::identifier::Identifiers;
+use crate::parser::TimeEntry;
+use jiff::ToSpan;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+
+/// Consecutive working days with time on one identifier tag. Weekends
+/// don't break a run; a weekday without time on it does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub identifier: String,
+    pub start: Date,
+    pub end: Date,
+    /// Days in the run with time on the identifier.
+    pub days: usize,
+    pub total: TimeDuration,
+    /// Working days without time on it since its previous run ended, `None`
+    /// for its first run.
+    pub gap: Option<usize>,
+}
+
+impl Run {
+    /// The run as CSV fields: identifier, start, end, days, total, and gap.
+    pub fn record(&self, unit: DurationUnit) -> [String; 6] {
+        [
+            self.identifier.clone(),
+            self.start.to_string(),
+            self.end.to_string(),
+            self.days.to_string(),
+            unit.format(&self.total),
+            self.gap.map(|gap| gap.to_string()).unwrap_or_default(),
+        ]
+    }
+}
+
+/// Stitches dated entries into runs per identifier tag, ordered by
+/// identifier and then start. An entry counts fully toward each identifier
+/// it carries.
+pub fn runs(entries: &[TimeEntry], identifiers: &Identifiers) -> Vec<Run> {
+    let mut days: BTreeMap<String, BTreeMap<Date, TimeDuration>> = BTreeMap::new();
+    for entry in entries {
+        let Some(date) = entry.date else {
+            continue;
+        };
+        for identifier in identifiers.find(&entry.tags).split(',') {
+            if !identifier.is_empty() {
+                *days
+                    .entry(identifier.to_string())
+                    .or_default()
+                    .entry(date)
+                    .or_default() += &entry.duration;
+            }
+        }
+    }
+
+    let mut runs = vec![];
+    for (identifier, days) in days {
+        let mut current: Option<Run> = None;
+        for (date, duration) in days {
+            let gap = current
+                .as_ref()
+                .map(|run| working_days_between(run.end, date));
+            match (&mut current, gap) {
+                (Some(run), Some(0)) => {
+                    run.end = date;
+                    run.days += 1;
+                    run.total += &duration;
+                }
+                _ => {
+                    runs.extend(current.take());
+                    current = Some(Run {
+                        identifier: identifier.clone(),
+                        start: date,
+                        end: date,
+                        days: 1,
+                        total: duration,
+                        gap,
+                    });
+                }
+            }
+        }
+        runs.extend(current);
+    }
+    runs
+}
+
+/// The weekdays strictly between `from` and `to`.
+fn working_days_between(from: Date, to: Date) -> usize {
+    from.series(1.day())
+        .skip(1)
+        .take_while(|date| *date < to)
+        .filter(|date| !is_weekend(*date))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_runs() {
+        // Thursday to Monday is one run across the weekend; Wednesday
+        // starts another after the idle Tuesday.
+        let entries = [
+            TimeEntry::test("#pbi-7", "1h").dated(date(2025, 1, 2)),
+            TimeEntry::test("#pbi-7,#dev", "2h").dated(date(2025, 1, 3)),
+            TimeEntry::test("#pbi-7", "30m").dated(date(2025, 1, 6)),
+            TimeEntry::test("#pbi-8", "1h").dated(date(2025, 1, 7)),
+            TimeEntry::test("#pbi-7", "1h").dated(date(2025, 1, 8)),
+            TimeEntry::test("#dev", "4h").dated(date(2025, 1, 9)),
+        ];
+        let runs = runs(&entries, &Identifiers::default());
+        let records: Vec<[String; 6]> = runs
+            .iter()
+            .map(|run| run.record(DurationUnit::Compact))
+            .collect();
+        assert_eq!(
+            vec![
+                ["#pbi-7", "2025-01-02", "2025-01-06", "3", "3h30m", ""],
+                ["#pbi-7", "2025-01-08", "2025-01-08", "1", "1h", "1"],
+                ["#pbi-8", "2025-01-07", "2025-01-07", "1", "1h", ""],
+            ],
+            records
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// src/scaffold.rs
+
+use crate::parser::lines;
+use jiff::civil::Date;
+
+/// A new daily note for `date`: `template`, with `{{date}}` and `{{title}}`
+/// replaced by the date as the note is named, then a `Work on [[N]]`
+/// section with an empty task for each of `pbis` the template doesn't
+/// already have. Sections end lines like the template does.
+pub fn daily_note(template: &str, date: Date, pbis: &[u64]) -> String {
+    let day = date.to_string();
+    let newline = if template.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut note = template
+        .replace("{{date}}", &day)
+        .replace("{{title}}", &day);
+    for pbi in pbis {
+        let heading = format!("Work on [[{}]]", pbi);
+        if lines(&note).any(|line| line.trim_start_matches('#').trim() == heading) {
+            continue;
+        }
+        if !note.is_empty() {
+            if !note.ends_with('\n') {
+                note.push_str(newline);
+            }
+            if !note.ends_with("\n\n") && !note.ends_with("\n\r\n") {
+                note.push_str(newline);
+            }
+        }
+        note.push_str(&format!("## {}{1}- [ ] {1}", heading, newline));
+    }
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_daily_note() {
+        assert_eq!(
+            "## Work on [[1234]]\n- [ ] \n\n## Work on [[5678]]\n- [ ] \n",
+            daily_note("", date(2025, 1, 6), &[1234, 5678])
+        );
+        assert_eq!(
+            "# 2025-01-06\n## Work on [[7]]\n- [ ] \n\n## Work on [[8]]\n- [ ] \n",
+            daily_note(
+                "# {{date}}\n## Work on [[7]]\n- [ ] \n",
+                date(2025, 1, 6),
+                &[7, 8]
+            )
+        );
+        assert_eq!(
+            "# 2025-01-06\r\n\r\n## Work on [[7]]\r\n- [ ] \r\n",
+            daily_note("# {{date}}\r\n", date(2025, 1, 6), &[7])
+        );
+    }
+}
@@ -0,0 +1,502 @@
+// src/scan.rs
+
+use jwalk::WalkDirGeneric;
+use memmap2::Mmap;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+use zip::read::ZipFile;
+
+thread_local! {
+    /// The archive read last, kept open so reading each of its notes doesn't
+    /// parse the central directory again.
+    static ARCHIVE: RefCell<Option<(PathBuf, ZipArchive<fs::File>)>> = const { RefCell::new(None) };
+}
+
+/// Whether `path` is a zip archive that can be scanned like a directory.
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && path.extension() == Some(OsStr::new("zip"))
+}
+
+/// Adds the Markdown files in `dir` to `entries`, reading subdirectories in
+/// parallel when `recursive`. The result is sorted, so output order does not
+/// depend on the walk. Links to notes and directories are followed, and
+/// named by the link's path. A zip archive is scanned like a directory, with its
+/// notes listed as paths below the archive's, like `backup.zip/2025-01-01.md`.
+pub fn collect_entries(
+    dir: &Path,
+    recursive: bool,
+    entries: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    if is_archive(dir) {
+        let archive = ZipArchive::new(fs::File::open(dir)?)?;
+        for name in archive.file_names() {
+            let name = name?;
+            if name.ends_with(".md") && (recursive || !name.contains('/')) {
+                entries.push(dir.join(name.as_ref()));
+            }
+        }
+        entries.sort();
+        return Ok(());
+    }
+    // Each directory's state is the real paths of those above it, so a link
+    // back to one of them isn't followed around again.
+    let walker = WalkDirGeneric::<(Vec<PathBuf>, ())>::new(dir)
+        .skip_hidden(false)
+        .follow_links(true)
+        .max_depth(if recursive { usize::MAX } else { 1 })
+        .process_read_dir(|depth, path, ancestors, children| {
+            if depth.is_none() {
+                return;
+            }
+            ancestors.extend(fs::canonicalize(path));
+            for child in children.iter_mut().flatten() {
+                if child.read_children.is_some()
+                    && fs::canonicalize(child.path()).is_ok_and(|real| ancestors.contains(&real))
+                {
+                    child.read_children = None;
+                }
+            }
+        });
+    for entry in walker {
+        let path = match entry {
+            Err(e) if e.loop_ancestor().is_some() => continue,
+            // A link to nothing.
+            Err(e)
+                if e.io_error()
+                    .is_some_and(|e| e.kind() == ErrorKind::NotFound) =>
+            {
+                continue;
+            }
+            entry => entry?.path(),
+        };
+        if path.is_file() && path.extension() == Some(OsStr::new("md")) {
+            entries.push(path);
+        }
+    }
+    entries.sort();
+    Ok(())
+}
+
+/// Notes at least this large are memory-mapped rather than read, which
+/// costs more than it saves for small files.
+const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// A note's raw content, memory-mapped when large, so notes can be matched
+/// as bytes before any of them is checked as UTF-8.
+pub enum Note {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Note {
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Note::Mapped(map) => map,
+            Note::Read(bytes) => bytes,
+        }
+    }
+
+    /// The content as text, without copying it.
+    pub fn text(&self, path: &Path) -> Result<&str, Box<dyn Error>> {
+        std::str::from_utf8(self.bytes())
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e).into())
+    }
+
+    pub fn into_string(self, path: &Path) -> Result<String, Box<dyn Error>> {
+        match self {
+            Note::Read(bytes) => String::from_utf8(bytes)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e).into()),
+            note => Ok(note.text(path)?.to_string()),
+        }
+    }
+}
+
+/// Opens a note found by `collect_entries`, from inside its archive if it
+/// has no file of its own. Large notes are mapped only when `map`, since a
+/// note truncated while mapped faults the process reading it, which a
+/// long-running one can't afford.
+pub fn open_note(path: &Path, map: bool) -> Result<Note, Box<dyn Error>> {
+    let read = |path: &Path| -> Result<Note, Box<dyn Error>> {
+        let failed = |e: std::io::Error| format!("failed to read {}: {}", path.display(), e);
+        let file = fs::File::open(path).map_err(failed)?;
+        if map && file.metadata().map_err(failed)?.len() >= MMAP_THRESHOLD {
+            // SAFETY: notes aren't expected to change during a scan; if one
+            // is truncated meanwhile, reading it may fault, as with any
+            // mapped file.
+            let map = unsafe { Mmap::map(&file) }.map_err(failed)?;
+            return Ok(Note::Mapped(map));
+        }
+        let mut bytes = vec![];
+        (&file).read_to_end(&mut bytes).map_err(failed)?;
+        Ok(Note::Read(bytes))
+    };
+    if path.is_file() {
+        return read(path);
+    }
+    let archived = archived(path, |mut file| {
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        Ok(Note::Read(content))
+    });
+    archived.unwrap_or_else(|| read(path))
+}
+
+/// Calls `read` with the note at `path` inside its archive, or returns
+/// `None` if no archive holds it.
+fn archived<T>(
+    path: &Path,
+    read: impl FnOnce(ZipFile<'_, fs::File>) -> Result<T, Box<dyn Error>>,
+) -> Option<Result<T, Box<dyn Error>>> {
+    let archive_path = path.ancestors().skip(1).find(|p| is_archive(p))?;
+    let name = path
+        .strip_prefix(archive_path)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(ARCHIVE.with_borrow_mut(|cached| {
+        if cached.as_ref().is_none_or(|(p, _)| p != archive_path) {
+            let archive = ZipArchive::new(fs::File::open(archive_path)?)?;
+            *cached = Some((archive_path.to_path_buf(), archive));
+        }
+        let (_, archive) = cached.as_mut().unwrap();
+        read(archive.by_name(&name)?)
+    }))
+}
+
+/// Notes larger than this are skipped unless `--max-file-size` or
+/// `max_file_size` says otherwise.
+pub const MAX_FILE_SIZE: u64 = 32 << 20;
+
+/// How much of a note is searched for NUL bytes, which text never has.
+const SNIFF_LENGTH: usize = 8 << 10;
+
+/// Why a note found by `collect_entries` isn't worth reading, if it isn't:
+/// it is larger than `max_size` bytes, or looks binary, like a PDF renamed
+/// `.md`. Notes that can't be opened are left for reading to report.
+pub fn skip_reason(path: &Path, max_size: u64) -> Option<String> {
+    let (size, head) = match fs::File::open(path) {
+        Ok(file) => {
+            let size = file.metadata().ok()?.len();
+            let mut head = vec![];
+            if size <= max_size {
+                file.take(SNIFF_LENGTH as u64).read_to_end(&mut head).ok()?;
+            }
+            (size, head)
+        }
+        // A note inside an archive, sized by its entry and unpacked only as
+        // far as is sniffed.
+        Err(_) => archived(path, |file| {
+            let size = file.size();
+            let mut head = vec![];
+            if size <= max_size {
+                file.take(SNIFF_LENGTH as u64).read_to_end(&mut head)?;
+            }
+            Ok((size, head))
+        })?
+        .ok()?,
+    };
+    if size > max_size {
+        Some(format!("{} bytes, over the {} byte limit", size, max_size))
+    } else if head.contains(&0) {
+        Some("binary content".to_string())
+    } else {
+        None
+    }
+}
+
+/// Reads a size like `512K`, `10MB`, or `1GiB` as bytes. Units are powers
+/// of 1024; a bare number is bytes.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let invalid = || {
+        format!(
+            "invalid size '{}'; expected a number of bytes or like 10M",
+            s
+        )
+    };
+    let s = s.trim();
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let number: u64 = s[..digits].parse().map_err(|_| invalid())?;
+    let shift = match s[digits..].trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(1 << shift).ok_or_else(invalid)
+}
+
+/// Reads a note found by `collect_entries` as text.
+pub fn read_note(path: &Path) -> Result<String, Box<dyn Error>> {
+    open_note(path, false)?.into_string(path)
+}
+
+pub fn read_file_list(source: &str, entries: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let reader: Box<dyn BufRead> = if source == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(fs::File::open(source).map_err(|e| {
+            format!("failed to read file list {}: {}", source, e)
+        })?))
+    };
+    for line in reader.lines() {
+        let line = line?;
+        // Only the line ending is dropped, so names may start or end with
+        // spaces.
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(PathBuf::from(line));
+    }
+    Ok(())
+}
+
+/// `path` with symlinks and `.`/`..` resolved, so two paths to one note
+/// compare equal. A note in an archive is resolved through the archive's
+/// path.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            canonical(parent).join(name)
+        }
+        _ => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+/// Drops notes already listed under another path, like a note found by both
+/// `-d Vault -r` and `-d Vault/Journals`, keeping the first. Returns how many
+/// were dropped.
+pub fn dedup_entries(entries: &mut Vec<PathBuf>) -> usize {
+    let before = entries.len();
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert(canonical(entry)));
+    before - entries.len()
+}
+
+/// The directories that are scanned again as part of another, each with the
+/// one containing it: the same directory given twice, or, when `recursive`,
+/// one inside another.
+pub fn overlapping_dirs(dirs: &[PathBuf], recursive: bool) -> Vec<(&Path, &Path)> {
+    let resolved: Vec<PathBuf> = dirs.iter().map(|dir| canonical(dir)).collect();
+    let mut overlaps = vec![];
+    for (i, inner) in resolved.iter().enumerate() {
+        let outer = resolved.iter().enumerate().find(|&(j, outer)| {
+            j != i
+                && match inner == outer {
+                    true => j < i,
+                    false => recursive && inner.starts_with(outer),
+                }
+        });
+        if let Some((j, _)) = outer {
+            overlaps.push((dirs[i].as_path(), dirs[j].as_path()));
+        }
+    }
+    overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_entries_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("2025");
+        fs::create_dir(&nested).unwrap();
+        for path in [
+            dir.path().join("b.md"),
+            dir.path().join("a.md"),
+            dir.path().join("notes.txt"),
+            nested.join("c.md"),
+        ] {
+            fs::write(path, "").unwrap();
+        }
+
+        let mut entries = vec![];
+        collect_entries(dir.path(), false, &mut entries).unwrap();
+        assert_eq!(
+            vec![dir.path().join("a.md"), dir.path().join("b.md")],
+            entries
+        );
+
+        let mut entries = vec![];
+        collect_entries(dir.path(), true, &mut entries).unwrap();
+        assert_eq!(
+            vec![
+                nested.join("c.md"),
+                dir.path().join("a.md"),
+                dir.path().join("b.md")
+            ],
+            entries
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_entries_follows_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        let shared = dir.path().join("shared");
+        fs::create_dir(&vault).unwrap();
+        fs::create_dir(&shared).unwrap();
+        fs::write(shared.join("a.md"), "").unwrap();
+        std::os::unix::fs::symlink("../shared", vault.join("linked")).unwrap();
+        std::os::unix::fs::symlink(".", shared.join("loop")).unwrap();
+        std::os::unix::fs::symlink("../vault", shared.join("back")).unwrap();
+        std::os::unix::fs::symlink("missing.md", shared.join("dangling.md")).unwrap();
+
+        let mut entries = vec![];
+        collect_entries(&vault, true, &mut entries).unwrap();
+        assert_eq!(vec![vault.join("linked").join("a.md")], entries);
+    }
+
+    #[test]
+    fn test_dedup_overlapping_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let journals = dir.path().join("Journals");
+        fs::create_dir(&journals).unwrap();
+        fs::write(journals.join("a.md"), "").unwrap();
+        fs::write(dir.path().join("b.md"), "").unwrap();
+
+        let dirs = vec![
+            dir.path().to_path_buf(),
+            journals.clone(),
+            journals.join("..").join("Journals"),
+        ];
+        assert_eq!(
+            vec![
+                (dirs[1].as_path(), dirs[0].as_path()),
+                (dirs[2].as_path(), dirs[0].as_path()),
+            ],
+            overlapping_dirs(&dirs, true)
+        );
+        assert_eq!(
+            vec![(dirs[2].as_path(), dirs[1].as_path())],
+            overlapping_dirs(&dirs, false)
+        );
+
+        let mut entries = vec![
+            journals.join("a.md"),
+            dir.path().join("b.md"),
+            dirs[2].join("a.md"),
+            journals.join("a.md"),
+        ];
+        assert_eq!(2, dedup_entries(&mut entries));
+        assert_eq!(
+            vec![journals.join("a.md"), dir.path().join("b.md")],
+            entries
+        );
+    }
+
+    #[test]
+    fn test_skip_reason() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("note.md");
+        fs::write(&note, "- [ ] Task [timeTracked: 1h]").unwrap();
+        let pdf = dir.path().join("export.md");
+        fs::write(&pdf, b"%PDF-1.7\n\x00\x01\x02").unwrap();
+
+        assert_eq!(None, skip_reason(&note, MAX_FILE_SIZE));
+        assert_eq!(
+            Some("binary content".to_string()),
+            skip_reason(&pdf, MAX_FILE_SIZE)
+        );
+        assert_eq!(
+            Some("28 bytes, over the 16 byte limit".to_string()),
+            skip_reason(&note, 16)
+        );
+        assert_eq!(None, skip_reason(&dir.path().join("missing.md"), 16));
+
+        assert_eq!(Ok(512), parse_size("512"));
+        assert_eq!(Ok(10 << 20), parse_size("10MB"));
+        assert_eq!(Ok(1 << 30), parse_size("1 GiB"));
+        assert!(parse_size("10 parsecs").is_err());
+    }
+
+    #[test]
+    fn test_read_file_list_keeps_odd_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("list.txt");
+        fs::write(
+            &list,
+            "-2025-01-01.md\r\n  \n notes/ leading space.md\nC:\\Vault\\#ops, [draft].md\n",
+        )
+        .unwrap();
+        let mut entries = vec![];
+        read_file_list(list.to_str().unwrap(), &mut entries).unwrap();
+        assert_eq!(
+            vec![
+                PathBuf::from("-2025-01-01.md"),
+                PathBuf::from(" notes/ leading space.md"),
+                PathBuf::from("C:\\Vault\\#ops, [draft].md"),
+            ],
+            entries
+        );
+        let error = read_note(&dir.path().join("-missing.md")).unwrap_err();
+        assert!(error.to_string().starts_with("failed to read "));
+    }
+
+    #[test]
+    fn test_open_note_maps_large_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = dir.path().join("small.md");
+        fs::write(&small, "- [ ] Task [timeTracked: 1h]").unwrap();
+        let note = open_note(&small, true).unwrap();
+        assert!(matches!(note, Note::Read(_)));
+        assert_eq!("- [ ] Task [timeTracked: 1h]", note.text(&small).unwrap());
+
+        let large = dir.path().join("large.md");
+        let content = "- [ ] Task [timeTracked: 1h]\n".repeat(40_000);
+        fs::write(&large, &content).unwrap();
+        let note = open_note(&large, true).unwrap();
+        assert!(matches!(note, Note::Mapped(_)));
+        assert_eq!(content, note.text(&large).unwrap());
+        let note = open_note(&large, false).unwrap();
+        assert!(matches!(note, Note::Read(_)));
+        assert_eq!(content, note.text(&large).unwrap());
+
+        fs::write(&small, b"\xff\xfe").unwrap();
+        assert!(read_note(&small).is_err());
+    }
+
+    #[test]
+    fn test_collect_entries_from_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, content) in [("a.md", "a"), ("notes.txt", ""), ("2025/b.md", "b")] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut writer, content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut entries = vec![];
+        collect_entries(&path, false, &mut entries).unwrap();
+        assert_eq!(vec![path.join("a.md")], entries);
+
+        let mut entries = vec![];
+        collect_entries(&path, true, &mut entries).unwrap();
+        assert_eq!(vec![path.join("2025/b.md"), path.join("a.md")], entries);
+        assert_eq!("b", read_note(&entries[0]).unwrap());
+        assert_eq!("a", read_note(&entries[1]).unwrap());
+        assert_eq!(None, skip_reason(&entries[0], 1));
+        assert_eq!(
+            Some("1 bytes, over the 0 byte limit".to_string()),
+            skip_reason(&entries[0], 0)
+        );
+    }
+}
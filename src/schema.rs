@@ -0,0 +1,70 @@
+// src/schema.rs
+
+use crate::json;
+
+/// The JSON type of a field in a report's records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Text,
+    /// Text written as `null` when empty.
+    OptionalText,
+    Integer,
+    Number,
+    /// An array of strings, like tags or file paths.
+    List,
+    /// Any JSON value, like a note property.
+    Any,
+}
+
+impl Field {
+    fn schema(self) -> &'static str {
+        match self {
+            Field::Text => r#"{"type":"string"}"#,
+            Field::OptionalText => r#"{"type":["string","null"]}"#,
+            Field::Integer => r#"{"type":"integer","minimum":0}"#,
+            Field::Number => r#"{"type":"number","minimum":0}"#,
+            Field::List => r#"{"type":"array","items":{"type":"string"}}"#,
+            Field::Any => "{}",
+        }
+    }
+}
+
+/// A JSON Schema (draft 2020-12) for an array of records that each have
+/// exactly `fields`.
+pub fn records(title: &str, fields: &[(&str, Field)]) -> String {
+    let properties: Vec<String> = fields
+        .iter()
+        .map(|(name, field)| format!("{}:{}", json::quote(name), field.schema()))
+        .collect();
+    let required: Vec<String> = fields.iter().map(|(name, _)| json::quote(name)).collect();
+    format!(
+        concat!(
+            r#"{{"$schema":"https://json-schema.org/draft/2020-12/schema","title":{},"#,
+            r#""type":"array","items":{{"type":"object","properties":{{{}}},"#,
+            r#""required":[{}],"additionalProperties":false}}}}"#
+        ),
+        json::quote(title),
+        properties.join(","),
+        required.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        assert_eq!(
+            concat!(
+                r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","title":"entries","#,
+                r#""type":"array","items":{"type":"object","properties":{"tags":{"type":"array","items":{"type":"string"}},"#,
+                r#""line":{"type":"integer","minimum":0}},"required":["tags","line"],"additionalProperties":false}}"#
+            ),
+            records(
+                "entries",
+                &[("tags", Field::List), ("line", Field::Integer)]
+            )
+        );
+    }
+}
@@ -0,0 +1,130 @@
+// src/script.rs
+
+use crate::duration::TimeDuration;
+use crate::parser::TimeEntry;
+use rhai::{AST, Array, Dynamic, Engine, Map, Scope};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// A Rhai script whose `process(entry)` function is called for every parsed
+/// entry before output. The entry is a map with `tags` (array), `seconds`,
+/// `line`, `priority`, and `file`; the function returns the (possibly
+/// modified) map, or `()` to drop the entry.
+pub struct RowHook {
+    engine: Engine,
+    ast: AST,
+}
+
+impl RowHook {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let engine = Engine::new();
+        let ast = engine
+            .compile_file(PathBuf::from(path))
+            .map_err(|e| format!("cannot load script {}: {}", path.display(), e))?;
+        Ok(RowHook { engine, ast })
+    }
+
+    pub fn apply(
+        &self,
+        entry: TimeEntry,
+        file: &Path,
+    ) -> Result<Option<TimeEntry>, Box<dyn Error>> {
+        let mut map = Map::new();
+        let tags: Array = entry
+            .tags
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(|t| Dynamic::from(t.to_string()))
+            .collect();
+        map.insert("tags".into(), tags.into());
+        map.insert(
+            "seconds".into(),
+            (entry.duration.total_seconds() as i64).into(),
+        );
+        map.insert("line".into(), (entry.line as i64).into());
+        map.insert(
+            "priority".into(),
+            entry
+                .priority
+                .map_or(Dynamic::UNIT, |p| p.to_string().into()),
+        );
+        map.insert("file".into(), file.to_string_lossy().into_owned().into());
+
+        let result: Dynamic =
+            self.engine
+                .call_fn(&mut Scope::new(), &self.ast, "process", (map,))?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        let map = result
+            .try_cast::<Map>()
+            .ok_or("process() must return the entry map or ()")?;
+
+        let mut tags: Vec<String> = match map.get("tags") {
+            Some(tags) => tags
+                .clone()
+                .into_typed_array::<String>()
+                .map_err(|e| format!("tags must be an array of strings: {}", e))?,
+            None => vec![],
+        };
+        tags.sort();
+        tags.dedup();
+        let seconds = match map.get("seconds") {
+            Some(seconds) => seconds
+                .as_int()
+                .map_err(|e| format!("seconds must be an integer: {}", e))?,
+            None => 0,
+        };
+        Ok(Some(TimeEntry {
+            tags: tags.join(","),
+            duration: TimeDuration::from_seconds(seconds.max(0) as u64),
+            ..entry
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn hook(script: &str) -> RowHook {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hook.rhai");
+        fs::write(&path, script).unwrap();
+        RowHook::load(&path).unwrap()
+    }
+
+    #[test]
+    fn test_hook_modifies_entry() {
+        let hook = hook(
+            r##"
+            fn process(entry) {
+                entry.tags.push("#billable");
+                entry.seconds *= 2;
+                entry
+            }
+            "##,
+        );
+        let result = hook
+            .apply(TimeEntry::test("#acme", "1h"), Path::new("a.md"))
+            .unwrap();
+        assert_eq!(Some(TimeEntry::test("#acme,#billable", "2h")), result);
+    }
+
+    #[test]
+    fn test_hook_drops_entry() {
+        let hook = hook(
+            r##"
+            fn process(entry) {
+                if "#private" in entry.tags { return; }
+                entry
+            }
+            "##,
+        );
+        let result = hook
+            .apply(TimeEntry::test("#private", "1h"), Path::new("a.md"))
+            .unwrap();
+        assert_eq!(None, result);
+    }
+}
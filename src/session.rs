@@ -0,0 +1,83 @@
+// src/session.rs
+
+use crate::duration::TimeDuration;
+use jiff::civil::{Date, DateTime, Time};
+use std::str::FromStr;
+
+/// When to close sessions that only have a `timeStarted` marker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloseAt {
+    /// The current time, capped at the end of the note's day.
+    Now,
+    /// A fixed time of day on the note's date.
+    Time(Time),
+}
+
+impl FromStr for CloseAt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("now") {
+            return Ok(CloseAt::Now);
+        }
+        s.parse::<Time>()
+            .map(CloseAt::Time)
+            .map_err(|_| format!("expected 'now' or HH:MM, got '{}'", s))
+    }
+}
+
+/// Elapsed time of a session started at `started` on `date`. Returns `None`
+/// when the closing time is not after the start.
+pub fn close_session(
+    date: Date,
+    started: Time,
+    close_at: CloseAt,
+    now: DateTime,
+) -> Option<TimeDuration> {
+    let start = date.to_datetime(started);
+    let end = match close_at {
+        CloseAt::Time(time) => date.to_datetime(time),
+        CloseAt::Now => now.min(date.tomorrow().ok()?.to_datetime(Time::midnight())),
+    };
+    let seconds = end.duration_since(start).as_secs();
+    if seconds <= 0 {
+        return None;
+    }
+    Some(TimeDuration::from_seconds(seconds as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::{date, time};
+
+    #[test]
+    fn test_close_session_now_same_day() {
+        let now = date(2025, 1, 3).at(11, 45, 0, 0);
+        let duration = close_session(date(2025, 1, 3), time(9, 15, 0, 0), CloseAt::Now, now);
+        assert_eq!(Some(parse_duration("2h30m")), duration);
+    }
+
+    #[test]
+    fn test_close_session_now_caps_at_end_of_day() {
+        let now = date(2025, 1, 5).at(11, 0, 0, 0);
+        let duration = close_session(date(2025, 1, 3), time(22, 0, 0, 0), CloseAt::Now, now);
+        assert_eq!(Some(parse_duration("2h")), duration);
+    }
+
+    #[test]
+    fn test_close_session_at_time() {
+        let close_at: CloseAt = "17:00".parse().unwrap();
+        let now = date(2025, 1, 5).at(0, 0, 0, 0);
+        let duration = close_session(date(2025, 1, 3), time(16, 30, 0, 0), close_at, now);
+        assert_eq!(Some(parse_duration("30m")), duration);
+    }
+
+    #[test]
+    fn test_close_session_before_start() {
+        let now = date(2025, 1, 3).at(8, 0, 0, 0);
+        let duration = close_session(date(2025, 1, 3), time(9, 15, 0, 0), CloseAt::Now, now);
+        assert_eq!(None, duration);
+    }
+}
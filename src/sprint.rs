@@ -0,0 +1,192 @@
+// src/sprint.rs
+
+use crate::config::SprintRange;
+use jiff::ToSpan;
+use jiff::civil::Date;
+use regex::Regex;
+
+/// A named stretch of days, both ends included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprint {
+    pub name: String,
+    pub start: Date,
+    pub end: Date,
+}
+
+/// A team's sprints: explicit `[[sprint]]` ranges, and a cadence of sprints
+/// of a fixed length from a start date for days outside them. Cadence
+/// sprints are named by the year they start in and their number within it,
+/// like `2025.03`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sprints {
+    /// The first day of a sprint and the sprints' length in days.
+    cadence: Option<(Date, i64)>,
+    ranges: Vec<Sprint>,
+}
+
+impl Sprints {
+    /// Builds the sprints from the config's `sprint_start` and
+    /// `sprint_length`, like `2025-01-06` and `2w`, and `[[sprint]]` tables.
+    pub fn new(
+        start: Option<&str>,
+        length: Option<&str>,
+        ranges: &[SprintRange],
+    ) -> Result<Self, String> {
+        let date = |field: &str, value: &str| {
+            value
+                .parse::<Date>()
+                .map_err(|_| format!("invalid {} '{}'; expected YYYY-MM-DD", field, value))
+        };
+        let cadence = match (start, length) {
+            (Some(start), length) => Some((
+                date("sprint_start", start)?,
+                parse_length(length.unwrap_or("2w"))?,
+            )),
+            (None, Some(_)) => return Err("sprint_length requires sprint_start".to_string()),
+            (None, None) => None,
+        };
+        let mut sprints = vec![];
+        for range in ranges {
+            if range.name.is_empty() {
+                return Err("sprint without a name".to_string());
+            }
+            let sprint = Sprint {
+                name: range.name.clone(),
+                start: date("sprint start", &range.start)?,
+                end: date("sprint end", &range.end)?,
+            };
+            if sprint.end < sprint.start {
+                return Err(format!("sprint '{}' ends before it starts", sprint.name));
+            }
+            sprints.push(sprint);
+        }
+        Ok(Sprints {
+            cadence,
+            ranges: sprints,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cadence.is_none() && self.ranges.is_empty()
+    }
+
+    /// The sprint `date` falls in: the first range holding it, else the
+    /// cadence's.
+    pub fn sprint(&self, date: Date) -> Option<Sprint> {
+        if let Some(sprint) = self
+            .ranges
+            .iter()
+            .find(|sprint| sprint.start <= date && date <= sprint.end)
+        {
+            return Some(sprint.clone());
+        }
+        let (anchor, length) = self.cadence?;
+        let index = (date - anchor).get_days() as i64;
+        let start = self.start(index.div_euclid(length))?;
+        let first = self.first_of_year(start.year())?;
+        Some(Sprint {
+            name: format!(
+                "{:04}.{:02}",
+                start.year(),
+                (start - first).get_days() as i64 / length + 1
+            ),
+            start,
+            end: start.checked_add((length - 1).days()).ok()?,
+        })
+    }
+
+    /// The sprint called `name`: a range's name, or a cadence sprint's like
+    /// `2025.03`.
+    pub fn find(&self, name: &str) -> Result<Sprint, String> {
+        if let Some(sprint) = self.ranges.iter().find(|sprint| sprint.name == name) {
+            return Ok(sprint.clone());
+        }
+        let unknown = || format!("unknown sprint '{}'", name);
+        let (_, length) = self.cadence.ok_or_else(unknown)?;
+        let re = Regex::new(r"^(\d{4})\.(\d{1,3})$").unwrap();
+        let cap = re.captures(name.trim()).ok_or_else(unknown)?;
+        let year: i16 = cap[1].parse().map_err(|_| unknown())?;
+        let number: i64 = cap[2].parse().map_err(|_| unknown())?;
+        let first = self.first_of_year(year).ok_or_else(unknown)?;
+        let start = first
+            .checked_add(((number - 1) * length).days())
+            .map_err(|_| unknown())?;
+        match number >= 1 && start.year() == year {
+            true => self.sprint(start).ok_or_else(unknown),
+            false => Err(unknown()),
+        }
+    }
+
+    /// The start of the cadence's sprint `index` sprints after the anchor's.
+    fn start(&self, index: i64) -> Option<Date> {
+        let (anchor, length) = self.cadence?;
+        anchor.checked_add((index * length).days()).ok()
+    }
+
+    /// The first cadence sprint starting in `year`.
+    fn first_of_year(&self, year: i16) -> Option<Date> {
+        let (anchor, length) = self.cadence?;
+        let january = Date::new(year, 1, 1).ok()?;
+        let index = (january - anchor).get_days() as i64;
+        self.start(-(-index).div_euclid(length))
+    }
+}
+
+/// A sprint length like `2w` or `10d`, of at most a year, since sprints
+/// are numbered within one.
+fn parse_length(length: &str) -> Result<i64, String> {
+    let re = Regex::new(r"^(\d+)([dw])$").unwrap();
+    let days = re.captures(length.trim()).and_then(|cap| {
+        let count: i64 = cap[1].parse().ok()?;
+        match &cap[2] {
+            "w" => count.checked_mul(7),
+            _ => Some(count),
+        }
+    });
+    match days {
+        Some(days) if (1..=366).contains(&days) => Ok(days),
+        _ => Err(format!(
+            "invalid sprint_length '{}'; expected like 2w or 10d, up to a year",
+            length
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_sprints() {
+        let ranges = [SprintRange {
+            name: "Hardening".to_string(),
+            start: "2025-03-03".to_string(),
+            end: "2025-03-07".to_string(),
+        }];
+        let sprints = Sprints::new(Some("2025-01-06"), Some("2w"), &ranges).unwrap();
+        let name = |day: Date| sprints.sprint(day).unwrap().name;
+        assert_eq!("2025.01", name(date(2025, 1, 6)));
+        assert_eq!("2025.01", name(date(2025, 1, 19)));
+        assert_eq!("2025.02", name(date(2025, 1, 20)));
+        assert_eq!("2024.26", name(date(2025, 1, 5)));
+        assert_eq!("Hardening", name(date(2025, 3, 5)));
+        assert_eq!(
+            Sprint {
+                name: "2025.03".to_string(),
+                start: date(2025, 2, 3),
+                end: date(2025, 2, 16),
+            },
+            sprints.find("2025.03").unwrap()
+        );
+        assert_eq!(date(2025, 3, 7), sprints.find("Hardening").unwrap().end);
+        assert_eq!(date(2024, 12, 23), sprints.find("2024.26").unwrap().start);
+        assert!(sprints.find("2024.27").is_err());
+        assert!(sprints.find("2025.00").is_err());
+
+        assert_eq!(None, Sprints::default().sprint(date(2025, 1, 6)));
+        assert!(Sprints::new(None, Some("2w"), &[]).is_err());
+        assert!(Sprints::new(Some("2025-01-06"), Some("2x"), &[]).is_err());
+        assert!(Sprints::new(Some("2025-01-06"), Some("99999999w"), &[]).is_err());
+    }
+}
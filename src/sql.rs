@@ -0,0 +1,123 @@
+// src/sql.rs
+
+use crate::report::Cell;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, params_from_iter};
+use std::error::Error;
+
+/// The result of a query: its column names and rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+/// Loads `records` into an in-memory SQLite table named `table` with
+/// `columns`, then runs `sql` against it. Number cells are stored as
+/// integers and empty text as NULL.
+pub fn query(
+    table: &str,
+    columns: &[&str],
+    records: &[Vec<Cell>],
+    sql: &str,
+) -> Result<Table, Box<dyn Error>> {
+    let connection = Connection::open_in_memory()?;
+    let quoted: Vec<String> = columns
+        .iter()
+        .map(|column| format!("\"{}\"", column.replace('"', "\"\"")))
+        .collect();
+    connection.execute_batch(&format!("CREATE TABLE {} ({});", table, quoted.join(", ")))?;
+
+    connection.execute_batch("BEGIN;")?;
+    {
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut insert =
+            connection.prepare(&format!("INSERT INTO {} VALUES ({})", table, placeholders))?;
+        for record in records {
+            insert.execute(params_from_iter(record.iter().map(value)))?;
+        }
+    }
+    connection.execute_batch("COMMIT;")?;
+
+    let mut statement = connection
+        .prepare(sql)
+        .map_err(|e| format!("invalid query: {}", e))?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let mut rows = vec![];
+    let mut results = statement.query([])?;
+    while let Some(row) = results.next()? {
+        let mut cells = vec![];
+        for index in 0..columns.len() {
+            cells.push(match row.get_ref(index)? {
+                ValueRef::Null => Cell::Text(String::new()),
+                ValueRef::Integer(number) => Cell::Number(number.to_string()),
+                ValueRef::Real(number) => Cell::Number(number.to_string()),
+                ValueRef::Text(text) | ValueRef::Blob(text) => {
+                    Cell::Text(String::from_utf8_lossy(text).into_owned())
+                }
+            });
+        }
+        rows.push(cells);
+    }
+    Ok(Table { columns, rows })
+}
+
+fn value(cell: &Cell) -> Value {
+    match cell {
+        Cell::Number(number) => match number.parse::<i64>() {
+            Ok(integer) => Value::Integer(integer),
+            Err(_) => number.parse().map_or(Value::Null, Value::Real),
+        },
+        cell => match cell.text() {
+            text if text.is_empty() => Value::Null,
+            text => Value::Text(text),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let record = |tag: &str, seconds: &str| {
+            vec![
+                Cell::Text(tag.to_string()),
+                Cell::Number(seconds.to_string()),
+            ]
+        };
+        let records = [
+            record("#dev", "3600"),
+            record("#ops", "1800"),
+            record("#dev", "1800"),
+            record("", "60"),
+        ];
+        let table = query(
+            "entries",
+            &["tag", "seconds"],
+            &records,
+            "SELECT tag, sum(seconds) / 3600.0 AS hours FROM entries GROUP BY 1 ORDER BY 1",
+        )
+        .unwrap();
+        assert_eq!(vec!["tag", "hours"], table.columns);
+        let rows: Vec<Vec<String>> = table
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.to_json()).collect())
+            .collect();
+        assert_eq!(
+            vec![
+                vec!["null", "0.016666666666666666"],
+                vec!["\"#dev\"", "1.5"],
+                vec!["\"#ops\"", "0.5"],
+            ],
+            rows
+        );
+        assert!(query("entries", &["tag"], &[], "SELECT nope FROM entries").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// src/stats.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::json;
+use crate::locale::Locale;
+use crate::parser::{Syntax, TimeEntry};
+use jiff::civil::Date;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::io::Write;
+
+/// Corpus-wide summary of a scan, for checking on the tracking habit.
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub files_scanned: usize,
+    pub files_with_entries: usize,
+    pub entries: usize,
+    pub total: TimeDuration,
+    /// Dates that have notes but no tracked time.
+    pub untracked_days: Vec<Date>,
+    /// Tags ordered by total tracked time, largest first.
+    pub top_tags: Vec<TagStats>,
+    /// Number of entries written in each syntax.
+    pub syntaxes: BTreeMap<Syntax, usize>,
+}
+
+/// A tag's total and when it was in use, to spot tags gone stale.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagStats {
+    pub tag: String,
+    pub total: TimeDuration,
+    /// The earliest and latest dates of its entries, if any are dated.
+    pub first_seen: Option<Date>,
+    pub last_seen: Option<Date>,
+    /// Dates with time tracked on it.
+    pub days_active: usize,
+}
+
+impl Stats {
+    /// Summarizes `files`, given as each file's date (if any) and entries,
+    /// keeping the `top` tags.
+    pub fn from_files(files: &[(Option<Date>, Vec<TimeEntry>)], top: usize) -> Self {
+        let mut stats = Stats {
+            files_scanned: files.len(),
+            ..Default::default()
+        };
+        let mut days: BTreeMap<Date, bool> = BTreeMap::new();
+        let mut tags: HashMap<&str, (TimeDuration, BTreeSet<Date>)> = HashMap::new();
+        for (date, entries) in files {
+            if !entries.is_empty() {
+                stats.files_with_entries += 1;
+            }
+            if let Some(date) = date {
+                *days.entry(*date).or_default() |= !entries.is_empty();
+            }
+            for entry in entries {
+                stats.entries += 1;
+                stats.total += &entry.duration;
+                *stats.syntaxes.entry(entry.syntax).or_default() += 1;
+                for tag in entry.tags.split(',').filter(|t| !t.is_empty()) {
+                    let (total, dates) = tags.entry(tag).or_default();
+                    *total += &entry.duration;
+                    dates.extend(entry.date);
+                }
+            }
+        }
+        stats.untracked_days = days
+            .into_iter()
+            .filter(|(_, tracked)| !tracked)
+            .map(|(date, _)| date)
+            .collect();
+
+        let mut top_tags: Vec<TagStats> = tags
+            .into_iter()
+            .map(|(tag, (total, dates))| TagStats {
+                tag: tag.to_string(),
+                total,
+                first_seen: dates.first().copied(),
+                last_seen: dates.last().copied(),
+                days_active: dates.len(),
+            })
+            .collect();
+        top_tags.sort_by(|a, b| {
+            b.total
+                .total_seconds()
+                .cmp(&a.total.total_seconds())
+                .then_with(|| a.tag.cmp(&b.tag))
+        });
+        top_tags.truncate(top);
+        stats.top_tags = top_tags;
+        stats
+    }
+
+    pub fn write_text<W: Write>(
+        &self,
+        mut writer: W,
+        unit: DurationUnit,
+        locale: &Locale,
+    ) -> Result<(), Box<dyn Error>> {
+        writeln!(writer, "Files scanned:      {}", self.files_scanned)?;
+        writeln!(writer, "Files with entries: {}", self.files_with_entries)?;
+        writeln!(writer, "Total entries:      {}", self.entries)?;
+        writeln!(
+            writer,
+            "Total tracked:      {}",
+            locale.duration(&self.total, unit)
+        )?;
+        let days: Vec<String> = self
+            .untracked_days
+            .iter()
+            .map(|d| locale.date(*d))
+            .collect();
+        writeln!(writer, "Untracked days:     {}", days.join(", "))?;
+        let syntaxes: Vec<String> = self
+            .syntaxes
+            .iter()
+            .map(|(syntax, count)| format!("{} {}", syntax, count))
+            .collect();
+        writeln!(writer, "Entries by syntax:  {}", syntaxes.join(", "))?;
+        writeln!(writer, "Top tags:")?;
+        let width = self
+            .top_tags
+            .iter()
+            .map(|t| t.tag.chars().count())
+            .max()
+            .unwrap_or(0);
+        let totals: Vec<String> = self
+            .top_tags
+            .iter()
+            .map(|t| locale.duration(&t.total, unit))
+            .collect();
+        let total_width = totals.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+        for (tag, total) in self.top_tags.iter().zip(totals) {
+            let line = format!("{:<width$}  {:<total_width$}", tag.tag, total);
+            match (tag.first_seen, tag.last_seen) {
+                (Some(first), Some(last)) => writeln!(
+                    writer,
+                    "  {}  {} to {}, {} {}",
+                    line,
+                    locale.date(first),
+                    locale.date(last),
+                    tag.days_active,
+                    if tag.days_active == 1 { "day" } else { "days" }
+                )?,
+                _ => writeln!(writer, "  {}", line.trim_end())?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        let days: Vec<String> = self
+            .untracked_days
+            .iter()
+            .map(|d| json::quote(&d.to_string()))
+            .collect();
+        let tags: Vec<String> = self
+            .top_tags
+            .iter()
+            .map(|tag| {
+                let date = |date: Option<Date>| {
+                    date.map_or("null".to_string(), |d| json::quote(&d.to_string()))
+                };
+                format!(
+                    "{{\"tag\":{},\"seconds\":{},\"first_seen\":{},\"last_seen\":{},\"days_active\":{}}}",
+                    json::quote(&tag.tag),
+                    tag.total.total_seconds(),
+                    date(tag.first_seen),
+                    date(tag.last_seen),
+                    tag.days_active
+                )
+            })
+            .collect();
+        let syntaxes: Vec<String> = self
+            .syntaxes
+            .iter()
+            .map(|(syntax, count)| format!("{}:{}", json::quote(&syntax.to_string()), count))
+            .collect();
+        format!(
+            "{{\"files_scanned\":{},\"files_with_entries\":{},\"entries\":{},\"seconds\":{},\"untracked_days\":[{}],\"top_tags\":[{}],\"syntaxes\":{{{}}}}}",
+            self.files_scanned,
+            self.files_with_entries,
+            self.entries,
+            self.total.total_seconds(),
+            days.join(","),
+            tags.join(","),
+            syntaxes.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_stats_from_files() {
+        let files = vec![
+            (
+                Some(date(2025, 1, 1)),
+                vec![
+                    TimeEntry::test("#a,#b", "1h").dated(date(2025, 1, 1)),
+                    TimeEntry::test("#b", "2h").dated(date(2025, 1, 1)),
+                ],
+            ),
+            (Some(date(2025, 1, 2)), vec![]),
+            (
+                Some(date(2025, 1, 5)),
+                vec![TimeEntry::test("#b", "15m").dated(date(2025, 1, 5))],
+            ),
+            (None, vec![TimeEntry::test("", "30m")]),
+        ];
+        let stats = Stats::from_files(&files, 1);
+        assert_eq!(4, stats.files_scanned);
+        assert_eq!(3, stats.files_with_entries);
+        assert_eq!(4, stats.entries);
+        assert_eq!(
+            parse_duration("3h45m").total_seconds(),
+            stats.total.total_seconds()
+        );
+        assert_eq!(vec![date(2025, 1, 2)], stats.untracked_days);
+        assert_eq!(
+            vec![TagStats {
+                tag: "#b".to_string(),
+                total: parse_duration("3h15m"),
+                first_seen: Some(date(2025, 1, 1)),
+                last_seen: Some(date(2025, 1, 5)),
+                days_active: 2,
+            }],
+            stats.top_tags
+        );
+        assert_eq!(BTreeMap::from([(Syntax::Field, 4)]), stats.syntaxes);
+    }
+}
@@ -0,0 +1,143 @@
+// src/streak.rs
+
+use crate::date::monday;
+use crate::duration::{TimeDuration, format_duration, parse_duration};
+use crate::parser::{TimeEntry, has_tag};
+use jiff::ToSpan;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A weekly target for a tag, written `#sideproject >= 5h/week`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Goal {
+    pub tag: String,
+    pub target: TimeDuration,
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid goal '{}'; expected like '#tag >= 5h/week'", s);
+        let (tag, target) = s.split_once(">=").ok_or_else(invalid)?;
+        let tag = tag.trim();
+        let target = target.trim();
+        let target = target.strip_suffix("/week").unwrap_or(target);
+        if !tag.starts_with('#') || tag.len() < 2 || tag.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+        let target = parse_duration(target);
+        if target.total_seconds() == 0 {
+            return Err(invalid());
+        }
+        Ok(Goal {
+            tag: tag.to_string(),
+            target,
+        })
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} >= {}/week", self.tag, format_duration(&self.target))
+    }
+}
+
+/// How a goal has gone, counted in weeks starting on Monday.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Streak {
+    /// Time on the tag so far in the week of `today`.
+    pub this_week: TimeDuration,
+    /// Consecutive weeks meeting the goal up to last week, plus this week
+    /// once it meets the goal too, so a week in progress doesn't break it.
+    pub current: usize,
+    pub longest: usize,
+}
+
+/// Totals the goal's tag per week, from the week of the first dated entry
+/// (of any tag) to the week of `today`, and counts the streaks of weeks
+/// meeting the target.
+pub fn streak(entries: &[TimeEntry], goal: &Goal, today: Date) -> Streak {
+    let this_monday = monday(today);
+    let mut weeks: BTreeMap<Date, TimeDuration> = BTreeMap::new();
+    let mut first = this_monday;
+    for entry in entries {
+        let Some(date) = entry.date else {
+            continue;
+        };
+        let week = monday(date);
+        if week > this_monday {
+            continue;
+        }
+        first = first.min(week);
+        if has_tag(&entry.tags, &goal.tag) {
+            *weeks.entry(week).or_default() += &entry.duration;
+        }
+    }
+
+    let met = |week: Date| {
+        weeks
+            .get(&week)
+            .is_some_and(|total| total.total_seconds() >= goal.target.total_seconds())
+    };
+    let (mut run, mut longest) = (0, 0);
+    let mut week = first;
+    while week < this_monday {
+        run = if met(week) { run + 1 } else { 0 };
+        longest = longest.max(run);
+        week = week.checked_add(1.week()).unwrap();
+    }
+    let current = if met(this_monday) { run + 1 } else { run };
+    Streak {
+        this_week: weeks.get(&this_monday).cloned().unwrap_or_default(),
+        current,
+        longest: longest.max(current),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_parse_goal() {
+        let goal: Goal = "#sideproject >= 5h/week".parse().unwrap();
+        assert_eq!("#sideproject", goal.tag);
+        assert_eq!(parse_duration("5h"), goal.target);
+        assert_eq!("#sideproject >= 5h/week", goal.to_string());
+        assert!("sideproject >= 5h".parse::<Goal>().is_err());
+        assert!("#sideproject > 5h".parse::<Goal>().is_err());
+    }
+
+    #[test]
+    fn test_streak() {
+        let goal: Goal = "#side >= 2h".parse().unwrap();
+        // Mondays of six weeks: met, met, missed, met, met, and this week.
+        let entries = vec![
+            TimeEntry::test("#side", "2h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#side", "1h").dated(date(2025, 1, 13)),
+            TimeEntry::test("#side,#dev", "1h").dated(date(2025, 1, 15)),
+            TimeEntry::test("#dev", "8h").dated(date(2025, 1, 20)),
+            TimeEntry::test("#side", "3h").dated(date(2025, 1, 28)),
+            TimeEntry::test("#side", "2h").dated(date(2025, 2, 3)),
+            TimeEntry::test("#side", "1h").dated(date(2025, 2, 10)),
+        ];
+        let today = date(2025, 2, 12);
+        assert_eq!(
+            Streak {
+                this_week: parse_duration("1h"),
+                current: 2,
+                longest: 2,
+            },
+            streak(&entries, &goal, today)
+        );
+
+        let mut entries = entries;
+        entries.push(TimeEntry::test("#side", "1h").dated(date(2025, 2, 11)));
+        assert_eq!(3, streak(&entries, &goal, today).current);
+        assert_eq!(3, streak(&entries, &goal, today).longest);
+    }
+}
@@ -0,0 +1,184 @@
+// src/suggest.rs
+
+use crate::parser::{TimeEntry, mask_code};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Words too common to say what a task is about.
+const STOP_WORDS: [&str; 12] = [
+    "an", "and", "at", "for", "in", "of", "on", "or", "the", "to", "with", "from",
+];
+
+/// The words of a task's text that are compared, lowercased, without its
+/// tags or stop words.
+pub fn tokens(text: &str) -> BTreeSet<String> {
+    let re_word = Regex::new(r"#[^\s,#]+|[\p{L}\p{N}]+").unwrap();
+    re_word
+        .find_iter(text)
+        .map(|m| m.as_str().to_lowercase())
+        .filter(|word| !word.starts_with('#') && word.chars().count() > 1)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Tags suggested for an untagged entry, from the tagged entry whose text
+/// is most like its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Comma-joined, as in `TimeEntry::tags`.
+    pub tags: String,
+    /// The share of the two texts' words they have in common, from 0 to 1.
+    pub similarity: f64,
+    /// The text of the tagged entry the tags come from.
+    pub example: String,
+}
+
+/// A tagged entry's text, as compared.
+struct Example {
+    tokens: BTreeSet<String>,
+    tags: String,
+    text: String,
+}
+
+/// Suggests tags for entries from the tagged ones, by how many words their
+/// texts share.
+#[derive(Default)]
+pub struct Suggester {
+    examples: Vec<Example>,
+    /// The examples each word appears in.
+    index: HashMap<String, Vec<usize>>,
+}
+
+impl Suggester {
+    /// Learns from `entries`, skipping those without tags. Entries with the
+    /// same words and tags are learned once.
+    pub fn new<'a>(entries: impl IntoIterator<Item = &'a TimeEntry>) -> Self {
+        let mut suggester = Suggester::default();
+        let mut seen = HashSet::new();
+        for entry in entries {
+            let tokens = tokens(&entry.text);
+            if entry.tags.is_empty()
+                || tokens.is_empty()
+                || !seen.insert((tokens.clone(), entry.tags.clone()))
+            {
+                continue;
+            }
+            for token in &tokens {
+                suggester
+                    .index
+                    .entry(token.clone())
+                    .or_default()
+                    .push(suggester.examples.len());
+            }
+            suggester.examples.push(Example {
+                tokens,
+                tags: entry.tags.clone(),
+                text: entry.text.clone(),
+            });
+        }
+        suggester
+    }
+
+    /// The tags of the examples most like `text`, as their Jaccard
+    /// similarity, if at least `min_similarity`. Ties go to the tags more
+    /// examples that similar carry.
+    pub fn suggest(&self, text: &str, min_similarity: f64) -> Option<Suggestion> {
+        let wanted = tokens(text);
+        let mut overlaps: BTreeMap<usize, usize> = BTreeMap::new();
+        for token in &wanted {
+            for &example in self.index.get(token).into_iter().flatten() {
+                *overlaps.entry(example).or_default() += 1;
+            }
+        }
+        // Per tag set: the best similarity, how many examples reach it, and
+        // the first of them.
+        let mut candidates: BTreeMap<&str, (f64, usize, usize)> = BTreeMap::new();
+        for (example, overlap) in overlaps {
+            let union = wanted.len() + self.examples[example].tokens.len() - overlap;
+            let similarity = overlap as f64 / union as f64;
+            if similarity < min_similarity {
+                continue;
+            }
+            let candidate = candidates
+                .entry(&self.examples[example].tags)
+                .or_insert((similarity, 0, example));
+            if similarity > candidate.0 {
+                *candidate = (similarity, 0, example);
+            }
+            if similarity == candidate.0 {
+                candidate.1 += 1;
+            }
+        }
+        candidates
+            .into_iter()
+            .rev()
+            .max_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(tags, (similarity, _, example))| Suggestion {
+                tags: tags.to_string(),
+                similarity,
+                example: self.examples[example].text.clone(),
+            })
+    }
+}
+
+/// Adds `tags` to a task's line, after its text and before its first
+/// inline field, keeping the line ending.
+pub fn add_tags(line: &str, tags: &[&str]) -> String {
+    let re_field = Regex::new(r"\[[^\[\]:]+::?[^\]]*\]").unwrap();
+    let body = line.trim_end();
+    let masked = mask_code(body);
+    let at = re_field.find(&masked).map_or(body.len(), |m| m.start());
+    let (head, tail) = line.split_at(at);
+    let separator = if at < body.len() { " " } else { "" };
+    format!(
+        "{} {}{}{}",
+        head.trim_end(),
+        tags.join(" "),
+        separator,
+        tail.trim_start_matches([' ', '\t'])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(
+            BTreeSet::from(["review".to_string(), "prs".to_string()]),
+            tokens("Review the PRs #dev,#pbi-1 a")
+        );
+        let entries = [
+            TimeEntry::test("#dev", "0m").with_text("Review pull requests #dev"),
+            TimeEntry::test("#dev,#billing", "0m").with_text("Review pull requests for billing"),
+            TimeEntry::test("#finance", "0m").with_text("Review budget"),
+            TimeEntry::test("#finance", "0m").with_text("Budget review"),
+            TimeEntry::test("#hr", "0m").with_text("Review hiring"),
+            TimeEntry::test("#hr", "0m").with_text("Review interviews"),
+            TimeEntry::test("", "0m").with_text("Untagged review"),
+        ];
+        let suggester = Suggester::new(&entries);
+        assert_eq!(
+            Some(Suggestion {
+                tags: "#dev".to_string(),
+                similarity: 0.75,
+                example: "Review pull requests #dev".to_string(),
+            }),
+            suggester.suggest("Review the pull requests again", 0.3)
+        );
+        // Two examples as similar outweigh one; the same words count once.
+        assert_eq!("#hr", suggester.suggest("Review", 0.0).unwrap().tags);
+        assert_eq!(None, suggester.suggest("Review", 0.6));
+        assert_eq!(None, suggester.suggest("Deploy", 0.0));
+
+        assert_eq!(
+            "- [ ] Review PRs #dev #web [timeTracked: 1h]\r\n",
+            add_tags("- [ ] Review PRs [timeTracked: 1h]\r\n", &["#dev", "#web"])
+        );
+        assert_eq!(
+            "- Call `[x: 1]` #ops\n",
+            add_tags("- Call `[x: 1]`  \n", &["#ops"])
+        );
+    }
+}
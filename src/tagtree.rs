@@ -0,0 +1,159 @@
+// src/tagtree.rs
+
+use crate::json;
+use crate::parser::TimeEntry;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A tag in the hierarchy of nested tags, where `#work/acme` is a child of
+/// `#work`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TagNode {
+    /// The last segment of the tag, like `acme` for `#work/acme`.
+    pub name: String,
+    /// The full tag, like `#work/acme`.
+    pub tag: String,
+    /// Time on entries with this tag or any tag nested under it, counting
+    /// each entry once.
+    pub seconds: u64,
+    /// Number of entries with this tag or any tag nested under it.
+    pub count: usize,
+    /// Time on entries tagged with exactly this tag.
+    pub self_seconds: u64,
+    pub children: Vec<TagNode>,
+}
+
+/// The tags of `entries` as a forest of nested tags, ordered by tag. Parents
+/// that no entry carries on its own, like `#work` for `#work/acme`, are
+/// filled in.
+pub fn tag_tree(entries: &[TimeEntry]) -> Vec<TagNode> {
+    let mut totals: BTreeMap<String, (u64, usize, u64)> = BTreeMap::new();
+    for entry in entries {
+        let seconds = entry.duration.total_seconds();
+        let tags: BTreeSet<&str> = entry.tags.split(',').filter(|t| !t.is_empty()).collect();
+        let mut paths = BTreeSet::new();
+        for tag in &tags {
+            for (index, _) in tag.match_indices('/') {
+                paths.insert(&tag[..index]);
+            }
+            paths.insert(*tag);
+        }
+        for path in paths {
+            let total = totals.entry(path.to_string()).or_default();
+            total.0 += seconds;
+            total.1 += 1;
+            if tags.contains(path) {
+                total.2 += seconds;
+            }
+        }
+    }
+    children(&totals, None)
+}
+
+/// The nodes directly under `parent`, or the top-level tags for `None`.
+fn children(totals: &BTreeMap<String, (u64, usize, u64)>, parent: Option<&str>) -> Vec<TagNode> {
+    totals
+        .iter()
+        .filter(|(tag, _)| match parent {
+            Some(parent) => tag
+                .strip_prefix(parent)
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|rest| !rest.contains('/')),
+            None => !tag.contains('/'),
+        })
+        .map(|(tag, &(seconds, count, self_seconds))| TagNode {
+            name: tag
+                .rsplit('/')
+                .next()
+                .unwrap_or(tag)
+                .trim_start_matches('#')
+                .to_string(),
+            tag: tag.clone(),
+            seconds,
+            count,
+            self_seconds,
+            children: children(totals, Some(tag)),
+        })
+        .collect()
+}
+
+/// The forest as a JSON object with the total time on tagged entries, in the
+/// `name`/`children` shape sunburst and treemap charts take.
+pub fn to_json(nodes: &[TagNode], entries: &[TimeEntry]) -> String {
+    let (seconds, count) = entries
+        .iter()
+        .filter(|entry| !entry.tags.is_empty())
+        .fold((0, 0), |(seconds, count), entry| {
+            (seconds + entry.duration.total_seconds(), count + 1)
+        });
+    format!(
+        "{{\"name\":\"\",\"seconds\":{},\"count\":{},\"children\":[{}]}}",
+        seconds,
+        count,
+        nodes.iter().map(node_json).collect::<Vec<_>>().join(",")
+    )
+}
+
+fn node_json(node: &TagNode) -> String {
+    format!(
+        "{{\"name\":{},\"tag\":{},\"seconds\":{},\"count\":{},\"self_seconds\":{},\"children\":[{}]}}",
+        json::quote(&node.name),
+        json::quote(&node.tag),
+        node.seconds,
+        node.count,
+        node.self_seconds,
+        node.children
+            .iter()
+            .map(node_json)
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_tree() {
+        let entries = [
+            TimeEntry::test("#work/acme/api", "1h"),
+            TimeEntry::test("#work/acme,#work/acme/api", "30m"),
+            TimeEntry::test("#work/globex,#dev", "2h"),
+            TimeEntry::test("#work", "15m"),
+            TimeEntry::test("", "5m"),
+        ];
+        let tree = tag_tree(&entries);
+        assert_eq!(
+            vec!["#dev", "#work"],
+            tree.iter().map(|n| n.tag.as_str()).collect::<Vec<_>>()
+        );
+        let work = &tree[1];
+        assert_eq!(
+            ("work", 13500, 4, 900),
+            (
+                work.name.as_str(),
+                work.seconds,
+                work.count,
+                work.self_seconds
+            )
+        );
+        let acme = &work.children[0];
+        assert_eq!(
+            ("#work/acme", 5400, 2, 1800),
+            (
+                acme.tag.as_str(),
+                acme.seconds,
+                acme.count,
+                acme.self_seconds
+            )
+        );
+        assert_eq!("api", acme.children[0].name);
+        assert_eq!(5400, acme.children[0].seconds);
+        assert_eq!("#work/globex", work.children[1].tag);
+        assert_eq!(
+            "{\"name\":\"\",\"seconds\":13500,\"count\":4,\"children\":[\
+             {\"name\":\"dev\",\"tag\":\"#dev\",\"seconds\":7200,\"count\":1,\"self_seconds\":7200,\"children\":[]}]}",
+            to_json(&tree[..1], &entries[..4])
+        );
+    }
+}
@@ -0,0 +1,305 @@
+// src/targets.rs
+
+use crate::date::monday;
+use crate::duration::{DurationUnit, TimeDuration, format_duration, parse_duration};
+use crate::parser::{TimeEntry, has_tag};
+use jiff::ToSpan;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The stretch of time a target's budget is for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// The first day of the period `date` is in; weeks start on Monday.
+    pub fn start(self, date: Date) -> Date {
+        match self {
+            Period::Day => date,
+            Period::Week => monday(date),
+            Period::Month => date.first_of_month(),
+        }
+    }
+
+    fn next(self, start: Date) -> Date {
+        match self {
+            Period::Day => start.checked_add(1.day()),
+            Period::Week => start.checked_add(1.week()),
+            Period::Month => start.checked_add(1.month()),
+        }
+        .unwrap()
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Period::Day => write!(f, "day"),
+            Period::Week => write!(f, "week"),
+            Period::Month => write!(f, "month"),
+        }
+    }
+}
+
+/// How a period's total is held against a target's duration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    AtLeast,
+    MoreThan,
+    AtMost,
+    LessThan,
+}
+
+impl Comparison {
+    fn holds(self, total: u64, limit: u64) -> bool {
+        match self {
+            Comparison::AtLeast => total >= limit,
+            Comparison::MoreThan => total > limit,
+            Comparison::AtMost => total <= limit,
+            Comparison::LessThan => total < limit,
+        }
+    }
+
+    /// Whether more time can only make the target harder to meet.
+    fn is_cap(self) -> bool {
+        matches!(self, Comparison::AtMost | Comparison::LessThan)
+    }
+}
+
+/// A time budget for a tag, like `#deepwork >= 20h/week` or `#meetings <
+/// 6h/week`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub tag: String,
+    pub comparison: Comparison,
+    pub duration: TimeDuration,
+    pub period: Period,
+}
+
+impl Target {
+    /// A target for `tag` from a budget like `20h/week`, `>= 20h/week`, or
+    /// `<6h/week`. Budgets without a comparison are minimums, and those
+    /// without a period are weekly.
+    pub fn new(tag: &str, budget: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "invalid target '{}' for {}; expected like '20h/week' or '<6h/day'",
+                budget, tag
+            )
+        };
+        if !tag.starts_with('#') || tag.len() < 2 || tag.contains(char::is_whitespace) {
+            return Err(format!("target tag '{}' must be like #tag", tag));
+        }
+        let budget = budget.trim();
+        let (comparison, rest) = [
+            (">=", Comparison::AtLeast),
+            ("<=", Comparison::AtMost),
+            (">", Comparison::MoreThan),
+            ("<", Comparison::LessThan),
+        ]
+        .into_iter()
+        .find_map(|(op, comparison)| budget.strip_prefix(op).map(|rest| (comparison, rest)))
+        .unwrap_or((Comparison::AtLeast, budget));
+        let (duration, period) = match rest.split_once('/') {
+            Some((duration, period)) => (duration, period.trim()),
+            None => (rest, "week"),
+        };
+        let period = match period {
+            "day" => Period::Day,
+            "week" => Period::Week,
+            "month" => Period::Month,
+            _ => return Err(invalid()),
+        };
+        let duration = parse_duration(duration.trim());
+        if duration.total_seconds() == 0 && !comparison.is_cap() {
+            return Err(invalid());
+        }
+        Ok(Target {
+            tag: tag.to_string(),
+            comparison,
+            duration,
+            period,
+        })
+    }
+
+    /// The config's `targets` table, by tag.
+    pub fn from_config(targets: &BTreeMap<String, String>) -> Result<Vec<Self>, String> {
+        targets
+            .iter()
+            .map(|(tag, budget)| Target::new(tag, budget))
+            .collect()
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Reads a target written whole, like `#meetings < 6h/week`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let end = s
+            .find(|c: char| c.is_whitespace() || "<>=".contains(c))
+            .ok_or_else(|| format!("invalid target '{}'; expected like '#tag >= 20h/week'", s))?;
+        Target::new(&s[..end], s[end..].trim_start_matches('=').trim())
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.comparison {
+            Comparison::AtLeast => ">=",
+            Comparison::MoreThan => ">",
+            Comparison::AtMost => "<=",
+            Comparison::LessThan => "<",
+        };
+        write!(
+            f,
+            "{} {} {}/{}",
+            self.tag,
+            op,
+            format_duration(&self.duration),
+            self.period
+        )
+    }
+}
+
+/// Whether a period kept to its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Met,
+    Missed,
+    /// The period is still running and could go either way.
+    Pending,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Met => write!(f, "met"),
+            Status::Missed => write!(f, "missed"),
+            Status::Pending => write!(f, "pending"),
+        }
+    }
+}
+
+/// A target's total for one period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Actual {
+    pub target: String,
+    pub period: Date,
+    pub total: TimeDuration,
+    pub status: Status,
+}
+
+impl Actual {
+    /// The period as CSV fields: target, period start, total in `unit`, and
+    /// status.
+    pub fn record(&self, unit: DurationUnit) -> [String; 4] {
+        let total = match self.total.total_seconds() {
+            0 => "0".to_string(),
+            _ => unit.format(&self.total),
+        };
+        [
+            self.target.clone(),
+            self.period.to_string(),
+            total,
+            self.status.to_string(),
+        ]
+    }
+}
+
+/// Totals each target's tag per period, for every period from the one of
+/// `since` to the one of `until`. The period of `today` is pending until a
+/// minimum is reached or a cap exceeded.
+pub fn actuals(
+    entries: &[TimeEntry],
+    targets: &[Target],
+    since: Date,
+    until: Date,
+    today: Date,
+) -> Vec<Actual> {
+    let mut actuals = vec![];
+    for target in targets {
+        let mut totals: BTreeMap<Date, TimeDuration> = BTreeMap::new();
+        for entry in entries {
+            if let Some(date) = entry.date
+                && has_tag(&entry.tags, &target.tag)
+            {
+                *totals.entry(target.period.start(date)).or_default() += &entry.duration;
+            }
+        }
+        let current = target.period.start(today);
+        let mut period = target.period.start(since);
+        while period <= until {
+            let total = totals.get(&period).cloned().unwrap_or_default();
+            let holds = target
+                .comparison
+                .holds(total.total_seconds(), target.duration.total_seconds());
+            let status = match (holds, period >= current) {
+                (true, true) if target.comparison.is_cap() => Status::Pending,
+                (false, true) if !target.comparison.is_cap() => Status::Pending,
+                (true, _) => Status::Met,
+                (false, _) => Status::Missed,
+            };
+            actuals.push(Actual {
+                target: target.to_string(),
+                period,
+                total,
+                status,
+            });
+            period = target.period.next(period);
+        }
+    }
+    actuals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_actuals() {
+        let targets = Target::from_config(&BTreeMap::from([
+            ("#deepwork".to_string(), "20h/week".to_string()),
+            ("#meetings".to_string(), "<6h/week".to_string()),
+        ]))
+        .unwrap();
+        assert_eq!("#deepwork >= 20h/week", targets[0].to_string());
+        assert_eq!(targets[1], "#meetings < 6h/week".parse::<Target>().unwrap());
+        assert_eq!(
+            Period::Month,
+            Target::new("#a", "<= 40h/month").unwrap().period
+        );
+        assert!(Target::new("#a", "20h/fortnight").is_err());
+        assert!(Target::new("a", "20h").is_err());
+
+        let entries = [
+            TimeEntry::test("#deepwork", "12h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#deepwork", "9h").dated(date(2025, 1, 10)),
+            TimeEntry::test("#meetings,#team", "7h").dated(date(2025, 1, 8)),
+            TimeEntry::test("#deepwork", "4h").dated(date(2025, 1, 14)),
+            TimeEntry::test("#meetings", "2h").dated(date(2025, 1, 15)),
+        ];
+        let today = date(2025, 1, 15);
+        let records: Vec<[String; 4]> = actuals(&entries, &targets, date(2025, 1, 6), today, today)
+            .iter()
+            .map(|actual| actual.record(DurationUnit::Compact))
+            .collect();
+        assert_eq!(
+            vec![
+                ["#deepwork >= 20h/week", "2025-01-06", "21h", "met"],
+                ["#deepwork >= 20h/week", "2025-01-13", "4h", "pending"],
+                ["#meetings < 6h/week", "2025-01-06", "7h", "missed"],
+                ["#meetings < 6h/week", "2025-01-13", "2h", "pending"],
+            ],
+            records
+        );
+    }
+}
@@ -0,0 +1,112 @@
+// src/timesheet.rs
+
+use crate::duration::{DurationUnit, TimeDuration};
+use crate::parser::TimeEntry;
+use csv::Writer;
+use jiff::civil::Date;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Write;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// One week of tracked time, with a row per tag set and a column per day.
+#[derive(Debug, PartialEq)]
+pub struct Timesheet {
+    /// The Monday the week starts on.
+    pub week: Date,
+    pub rows: BTreeMap<String, [TimeDuration; 7]>,
+}
+
+impl Timesheet {
+    /// Sums entries dated within the week starting on `week` (a Monday) by
+    /// their tags. Undated entries and entries outside the week are left out.
+    pub fn from_entries(entries: &[TimeEntry], week: Date) -> Self {
+        let mut rows: BTreeMap<String, [TimeDuration; 7]> = BTreeMap::new();
+        for entry in entries {
+            let Some(date) = entry.date else {
+                continue;
+            };
+            let day = week.until(date).map_or(-1, |span| span.get_days());
+            if !(0..7).contains(&day) {
+                continue;
+            }
+            rows.entry(entry.tags.clone()).or_default()[day as usize] += &entry.duration;
+        }
+        Timesheet { week, rows }
+    }
+
+    /// Writes the tags, a column per day headed with its date, and a total
+    /// column, followed by a row of daily totals. Days without time are
+    /// left blank.
+    pub fn write_csv<W: Write>(&self, writer: W, unit: DurationUnit) -> Result<(), Box<dyn Error>> {
+        let mut csv_writer = Writer::from_writer(writer);
+        let mut header = vec!["tags".to_string()];
+        let mut day = self.week;
+        for name in WEEKDAYS {
+            header.push(format!("{} {}", name, day));
+            day = day.tomorrow()?;
+        }
+        header.push("total".to_string());
+        csv_writer.write_record(&header)?;
+
+        let mut totals: [TimeDuration; 7] = Default::default();
+        for (tags, days) in &self.rows {
+            for (total, duration) in totals.iter_mut().zip(days) {
+                *total += duration;
+            }
+            csv_writer.write_record(row(tags, days, unit))?;
+        }
+        csv_writer.write_record(row("total", &totals, unit))?;
+        csv_writer.flush()?;
+        Ok(())
+    }
+}
+
+fn row(label: &str, days: &[TimeDuration; 7], unit: DurationUnit) -> Vec<String> {
+    let mut total = TimeDuration::default();
+    let mut record = vec![label.to_string()];
+    for duration in days {
+        total += duration;
+        record.push(format_cell(duration, unit));
+    }
+    record.push(format_cell(&total, unit));
+    record
+}
+
+fn format_cell(duration: &TimeDuration, unit: DurationUnit) -> String {
+    if duration.total_seconds() == 0 {
+        String::new()
+    } else {
+        unit.format(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::date;
+
+    #[test]
+    fn test_timesheet_csv() {
+        let entries = vec![
+            TimeEntry::test("#acme", "2h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#acme", "1h").dated(date(2025, 1, 6)),
+            TimeEntry::test("#globex", "30m").dated(date(2025, 1, 12)),
+            TimeEntry::test("#acme", "8h").dated(date(2025, 1, 13)),
+            TimeEntry::test("#acme", "8h").dated(date(2025, 1, 5)),
+        ];
+        let timesheet = Timesheet::from_entries(&entries, date(2025, 1, 6));
+        let mut output = vec![];
+        timesheet
+            .write_csv(&mut output, DurationUnit::Compact)
+            .unwrap();
+        assert_eq!(
+            "tags,Mon 2025-01-06,Tue 2025-01-07,Wed 2025-01-08,Thu 2025-01-09,Fri 2025-01-10,Sat 2025-01-11,Sun 2025-01-12,total\n\
+             #acme,3h,,,,,,,3h\n\
+             #globex,,,,,,,30m,30m\n\
+             total,3h,,,,,,30m,3h30m\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
@@ -0,0 +1,122 @@
+// src/timings.rs
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A stage of a run that `--timings` measures. Whatever isn't in one of
+/// them, like filtering and totaling entries, is reported as aggregation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Walking the directories for notes.
+    Discovery,
+    /// Opening and reading notes.
+    Io,
+    /// Matching the notes' lines for entries.
+    Parsing,
+    /// Writing the output.
+    Writing,
+}
+
+const PHASES: [(Phase, &str, &str); 4] = [
+    (Phase::Discovery, "discovery", "scans"),
+    (Phase::Io, "io", "notes"),
+    (Phase::Parsing, "parsing", "notes"),
+    (Phase::Writing, "writing", "writes"),
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NANOS: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+static CALLS: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+
+/// Starts measuring; until then `time` only runs what it's given.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Runs `f`, adding the time it takes to `phase` when measuring.
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    let nanos = start.elapsed().as_nanos() as u64;
+    NANOS[phase as usize].fetch_add(nanos, Ordering::Relaxed);
+    CALLS[phase as usize].fetch_add(1, Ordering::Relaxed);
+    result
+}
+
+/// A writer whose writes count as `Phase::Writing`.
+pub struct Timed<W: Write>(pub W);
+
+impl<W: Write> Write for Timed<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        time(Phase::Writing, || self.0.write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        time(Phase::Writing, || self.0.flush())
+    }
+}
+
+/// The breakdown of a run that took `wall`, from what was measured.
+pub fn report(wall: Duration) -> String {
+    let measured: Vec<(Duration, u64)> = (0..PHASES.len())
+        .map(|index| {
+            (
+                Duration::from_nanos(NANOS[index].load(Ordering::Relaxed)),
+                CALLS[index].load(Ordering::Relaxed),
+            )
+        })
+        .collect();
+    render(wall, &measured)
+}
+
+fn render(wall: Duration, measured: &[(Duration, u64)]) -> String {
+    let share = |duration: Duration| match wall.is_zero() {
+        true => 0.0,
+        false => duration.as_secs_f64() / wall.as_secs_f64() * 100.0,
+    };
+    let line = |name: &str, duration: Duration, calls: Option<String>| {
+        let mut line = format!(
+            "  {:<12}{:>10.1}ms{:>7.1}%",
+            name,
+            duration.as_secs_f64() * 1000.0,
+            share(duration)
+        );
+        if let Some(calls) = calls {
+            line.push_str(&format!("  {}", calls));
+        }
+        line + "\n"
+    };
+    let mut report = format!("timings: {:.1}ms wall\n", wall.as_secs_f64() * 1000.0);
+    let accounted: Duration = measured.iter().map(|(duration, _)| *duration).sum();
+    for ((phase, name, unit), (duration, calls)) in PHASES.iter().zip(measured) {
+        if *phase == Phase::Writing {
+            report.push_str(&line("aggregation", wall.saturating_sub(accounted), None));
+        }
+        report.push_str(&line(name, *duration, Some(format!("{} {}", calls, unit))));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let ms = Duration::from_millis;
+        let measured = [(ms(10), 1), (ms(500), 120), (ms(250), 80), (ms(40), 3)];
+        assert_eq!(
+            "timings: 1000.0ms wall\n\
+             \x20 discovery         10.0ms    1.0%  1 scans\n\
+             \x20 io               500.0ms   50.0%  120 notes\n\
+             \x20 parsing          250.0ms   25.0%  80 notes\n\
+             \x20 aggregation      200.0ms   20.0%\n\
+             \x20 writing           40.0ms    4.0%  3 writes\n",
+            render(ms(1000), &measured)
+        );
+    }
+}
@@ -0,0 +1,159 @@
+// src/vault.rs
+
+use crate::json;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where an Obsidian vault keeps its daily notes, from the core Daily notes
+/// plugin's settings.
+#[derive(Debug, PartialEq)]
+pub struct DailyNotes {
+    pub dir: PathBuf,
+    /// Whether the note name format puts notes in subfolders, like
+    /// `YYYY/MM/YYYY-MM-DD`.
+    pub nested: bool,
+}
+
+/// The nearest directory at or above `start` containing `.obsidian/`.
+pub fn find_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".obsidian").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// Reads `.obsidian/daily-notes.json` under `root`. Without the file, or
+/// without a `folder` in it, daily notes live in the vault root.
+pub fn daily_notes(root: &Path) -> DailyNotes {
+    let settings =
+        fs::read_to_string(root.join(".obsidian").join("daily-notes.json")).unwrap_or_default();
+    let setting = |key: &str| {
+        let re = Regex::new(&format!(r#""{}"\s*:\s*"((?:[^"\\]|\\.)*)""#, key)).unwrap();
+        re.captures(&settings)
+            .map(|cap| cap[1].replace("\\/", "/").replace("\\\\", "\\"))
+            .filter(|value| !value.is_empty())
+    };
+    let dir = match setting("folder") {
+        Some(folder) => root.join(folder.trim_matches('/')),
+        None => root.to_path_buf(),
+    };
+    DailyNotes {
+        dir,
+        nested: setting("format").is_some_and(|format| format.contains('/')),
+    }
+}
+
+/// The vault's "Excluded files" setting, which Obsidian leaves out of its
+/// search and graph.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFilters {
+    root: PathBuf,
+    /// Filters matching the start of a vault-relative path, like `Archive/`.
+    prefixes: Vec<String>,
+    /// Filters written as `/regex/`, searched for in vault-relative paths.
+    patterns: Vec<Regex>,
+}
+
+impl IgnoreFilters {
+    /// Whether Obsidian excludes `path`. Paths outside the vault never are.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+        else {
+            return false;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.prefixes
+            .iter()
+            .any(|prefix| relative.starts_with(prefix.as_str()))
+            || self.patterns.iter().any(|re| re.is_match(&relative))
+    }
+}
+
+/// Reads `userIgnoreFilters` from `.obsidian/app.json` under `root`. A
+/// filter that isn't a valid `/regex/` is matched as a path prefix.
+pub fn ignore_filters(root: &Path) -> IgnoreFilters {
+    let settings = fs::read_to_string(root.join(".obsidian").join("app.json")).unwrap_or_default();
+    let mut filters = IgnoreFilters {
+        root: std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf()),
+        ..Default::default()
+    };
+    let re_filters = Regex::new(r#""userIgnoreFilters"\s*:\s*\["#).unwrap();
+    let re_filter = Regex::new(r#"^\s*,?\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    let Some(list) = re_filters.find(&settings) else {
+        return filters;
+    };
+    let mut rest = &settings[list.end()..];
+    while let Some(cap) = re_filter.captures(rest) {
+        let filter = json::unescape(&cap[1]);
+        rest = &rest[cap[0].len()..];
+        let pattern = filter
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+            .filter(|pattern| !pattern.is_empty())
+            .and_then(|pattern| Regex::new(pattern).ok());
+        match pattern {
+            Some(re) => filters.patterns.push(re),
+            None if !filter.is_empty() => filters.prefixes.push(filter),
+            None => {}
+        }
+    }
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_root_and_daily_notes() {
+        let vault = tempfile::tempdir().unwrap();
+        let nested = vault.path().join("Projects").join("Acme");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(vault.path().join(".obsidian")).unwrap();
+
+        assert_eq!(Some(vault.path().to_path_buf()), find_root(&nested));
+        assert_eq!(
+            DailyNotes {
+                dir: vault.path().to_path_buf(),
+                nested: false,
+            },
+            daily_notes(vault.path())
+        );
+
+        fs::write(
+            vault.path().join(".obsidian").join("daily-notes.json"),
+            r#"{"folder": "Journals/Daily", "format": "YYYY/YYYY-MM-DD"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            DailyNotes {
+                dir: vault.path().join("Journals/Daily"),
+                nested: true,
+            },
+            daily_notes(vault.path())
+        );
+    }
+
+    #[test]
+    fn test_ignore_filters() {
+        let vault = tempfile::tempdir().unwrap();
+        fs::create_dir(vault.path().join(".obsidian")).unwrap();
+        assert!(!ignore_filters(vault.path()).is_ignored(&vault.path().join("a.md")));
+
+        fs::write(
+            vault.path().join(".obsidian").join("app.json"),
+            r#"{"alwaysUpdateLinks": true, "userIgnoreFilters": ["Archive/", "/\\.excalidraw\\.md$/", "Templates"]}"#,
+        )
+        .unwrap();
+        let filters = ignore_filters(vault.path());
+        assert!(filters.is_ignored(&vault.path().join("Archive/2024/old.md")));
+        assert!(filters.is_ignored(&vault.path().join("Templates/daily.md")));
+        assert!(filters.is_ignored(&vault.path().join("Journals/plan.excalidraw.md")));
+        assert!(!filters.is_ignored(&vault.path().join("Journals/Archive/2025-01-02.md")));
+        assert!(!filters.is_ignored(Path::new("/elsewhere/Archive/a.md")));
+    }
+}
@@ -0,0 +1,160 @@
+// src/verify.rs
+
+use crate::duration::{DurationUnit, TimeDuration, parse_duration};
+use crate::parser::TimeEntry;
+use csv::ReaderBuilder;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+
+/// A tag set whose total differs from the baseline's. A side is `None`
+/// when only the other one has the tag set.
+#[derive(Debug, PartialEq)]
+pub struct Discrepancy {
+    pub tags: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => {
+                write!(f, "{}: expected {}, found {}", self.tags, expected, actual)
+            }
+            (Some(expected), None) => write!(f, "{}: expected {}, found none", self.tags, expected),
+            (None, Some(actual)) => write!(f, "{}: not in baseline, found {}", self.tags, actual),
+            (None, None) => write!(f, "{}", self.tags),
+        }
+    }
+}
+
+/// Reads the totals per tag set from a baseline report. Either the
+/// headerless `--accumulate` CSV, with the duration in the second column,
+/// or a CSV headed by `tags` with a `total` or `duration` column, like a
+/// timesheet. A `total` row, as timesheets end with, is the overall total.
+pub fn read_baseline<R: Read>(reader: R) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut csv_reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let mut column = 1;
+    let mut rows = vec![];
+    for (index, record) in csv_reader.records().enumerate() {
+        let record = record?;
+        if index == 0 && record.get(0) == Some("tags") {
+            column = ["total", "duration"]
+                .iter()
+                .find_map(|name| record.iter().position(|field| field == *name))
+                .ok_or("baseline has no total or duration column")?;
+            continue;
+        }
+        let (Some(tags), Some(duration)) = (record.get(0), record.get(column)) else {
+            return Err(format!("baseline line {} has no duration", index + 1).into());
+        };
+        rows.push((tags.to_string(), duration.to_string()));
+    }
+    Ok(rows)
+}
+
+/// Compares the entries' totals per tag set, and overall for a `total`
+/// row, with the baseline's. Durations are compared as written in `unit`,
+/// so a baseline in decimal hours matches to its precision. Tag sets only
+/// the entries have are discrepancies too.
+pub fn compare(
+    baseline: &[(String, String)],
+    entries: &[TimeEntry],
+    unit: DurationUnit,
+) -> Vec<Discrepancy> {
+    let mut actual: BTreeMap<&str, TimeDuration> = BTreeMap::new();
+    let mut overall = TimeDuration::default();
+    for entry in entries {
+        *actual.entry(&entry.tags).or_default() += &entry.duration;
+        overall += &entry.duration;
+    }
+
+    let same = |expected: &str, actual: &TimeDuration| match unit {
+        DurationUnit::Compact => parse_duration(expected) == *actual,
+        _ => expected.trim().parse::<f64>().ok() == unit.format(actual).parse::<f64>().ok(),
+    };
+    let mut discrepancies = vec![];
+    for (tags, expected) in baseline {
+        let found = match tags.as_str() {
+            "total" => Some(overall.clone()),
+            tags => actual.remove(tags),
+        };
+        if !same(expected, &found.clone().unwrap_or_default()) {
+            discrepancies.push(Discrepancy {
+                tags: tags.clone(),
+                expected: Some(expected.clone()),
+                actual: found.map(|d| unit.format(&d)),
+            });
+        }
+    }
+    for (tags, found) in actual {
+        discrepancies.push(Discrepancy {
+            tags: tags.to_string(),
+            expected: None,
+            actual: Some(unit.format(&found)),
+        });
+    }
+    discrepancies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    #[test]
+    fn test_compare_timesheet_baseline() {
+        let baseline = dedent!(
+            r#"
+        tags,Mon 2025-01-06,Tue 2025-01-07,total
+        #dev,1h,1h,2h
+        #ops,30m,,30m
+        total,1h30m,1h,2h30m
+        "#
+        );
+        let baseline = read_baseline(baseline.as_bytes()).unwrap();
+        assert_eq!(("#ops".to_string(), "30m".to_string()), baseline[1]);
+
+        let entries = [
+            TimeEntry::test("#dev", "2h"),
+            TimeEntry::test("#ops", "15m"),
+            TimeEntry::test("#qa", "1h"),
+        ];
+        let discrepancies = compare(&baseline, &entries, DurationUnit::Compact);
+        assert_eq!(
+            vec![
+                "#ops: expected 30m, found 15m",
+                "total: expected 2h30m, found 3h15m",
+                "#qa: not in baseline, found 1h",
+            ],
+            discrepancies
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert!(compare(&baseline[..1], &entries[..1], DurationUnit::Compact).is_empty());
+    }
+
+    #[test]
+    fn test_compare_accumulate_baseline_in_hours() {
+        let baseline = read_baseline("#dev,1.50,3\n#ops,0.5,1\n".as_bytes()).unwrap();
+        let discrepancies = compare(
+            &baseline,
+            &[TimeEntry::test("#dev", "1h30m")],
+            DurationUnit::Hours,
+        );
+        assert_eq!(
+            vec![Discrepancy {
+                tags: "#ops".to_string(),
+                expected: Some("0.5".to_string()),
+                actual: None,
+            }],
+            discrepancies
+        );
+    }
+}
@@ -0,0 +1,419 @@
+// src/writeback.rs
+
+use crate::atomic::AtomicFile;
+use crate::json;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Unchanged lines shown around each change in a unified diff.
+const CONTEXT: usize = 3;
+/// The largest changed stretch, in pairs of old and new lines, that is
+/// diffed line by line. Beyond it the stretch is shown replaced whole,
+/// rather than tabulated at quadratic cost.
+const MAX_CELLS: usize = 4 << 20;
+
+/// A line rewritten by a write-back subcommand like `retag`. `line` is
+/// 1-based.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+impl Change {
+    /// The change as a JSON object, for `--json-diff`.
+    pub fn to_json(&self, file: &str) -> String {
+        format!(
+            "{{\"file\":{},\"line\":{},\"before\":{},\"after\":{}}}",
+            json::quote(file),
+            self.line,
+            json::quote(&self.before),
+            json::quote(&self.after)
+        )
+    }
+}
+
+/// A line of a diff, with its line ending.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op<'a> {
+    Keep(&'a str),
+    Remove(&'a str),
+    Add(&'a str),
+}
+
+/// The edits turning `old` into `new`, keeping their longest common
+/// subsequence of lines.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut ops: Vec<Op> = old[..prefix].iter().map(|line| Op::Keep(line)).collect();
+    if old_middle.len() * new_middle.len() > MAX_CELLS {
+        ops.extend(old_middle.iter().map(|line| Op::Remove(line)));
+        ops.extend(new_middle.iter().map(|line| Op::Add(line)));
+    } else {
+        // lcs[i][j] is the length of the longest common subsequence of
+        // old_middle[i..] and new_middle[j..].
+        let mut lcs = vec![vec![0u32; new_middle.len() + 1]; old_middle.len() + 1];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                lcs[i][j] = match old_middle[i] == new_middle[j] {
+                    true => lcs[i + 1][j + 1] + 1,
+                    false => lcs[i + 1][j].max(lcs[i][j + 1]),
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() && j < new_middle.len() {
+            if old_middle[i] == new_middle[j] {
+                ops.push(Op::Keep(old_middle[i]));
+                (i, j) = (i + 1, j + 1);
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                ops.push(Op::Remove(old_middle[i]));
+                i += 1;
+            } else {
+                ops.push(Op::Add(new_middle[j]));
+                j += 1;
+            }
+        }
+        ops.extend(old_middle[i..].iter().map(|line| Op::Remove(line)));
+        ops.extend(new_middle[j..].iter().map(|line| Op::Add(line)));
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|line| Op::Keep(line)));
+    ops
+}
+
+/// The change from `before` to `after` as a unified diff of `path`, with
+/// three lines of context, or an empty string when nothing changed.
+pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.split_inclusive('\n').collect();
+    let new: Vec<&str> = after.split_inclusive('\n').collect();
+    let ops = diff_lines(&old, &new);
+
+    // Each hunk is a range of ops: the changes, with their context, that
+    // are close enough for their context to touch.
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for (index, op) in ops.iter().enumerate() {
+        if matches!(op, Op::Keep(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + 1 + CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", path, path);
+    let (mut old_line, mut new_line, mut next) = (0, 0, 0);
+    for (start, end) in hunks {
+        for op in &ops[next..start] {
+            old_line += usize::from(!matches!(op, Op::Add(_)));
+            new_line += usize::from(!matches!(op, Op::Remove(_)));
+        }
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| !matches!(op, Op::Add(_))).count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, Op::Remove(_)))
+            .count();
+        // An empty side is numbered by the line before it.
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_line + usize::from(old_count > 0),
+            old_count,
+            new_line + usize::from(new_count > 0),
+            new_count
+        ));
+        for op in hunk {
+            let (marker, line) = match op {
+                Op::Keep(line) => (' ', line),
+                Op::Remove(line) => ('-', line),
+                Op::Add(line) => ('+', line),
+            };
+            diff.push(marker);
+            match line.strip_suffix('\n') {
+                Some(line) => diff.push_str(line),
+                None => diff.push_str(&format!("{}\n\\ No newline at end of file", line)),
+            }
+            diff.push('\n');
+        }
+        old_line += old_count;
+        new_line += new_count;
+        next = end;
+    }
+    diff
+}
+
+/// Where a note's original is kept before a write-back subcommand rewrites
+/// it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backup {
+    None,
+    /// Next to the note, as `FILE.bak`.
+    Beside,
+    /// Under the directory, at the note's path below the directory it was
+    /// found in.
+    Dir(PathBuf),
+}
+
+impl Backup {
+    /// Copies `note`, found at `relative` below the directory searched, to
+    /// its backup, returning where that is. Backups from an earlier run are
+    /// replaced.
+    pub fn save(&self, note: &Path, relative: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let backup = match self {
+            Backup::None => return Ok(None),
+            Backup::Beside => {
+                let mut backup = note.to_path_buf().into_os_string();
+                backup.push(".bak");
+                PathBuf::from(backup)
+            }
+            // Only the relative path's names are kept, so a note given by
+            // an absolute path or with `..` is still kept within `dir`.
+            Backup::Dir(dir) => dir.join(
+                relative
+                    .components()
+                    .filter(|component| matches!(component, Component::Normal(_)))
+                    .collect::<PathBuf>(),
+            ),
+        };
+        if let Some(parent) = backup.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(note, &backup).map_err(|e| {
+            format!(
+                "failed to back up {} to {}: {}",
+                note.display(),
+                backup.display(),
+                e
+            )
+        })?;
+        Ok(Some(backup))
+    }
+}
+
+/// How a write-back subcommand shows its changes: listed its own way, as
+/// unified diffs, or as JSON records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Show {
+    List,
+    Diff,
+    JsonDiff,
+}
+
+/// Shows and applies the notes a write-back subcommand rewrites, counting
+/// the lines and files changed.
+pub struct Writeback {
+    /// Whether the notes are rewritten, or the changes only shown.
+    pub write: bool,
+    pub backup: Backup,
+    pub show: Show,
+    records: Vec<String>,
+    lines: usize,
+    files: usize,
+}
+
+impl Writeback {
+    pub fn new(write: bool, backup: Backup, show: Show) -> Self {
+        Writeback {
+            write,
+            backup,
+            show,
+            records: vec![],
+            lines: 0,
+            files: 0,
+        }
+    }
+
+    /// Shows the rewrite of `note`, shown as `path` and found at `relative`
+    /// below the directory searched, from `before` to `after`, then writes
+    /// it if asked to, backing up the original first. The note is replaced
+    /// whole, so a failed write leaves it as it was.
+    pub fn apply(
+        &mut self,
+        note: &Path,
+        relative: &Path,
+        path: &str,
+        (before, after): (&str, &str),
+        changes: &[Change],
+        writer: &mut dyn Write,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.show {
+            Show::List => {}
+            Show::Diff => write!(writer, "{}", unified_diff(path, before, after))?,
+            Show::JsonDiff => self
+                .records
+                .extend(changes.iter().map(|change| change.to_json(path))),
+        }
+        if self.write {
+            if !note.is_file() {
+                return Err(format!("cannot rewrite {}: not a file", note.display()).into());
+            }
+            if let Some(backup) = self.backup.save(note, relative)? {
+                log::debug!("backed up {} to {}", note.display(), backup.display());
+            }
+            // A linked note is rewritten where it lives, keeping the link.
+            let target = fs::canonicalize(note)
+                .map_err(|e| format!("cannot rewrite {}: {}", note.display(), e))?;
+            let mut file = AtomicFile::create(&target)?;
+            file.write_all(after.as_bytes())?;
+            file.commit()?;
+        }
+        self.lines += changes.len();
+        self.files += 1;
+        Ok(())
+    }
+
+    /// Ends the run with the JSON records, if shown, and a summary like
+    /// `changed 3 lines in 2 files`, using `done` or `would` for the verb
+    /// as the notes were written or not. Diffs are left as they are, for
+    /// tools to apply or read, with the summary logged instead.
+    pub fn finish(
+        self,
+        done: &str,
+        would: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), Box<dyn Error>> {
+        let summary = format!(
+            "{} {} lines in {} files",
+            if self.write { done } else { would },
+            self.lines,
+            self.files
+        );
+        if self.show == Show::JsonDiff {
+            writeln!(writer, "[{}]", self.records.join(","))?;
+        }
+        match self.show {
+            Show::List => writeln!(writer, "{}", summary)?,
+            Show::Diff | Show::JsonDiff => log::info!("{}", summary),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dedent::dedent;
+
+    #[test]
+    fn test_unified_diff() {
+        let before: String = (1..=12).map(|n| format!("line {}\n", n)).collect();
+        let after = before
+            .replace("line 2\n", "line two\n")
+            .replace("line 4\n", "")
+            .replace("line 12\n", "line 12\nline 13");
+        assert_eq!(
+            dedent!(
+                r#"
+            --- note.md
+            +++ note.md
+            @@ -1,7 +1,6 @@
+             line 1
+            -line 2
+            +line two
+             line 3
+            -line 4
+             line 5
+             line 6
+             line 7
+            @@ -10,3 +9,4 @@
+             line 10
+             line 11
+             line 12
+            +line 13
+            \ No newline at end of file
+            "#
+            )
+            .to_string()
+                + "\n",
+            unified_diff("note.md", &before, &after)
+        );
+        assert_eq!("", unified_diff("note.md", &before, &before));
+        assert_eq!(
+            "--- new.md\n+++ new.md\n@@ -0,0 +1,1 @@\n+first\n",
+            unified_diff("new.md", "", "first\n")
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("note.md");
+        fs::write(&note, "before").unwrap();
+        let backups = dir.path().join("backups");
+        let backup = Backup::Dir(backups.clone())
+            .save(&note, Path::new("../2025/note.md"))
+            .unwrap();
+        assert_eq!(Some(backups.join("2025").join("note.md")), backup);
+        assert_eq!("before", fs::read_to_string(backup.unwrap()).unwrap());
+        assert_eq!(
+            Some(dir.path().join("note.md.bak")),
+            Backup::Beside.save(&note, Path::new("note.md")).unwrap()
+        );
+        assert_eq!(
+            None,
+            Backup::None.save(&note, Path::new("note.md")).unwrap()
+        );
+
+        let changes = [Change {
+            line: 1,
+            before: "before".to_string(),
+            after: "after".to_string(),
+        }];
+        let mut out = vec![];
+        let mut writeback = Writeback::new(true, Backup::None, Show::JsonDiff);
+        writeback
+            .apply(
+                &note,
+                Path::new("note.md"),
+                "note.md",
+                ("before", "after"),
+                &changes,
+                &mut out,
+            )
+            .unwrap();
+        writeback
+            .finish("changed", "would change", &mut out)
+            .unwrap();
+        assert_eq!("after", fs::read_to_string(&note).unwrap());
+        assert_eq!(
+            "[{\"file\":\"note.md\",\"line\":1,\"before\":\"before\",\"after\":\"after\"}]\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        #[cfg(unix)]
+        {
+            let shared = dir.path().join("shared.md");
+            fs::write(&shared, "before").unwrap();
+            let link = dir.path().join("linked.md");
+            std::os::unix::fs::symlink("shared.md", &link).unwrap();
+            Writeback::new(true, Backup::None, Show::List)
+                .apply(
+                    &link,
+                    Path::new("linked.md"),
+                    "linked.md",
+                    ("before", "after"),
+                    &changes,
+                    &mut vec![],
+                )
+                .unwrap();
+            assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+            assert_eq!("after", fs::read_to_string(&shared).unwrap());
+        }
+    }
+}